@@ -0,0 +1,15 @@
+-- Your SQL goes here
+CREATE TABLE embedding_dead_letters (
+  id UUID NOT NULL PRIMARY KEY,
+  dataset_id UUID NOT NULL,
+  chunk_id UUID NOT NULL,
+  tracking_id TEXT,
+  attempts INT NOT NULL,
+  error_message TEXT NOT NULL,
+  ingestion_message JSONB NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+
+  CONSTRAINT embedding_dead_letters_dataset_id_fkey FOREIGN KEY (dataset_id) REFERENCES datasets (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX embedding_dead_letters_dataset_id_created_at_idx ON embedding_dead_letters (dataset_id, created_at);
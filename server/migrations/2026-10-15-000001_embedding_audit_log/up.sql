@@ -0,0 +1,16 @@
+-- Your SQL goes here
+CREATE TABLE embedding_audit_log (
+  id UUID NOT NULL PRIMARY KEY,
+  dataset_id UUID NOT NULL,
+  operation TEXT NOT NULL,
+  origin TEXT NOT NULL,
+  item_count INT NOT NULL,
+  total_chars BIGINT NOT NULL,
+  content_digest TEXT NOT NULL,
+  outcome TEXT NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+
+  CONSTRAINT embedding_audit_log_dataset_id_fkey FOREIGN KEY (dataset_id) REFERENCES datasets (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX embedding_audit_log_dataset_id_created_at_idx ON embedding_audit_log (dataset_id, created_at);
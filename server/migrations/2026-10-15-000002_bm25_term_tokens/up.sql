@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE bm25_term_tokens (
+  dataset_id UUID NOT NULL,
+  term_id BIGINT NOT NULL,
+  token TEXT NOT NULL,
+  last_seen_at TIMESTAMP NOT NULL DEFAULT NOW(),
+
+  PRIMARY KEY (dataset_id, term_id, token),
+  CONSTRAINT bm25_term_tokens_dataset_id_fkey FOREIGN KEY (dataset_id) REFERENCES datasets (id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX bm25_term_tokens_dataset_id_last_seen_at_idx ON bm25_term_tokens (dataset_id, last_seen_at);
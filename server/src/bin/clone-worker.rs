@@ -0,0 +1,206 @@
+use diesel_async::pooled_connection::{AsyncDieselConnectionManager, ManagerConfig};
+use signal_hook::consts::SIGTERM;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use trieve_server::{
+    data::models::{self, DatasetCloneStatus},
+    establish_connection, get_env,
+    operators::dataset_operator::{
+        clone_dataset_chunks_query, get_clone_job_stale_after, get_dataset_clone_job_query,
+        requeue_stale_dataset_clone_jobs_query, set_dataset_clone_job_query,
+        DATASET_CLONE_PROCESSING, DATASET_CLONE_QUEUE,
+    },
+};
+
+fn main() {
+    dotenvy::dotenv().ok();
+    env_logger::builder()
+        .target(env_logger::Target::Stdout)
+        .filter_level(log::LevelFilter::Info)
+        .init();
+
+    let database_url = get_env!("DATABASE_URL", "DATABASE_URL is not set");
+
+    let mut config = ManagerConfig::default();
+    config.custom_setup = Box::new(establish_connection);
+
+    let mgr = AsyncDieselConnectionManager::<diesel_async::AsyncPgConnection>::new_with_config(
+        database_url,
+        config,
+    );
+
+    let pool = diesel_async::pooled_connection::deadpool::Pool::builder(mgr)
+        .max_size(3)
+        .build()
+        .expect("Failed to create diesel_async pool");
+
+    let web_pool = actix_web::web::Data::new(pool.clone());
+
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to create tokio runtime")
+        .block_on(async move {
+            let redis_url = get_env!("REDIS_URL", "REDIS_URL is not set");
+            let redis_connections: u32 = std::env::var("REDIS_CONNECTIONS")
+                .unwrap_or("2".to_string())
+                .parse()
+                .unwrap_or(2);
+
+            let redis_manager = bb8_redis::RedisConnectionManager::new(redis_url)
+                .expect("Failed to connect to redis");
+
+            let redis_pool = bb8_redis::bb8::Pool::builder()
+                .max_size(redis_connections)
+                .connection_timeout(std::time::Duration::from_secs(2))
+                .build(redis_manager)
+                .await
+                .expect("Failed to create redis pool");
+
+            let web_redis_pool = actix_web::web::Data::new(redis_pool);
+
+            let should_terminate = Arc::new(AtomicBool::new(false));
+            signal_hook::flag::register(SIGTERM, Arc::clone(&should_terminate))
+                .expect("Failed to register shutdown hook");
+            clone_worker(should_terminate, web_redis_pool, web_pool).await;
+        });
+}
+
+async fn clone_worker(
+    should_terminate: Arc<AtomicBool>,
+    redis_pool: actix_web::web::Data<models::RedisPool>,
+    web_pool: actix_web::web::Data<models::Pool>,
+) {
+    log::info!("Starting clone worker service thread");
+    let mut redis_conn_sleep = std::time::Duration::from_secs(1);
+    #[allow(unused_assignments)]
+    let mut opt_redis_connection = None;
+
+    loop {
+        let borrowed_redis_connection = match redis_pool.get().await {
+            Ok(redis_connection) => Some(redis_connection),
+            Err(err) => {
+                log::error!("Failed to get redis connection outside of loop: {:?}", err);
+                None
+            }
+        };
+
+        if borrowed_redis_connection.is_some() {
+            opt_redis_connection = borrowed_redis_connection;
+            break;
+        }
+
+        tokio::time::sleep(redis_conn_sleep).await;
+        redis_conn_sleep = std::cmp::min(redis_conn_sleep * 2, std::time::Duration::from_secs(300));
+    }
+    let mut redis_connection =
+        opt_redis_connection.expect("Failed to get redis connection outside of loop");
+    let mut broken_pipe_sleep = std::time::Duration::from_secs(10);
+    // Jobs of workers which stopped mid job are picked up at startup and then once per stale
+    // window, in case another worker stops while this one runs
+    let stale_after = get_clone_job_stale_after();
+    let mut next_requeue = std::time::Instant::now();
+
+    loop {
+        if should_terminate.load(Ordering::Relaxed) {
+            log::info!("Shutting down");
+            break;
+        }
+
+        if std::time::Instant::now() >= next_requeue {
+            match requeue_stale_dataset_clone_jobs_query(stale_after, redis_pool.clone()).await {
+                Ok(0) => {}
+                Ok(requeued_jobs) => log::info!("Requeued {} stale clone jobs", requeued_jobs),
+                Err(err) => log::error!("Failed to requeue stale clone jobs: {:?}", err),
+            }
+            next_requeue = std::time::Instant::now()
+                + stale_after
+                    .to_std()
+                    .unwrap_or(std::time::Duration::from_secs(300));
+        }
+
+        let payload_result: Result<Vec<String>, redis::RedisError> = redis::cmd("brpoplpush")
+            .arg(DATASET_CLONE_QUEUE)
+            .arg(DATASET_CLONE_PROCESSING)
+            .arg(1.0)
+            .query_async(&mut redis_connection.clone())
+            .await;
+
+        let serialized_message = if let Ok(payload) = payload_result {
+            broken_pipe_sleep = std::time::Duration::from_secs(10);
+            if payload.is_empty() {
+                continue;
+            }
+            payload
+                .first()
+                .expect("Payload must have a first element")
+                .clone()
+        } else {
+            log::error!("Unable to process {:?}", payload_result);
+            if payload_result.is_err_and(|err| err.is_io_error()) {
+                tokio::time::sleep(broken_pipe_sleep).await;
+                broken_pipe_sleep =
+                    std::cmp::min(broken_pipe_sleep * 2, std::time::Duration::from_secs(300));
+            }
+            continue;
+        };
+
+        let job = match uuid::Uuid::parse_str(&serialized_message) {
+            Ok(job_id) => get_dataset_clone_job_query(job_id, redis_pool.clone()).await,
+            Err(err) => {
+                log::error!("Failed to parse clone job id: {:?}", err);
+                continue;
+            }
+        };
+
+        match job {
+            Ok(job)
+                if matches!(
+                    job.status,
+                    DatasetCloneStatus::Completed | DatasetCloneStatus::Failed
+                ) =>
+            {
+                log::info!("Clone job {} already finished", job.id);
+            }
+            Ok(mut job) => {
+                log::info!(
+                    "Cloning dataset {} into {} from offset {:?}",
+                    job.source_dataset_id,
+                    job.target_dataset_id,
+                    job.next_offset
+                );
+                job.status = DatasetCloneStatus::Running;
+                job.updated_at = chrono::Utc::now().naive_local();
+                let _ = set_dataset_clone_job_query(&job, redis_pool.clone()).await;
+
+                match clone_dataset_chunks_query(&mut job, web_pool.clone(), redis_pool.clone())
+                    .await
+                {
+                    Ok(()) => {
+                        log::info!("Cloned {} chunks for job {}", job.cloned_chunks, job.id);
+                        job.status = DatasetCloneStatus::Completed;
+                    }
+                    Err(err) => {
+                        log::error!("Failed to clone dataset for job {}: {:?}", job.id, err);
+                        job.status = DatasetCloneStatus::Failed;
+                        job.error = Some(err.to_string());
+                    }
+                }
+                job.updated_at = chrono::Utc::now().naive_local();
+                let _ = set_dataset_clone_job_query(&job, redis_pool.clone()).await;
+            }
+            Err(err) => {
+                log::error!("Failed to get clone job {}: {:?}", serialized_message, err);
+            }
+        }
+
+        let _ = redis::cmd("LREM")
+            .arg(DATASET_CLONE_PROCESSING)
+            .arg(1)
+            .arg(serialized_message)
+            .query_async::<redis::aio::MultiplexedConnection, usize>(&mut *redis_connection)
+            .await;
+    }
+}
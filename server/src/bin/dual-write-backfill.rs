@@ -0,0 +1,69 @@
+use diesel_async::pooled_connection::{AsyncDieselConnectionManager, ManagerConfig};
+use trieve_server::{
+    data::models::DatasetConfiguration,
+    errors::ServiceError,
+    establish_connection, get_env,
+    operators::{
+        dataset_operator::get_dataset_by_id_query, qdrant_operator::backfill_dual_write_vectors,
+    },
+};
+
+#[tokio::main]
+async fn main() -> Result<(), ServiceError> {
+    dotenvy::dotenv().ok();
+    env_logger::builder()
+        .target(env_logger::Target::Stdout)
+        .filter_level(log::LevelFilter::Info)
+        .init();
+
+    let dataset_id: uuid::Uuid = std::env::var("DATASET_ID")
+        .map_err(|_| ServiceError::BadRequest("DATASET_ID is not set".to_string()))?
+        .parse()
+        .map_err(|_| ServiceError::BadRequest("DATASET_ID must be a uuid".to_string()))?;
+
+    let database_url = get_env!("DATABASE_URL", "DATABASE_URL is not set");
+
+    let mut config = ManagerConfig::default();
+    config.custom_setup = Box::new(establish_connection);
+
+    let mgr = AsyncDieselConnectionManager::<diesel_async::AsyncPgConnection>::new_with_config(
+        database_url,
+        config,
+    );
+
+    let pool = diesel_async::pooled_connection::deadpool::Pool::builder(mgr)
+        .max_size(1)
+        .build()
+        .expect("Failed to create diesel_async pool");
+
+    let pool = actix_web::web::Data::new(pool.clone());
+
+    let dataset = get_dataset_by_id_query(dataset_id, pool).await?;
+    let dataset_config = DatasetConfiguration::from_json(dataset.server_configuration);
+
+    let target =
+        dataset_config
+            .EMBEDDING_DUAL_WRITE_TARGET
+            .clone()
+            .ok_or(ServiceError::BadRequest(
+                "The dataset has no EMBEDDING_DUAL_WRITE_TARGET to backfill vectors for"
+                    .to_string(),
+            ))?;
+
+    log::info!(
+        "Backfilling {} vectors for dataset {}",
+        target.model_name,
+        dataset_id
+    );
+    let written = backfill_dual_write_vectors(dataset_id, &dataset_config, |written| {
+        log::info!("Wrote {} dual-write vectors so far", written);
+    })
+    .await?;
+    log::info!(
+        "Finished backfilling {} dual-write vectors for dataset {}, it can be cut over",
+        written,
+        dataset_id
+    );
+
+    Ok(())
+}
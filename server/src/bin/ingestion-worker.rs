@@ -4,12 +4,14 @@ use diesel_async::pooled_connection::{AsyncDieselConnectionManager, ManagerConfi
 use futures_util::StreamExt;
 use itertools::{izip, Itertools};
 use qdrant_client::qdrant::{PointStruct, Vector};
+use qdrant_client::Payload;
 use signal_hook::consts::SIGTERM;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{atomic::AtomicBool, atomic::Ordering, Arc};
 use trieve_server::data::models::{
-    self, ChunkBoost, ChunkData, ChunkGroup, ChunkMetadata, DatasetConfiguration, QdrantPayload,
-    WorkerEvent,
+    self, ChunkBoost, ChunkData, ChunkGroup, ChunkMetadata, DatasetConfiguration,
+    EmptyContentPolicy, PreprocessingEvent, PreprocessingEventKind, QdrantPayload,
+    SparseVectorStats, SparseVectorStrategy, WorkerEvent,
 };
 use trieve_server::errors::ServiceError;
 use trieve_server::handlers::chunk_handler::{
@@ -17,6 +19,9 @@ use trieve_server::handlers::chunk_handler::{
     UploadIngestionMessage,
 };
 use trieve_server::handlers::group_handler::dataset_owns_group;
+use trieve_server::handlers::metrics_handler::{
+    get_ingestion_worker_registry, serve_worker_metrics,
+};
 use trieve_server::operators::chunk_operator::{
     bulk_insert_chunk_metadata_query, bulk_revert_insert_chunk_metadata_query,
     get_row_count_for_organization_id_query, insert_chunk_boost, insert_chunk_metadata_query,
@@ -26,18 +31,36 @@ use trieve_server::operators::clickhouse_operator::{ClickHouseEvent, EventQueue}
 use trieve_server::operators::dataset_operator::{
     get_dataset_and_organization_from_dataset_id_query, get_dataset_by_id_query,
 };
+use trieve_server::operators::dead_letter_operator::{
+    get_dead_letters_from_payload, get_embedding_dead_letter_attempts, get_requeue_delay,
+    insert_embedding_dead_letters_query, plan_ingestion_retry, wait_for_redelivery_slot,
+    IngestionRetry, RequeuePolicy,
+};
+use trieve_server::operators::embedding_audit_operator::with_embedding_audit_log;
 use trieve_server::operators::group_operator::{
     create_groups_query, get_group_ids_from_tracking_ids_query, get_groups_from_group_ids_query,
 };
 use trieve_server::operators::model_operator::{
-    get_bm25_embeddings, get_dense_vector, get_dense_vectors, get_sparse_vectors,
+    check_created_vector_count, check_model_endpoints, check_vector_norms, clip_boost_phrases,
+    ensure_vector_norms, filter_boost_for_embed_type, get_bm25_embeddings_async, get_dense_vector,
+    get_dense_vectors, get_dense_vectors_with_phrases, get_distance_phrase_vector,
+    get_dual_write_vectors, get_fulltext_embedding_content, get_preprocessing_events,
+    get_sparse_vectors, get_templated_embedding_content, get_vector_field_vectors,
+    record_sparse_vector_stats, resolve_empty_content, validate_model_env,
+    with_embedding_rate_limit_dataset, DenseVectorWithPhrase, EmbedContext, EmbeddingTokenCounts,
+    EmptyContentResolution,
 };
 use trieve_server::operators::parse_operator::{
     average_embeddings, coarse_doc_chunker, convert_html_to_text,
 };
 use trieve_server::operators::qdrant_operator::{
-    bulk_upsert_qdrant_points_query, update_qdrant_point_query,
+    bulk_upsert_qdrant_points_query, get_chunk_content_hash, get_distance_phrase_vector_name,
+    get_vector_field_vector_name, insert_dense_vectors, should_defer_sparse_encoding,
+    update_distance_phrase_vector_query, update_qdrant_point_query,
+    update_vector_field_vectors_query, ChunkVectorUpdate, CONTENT_HASH_PAYLOAD_KEY,
+    DISTANCE_FACTOR_PAYLOAD_KEY, SPARSE_ENCODED_PAYLOAD_KEY,
 };
+use trieve_server::operators::term_token_operator::record_bm25_term_tokens;
 use trieve_server::{establish_connection, get_env};
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -54,6 +77,11 @@ fn main() {
         .filter_level(log::LevelFilter::Info)
         .init();
 
+    if let Err(report) = validate_model_env() {
+        log::error!("{}", report);
+        std::process::exit(1);
+    }
+
     let database_url = get_env!("DATABASE_URL", "DATABASE_URL is not set");
 
     let mut config = ManagerConfig::default();
@@ -71,11 +99,21 @@ fn main() {
 
     let web_pool = actix_web::web::Data::new(pool.clone());
 
+    match get_ingestion_worker_registry() {
+        Ok(registry) => serve_worker_metrics(registry),
+        Err(err) => log::error!("Failed to create worker metrics {:?}", err),
+    }
+
     tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .build()
         .expect("Failed to create tokio runtime")
         .block_on(async move {
+            if let Err(report) = check_model_endpoints().await {
+                log::error!("{}", report);
+                std::process::exit(1);
+            }
+
             let redis_url = get_env!("REDIS_URL", "REDIS_URL is not set");
             let redis_connections: u32 = std::env::var("REDIS_CONNECTIONS")
                 .unwrap_or("2".to_string())
@@ -235,6 +273,7 @@ async fn ingestion_worker(
                     ingestion_message,
                     err.clone(),
                     redis_pool.clone(),
+                    web_pool.clone(),
                     event_queue.clone(),
                 )
                 .await;
@@ -251,22 +290,78 @@ async fn ingestion_worker(
                     payload.ingestion_messages.len(),
                     payload.dataset_id
                 );
-                match bulk_upload_chunks(
-                    payload.clone(),
-                    dataset_config.clone(),
+                match with_embedding_audit_log(
+                    payload.dataset_id,
+                    &dataset_config,
                     web_pool.clone(),
-                    reqwest_client.clone(),
+                    with_embedding_rate_limit_dataset(
+                        payload.dataset_id,
+                        bulk_upload_chunks(
+                            payload.clone(),
+                            dataset_config.clone(),
+                            web_pool.clone(),
+                            reqwest_client.clone(),
+                        ),
+                    ),
                 )
                 .await
                 {
-                    Ok(chunk_ids) => {
+                    Ok((chunk_ids, preprocessing_events, rejected_chunks, sparse_vector_stats)) => {
                         log::info!("Uploaded {:} chunks", chunk_ids.len());
 
+                        for (chunk_id, error) in rejected_chunks {
+                            event_queue
+                                .send(ClickHouseEvent::WorkerEvent(
+                                    WorkerEvent::from_details(
+                                        payload.dataset_id,
+                                        models::EventType::BulkChunkUploadFailed {
+                                            chunk_ids: vec![chunk_id],
+                                            error,
+                                        },
+                                    )
+                                    .into(),
+                                ))
+                                .await;
+                        }
+
+                        let truncated_chunks = preprocessing_events
+                            .iter()
+                            .filter(|event| event.kind == PreprocessingEventKind::Truncated)
+                            .map(|event| event.chunk_id)
+                            .unique()
+                            .count();
+                        let emptied_chunks = preprocessing_events
+                            .iter()
+                            .filter(|event| event.kind == PreprocessingEventKind::Emptied)
+                            .count();
+
+                        let sparse_vector_stats = (std::env::var("INGESTION_REPORT_VERBOSE")
+                            .unwrap_or("false".to_string())
+                            == "true")
+                            .then_some(sparse_vector_stats);
+
+                        for preprocessing_event in preprocessing_events {
+                            event_queue
+                                .send(ClickHouseEvent::WorkerEvent(
+                                    WorkerEvent::from_details(
+                                        payload.dataset_id,
+                                        preprocessing_event.into(),
+                                    )
+                                    .into(),
+                                ))
+                                .await;
+                        }
+
                         event_queue
                             .send(ClickHouseEvent::WorkerEvent(
                                 WorkerEvent::from_details(
                                     payload.dataset_id,
-                                    models::EventType::ChunksUploaded { chunk_ids },
+                                    models::EventType::ChunksUploaded {
+                                        chunk_ids,
+                                        truncated_chunks,
+                                        emptied_chunks,
+                                        sparse_vector_stats,
+                                    },
                                 )
                                 .into(),
                             ))
@@ -288,6 +383,7 @@ async fn ingestion_worker(
                             ingestion_message,
                             err,
                             redis_pool.clone(),
+                            web_pool.clone(),
                             event_queue.clone(),
                         )
                         .await;
@@ -296,7 +392,17 @@ async fn ingestion_worker(
             }
 
             IngestionMessage::Update(payload) => {
-                match update_chunk(payload.clone(), web_pool.clone(), dataset_config).await {
+                match with_embedding_audit_log(
+                    payload.dataset_id,
+                    &dataset_config,
+                    web_pool.clone(),
+                    with_embedding_rate_limit_dataset(
+                        payload.dataset_id,
+                        update_chunk(payload.clone(), web_pool.clone(), dataset_config.clone()),
+                    ),
+                )
+                .await
+                {
                     Ok(_) => {
                         log::info!("Updated chunk: {:?}", payload.chunk_metadata.id);
                         event_queue
@@ -325,6 +431,7 @@ async fn ingestion_worker(
                             ingestion_message,
                             err,
                             redis_pool.clone(),
+                            web_pool.clone(),
                             event_queue.clone(),
                         )
                         .await;
@@ -335,12 +442,21 @@ async fn ingestion_worker(
     }
 }
 
+#[allow(clippy::type_complexity)]
 pub async fn bulk_upload_chunks(
-    payload: BulkUploadIngestionMessage,
+    mut payload: BulkUploadIngestionMessage,
     dataset_config: DatasetConfiguration,
     web_pool: actix_web::web::Data<models::Pool>,
     reqwest_client: reqwest::Client,
-) -> Result<Vec<uuid::Uuid>, ServiceError> {
+) -> Result<
+    (
+        Vec<uuid::Uuid>,
+        Vec<PreprocessingEvent>,
+        Vec<(uuid::Uuid, String)>,
+        Vec<SparseVectorStats>,
+    ),
+    ServiceError,
+> {
     let unlimited = std::env::var("UNLIMITED").unwrap_or("false".to_string());
     if unlimited == "false" && !dataset_config.QDRANT_ONLY {
         log::info!("Getting dataset, organization, and its plan+subscription information for dataset_id: {:?}", payload.dataset_id);
@@ -448,6 +564,21 @@ pub async fn bulk_upload_chunks(
         get_groups_from_group_ids_query(all_group_ids, web_pool.clone()).await?
     };
 
+    let boost_phrase_events = payload
+        .ingestion_messages
+        .iter_mut()
+        .flat_map(|message| {
+            clip_boost_phrases(
+                message.ingest_specific_chunk_metadata.id,
+                message.chunk.tracking_id.clone(),
+                &mut message.chunk.fulltext_boost,
+                &mut message.chunk.semantic_boost,
+                &dataset_config,
+            )
+        })
+        .collect::<Vec<PreprocessingEvent>>();
+
+    let mut empty_content_actions: HashMap<uuid::Uuid, EmptyContentPolicy> = HashMap::new();
     let ingestion_data: Vec<ChunkData> = payload
         .ingestion_messages
         .iter()
@@ -457,6 +588,24 @@ pub async fn bulk_upload_chunks(
             } else {
                 message.chunk.chunk_html.clone().unwrap_or_default()
             };
+            let content = match resolve_empty_content(
+                &content,
+                &message.chunk.chunk_html.clone().unwrap_or_default(),
+                message.chunk.metadata.as_ref(),
+                &dataset_config,
+            ) {
+                Some(resolution) => {
+                    empty_content_actions.insert(
+                        message.ingest_specific_chunk_metadata.id,
+                        resolution.action(),
+                    );
+                    match resolution {
+                        EmptyContentResolution::Fallback(fallback_text) => fallback_text,
+                        _ => content,
+                    }
+                }
+                None => content,
+            };
 
             let qdrant_point_id = message.ingest_specific_chunk_metadata.qdrant_point_id;
 
@@ -527,8 +676,20 @@ pub async fn bulk_upload_chunks(
 
             ChunkData {
                 chunk_metadata,
-                content: content.clone(),
-                embedding_content: message.chunk.semantic_content.clone().unwrap_or(content),
+                content: get_fulltext_embedding_content(
+                    content.clone(),
+                    message.chunk.metadata.as_ref(),
+                    &dataset_config,
+                ),
+                embedding_content: message.chunk.semantic_content.clone().unwrap_or(
+                    get_templated_embedding_content(
+                        content,
+                        message.chunk.link.as_ref(),
+                        message.chunk.tag_set.clone(),
+                        message.chunk.metadata.as_ref(),
+                        &dataset_config,
+                    ),
+                ),
                 group_ids: Some(deduped_group_ids),
                 upsert_by_tracking_id: message.upsert_by_tracking_id,
                 fulltext_boost: message
@@ -543,7 +704,49 @@ pub async fn bulk_upload_chunks(
                     .filter(|boost| !boost.phrase.is_empty()),
             }
         })
-        .filter(|data| !data.content.is_empty())
+        .collect();
+
+    let preprocessing_events = ingestion_data
+        .iter()
+        .flat_map(|data| {
+            get_preprocessing_events(
+                data.chunk_metadata.id,
+                data.chunk_metadata.tracking_id.clone(),
+                &data.chunk_metadata.chunk_html.clone().unwrap_or_default(),
+                &data.content,
+                &data.embedding_content,
+                empty_content_actions.get(&data.chunk_metadata.id).copied(),
+                &dataset_config,
+            )
+        })
+        .chain(boost_phrase_events)
+        .collect::<Vec<PreprocessingEvent>>();
+
+    let is_rejected_empty = |chunk_id: &uuid::Uuid| {
+        empty_content_actions.get(chunk_id) == Some(&EmptyContentPolicy::Reject)
+    };
+    let rejected_empty_chunks: Vec<(uuid::Uuid, String)> = ingestion_data
+        .iter()
+        .filter(|data| is_rejected_empty(&data.chunk_metadata.id))
+        .map(|data| {
+            (
+                data.chunk_metadata.id,
+                "Chunk has no text left after removing its html and EMPTY_CONTENT_POLICY is reject"
+                    .to_string(),
+            )
+        })
+        .collect();
+    // These are stored for filtering and full payload retrieval but get no dense, sparse or bm25
+    // vectors of their own
+    let metadata_only_chunks: HashSet<uuid::Uuid> = empty_content_actions
+        .iter()
+        .filter(|(_, action)| **action == EmptyContentPolicy::MetadataOnly)
+        .map(|(chunk_id, _)| *chunk_id)
+        .collect();
+
+    let ingestion_data: Vec<ChunkData> = ingestion_data
+        .into_iter()
+        .filter(|data| !is_rejected_empty(&data.chunk_metadata.id))
         .collect();
 
     if split_average_being_used {
@@ -553,7 +756,11 @@ pub async fn bulk_upload_chunks(
         );
 
         let mut chunk_ids = vec![];
-        for (message, ingestion_data) in izip!(payload.ingestion_messages, ingestion_data) {
+        let ingestion_messages = payload
+            .ingestion_messages
+            .into_iter()
+            .filter(|message| !is_rejected_empty(&message.ingest_specific_chunk_metadata.id));
+        for (message, ingestion_data) in izip!(ingestion_messages, ingestion_data) {
             let upload_chunk_result = upload_chunk(
                 message,
                 dataset_config.clone(),
@@ -568,7 +775,12 @@ pub async fn bulk_upload_chunks(
             }
         }
 
-        return Ok(chunk_ids);
+        return Ok((
+            chunk_ids,
+            preprocessing_events,
+            rejected_empty_chunks,
+            vec![],
+        ));
     }
 
     let qdrant_only = dataset_config.QDRANT_ONLY;
@@ -588,7 +800,7 @@ pub async fn bulk_upload_chunks(
 
     if inserted_chunk_metadatas.is_empty() {
         // All collisions
-        return Ok(vec![]);
+        return Ok((vec![], preprocessing_events, rejected_empty_chunks, vec![]));
     }
 
     // Only embed the things we get returned from here, this reduces the number of times we embed data that are just duplicates
@@ -610,25 +822,204 @@ pub async fn bulk_upload_chunks(
         .unique()
         .collect();
 
+    // Vectors created inline by the chunk handler when return_embeddings is set, these must be the
+    // ones stored since they were already returned to the caller
+    let precomputed_dense_vectors: HashMap<uuid::Uuid, Vec<f32>> = payload
+        .ingestion_messages
+        .iter()
+        .filter_map(|message| {
+            message
+                .dense_vector
+                .clone()
+                .map(|vector| (message.ingest_specific_chunk_metadata.id, vector))
+        })
+        .collect();
+    let precomputed_sparse_vectors: HashMap<uuid::Uuid, Vec<(u32, f32)>> = payload
+        .ingestion_messages
+        .iter()
+        .filter_map(|message| {
+            message
+                .sparse_vector
+                .clone()
+                .map(|vector| (message.ingest_specific_chunk_metadata.id, vector))
+        })
+        .collect();
+
+    let context = EmbedContext::from_dataset_config(&dataset_config);
     let embedding_vectors = match dataset_config.SEMANTIC_ENABLED {
         true => {
+            let content_and_distances_to_embed: Vec<(String, Option<SemanticBoost>)> =
+                izip!(ingestion_data.iter(), embedding_content_and_boosts.iter())
+                    .filter(|(data, _)| {
+                        !precomputed_dense_vectors.contains_key(&data.chunk_metadata.id)
+                            && !metadata_only_chunks.contains(&data.chunk_metadata.id)
+                    })
+                    .map(|(_, (content, _, semantic_boost))| {
+                        (content.clone(), semantic_boost.clone())
+                    })
+                    .collect();
+
             log::info!(
-                "Creating embeddings for {} chunks",
-                embedding_content_and_boosts.len()
+                "Creating embeddings for {} chunks, {} were precomputed",
+                content_and_distances_to_embed.len(),
+                embedding_content_and_boosts.len() - content_and_distances_to_embed.len()
             );
-            let vectors = match get_dense_vectors(
-                embedding_content_and_boosts
-                    .iter()
-                    .map(|(content, _, semantic_boost)| (content.clone(), semantic_boost.clone()))
-                    .collect(),
-                "doc",
-                dataset_config.clone(),
-                reqwest_client.clone(),
+            let vectors = if content_and_distances_to_embed.is_empty() {
+                vec![]
+            } else {
+                let requested_vectors = content_and_distances_to_embed.len();
+                let created_vectors = if dataset_config.SEPARATE_DISTANCE_PHRASE_VECTORS {
+                    get_dense_vectors_with_phrases(
+                        content_and_distances_to_embed,
+                        "doc",
+                        &context,
+                        reqwest_client.clone(),
+                    )
+                    .await
+                } else {
+                    get_dense_vectors(
+                        content_and_distances_to_embed,
+                        "doc",
+                        &context,
+                        reqwest_client.clone(),
+                    )
+                    .await
+                    .map(|vectors| {
+                        vectors
+                            .into_iter()
+                            .map(|content| DenseVectorWithPhrase {
+                                content,
+                                distance_phrase: None,
+                                token_counts: EmbeddingTokenCounts::default(),
+                            })
+                            .collect()
+                    })
+                };
+
+                match created_vectors.and_then(|vectors| {
+                    check_created_vector_count(vectors, requested_vectors, "dense")
+                }) {
+                    Ok(vectors) => Ok(vectors),
+                    Err(err) => {
+                        if !upsert_by_tracking_id_being_used {
+                            bulk_revert_insert_chunk_metadata_query(
+                                inserted_chunk_metadata_ids.clone(),
+                                web_pool.clone(),
+                            )
+                            .await?;
+                        }
+                        log::error!("Failed to create embeddings: {:?}", err);
+                        // Rejections are kept as is so the chunk can be dead-lettered
+                        Err(match err {
+                            ServiceError::ModelRejected(message) => {
+                                ServiceError::ModelRejected(message)
+                            }
+                            err => ServiceError::InternalServerError(format!(
+                                "Failed to create embeddings: {:?}",
+                                err
+                            )),
+                        })
+                    }
+                }?
+            };
+
+            let mut created_vectors = vectors.into_iter();
+            ingestion_data
+                .iter()
+                .map(|data| {
+                    if metadata_only_chunks.contains(&data.chunk_metadata.id) {
+                        return None;
+                    }
+                    precomputed_dense_vectors
+                        .get(&data.chunk_metadata.id)
+                        .cloned()
+                        .map(|content| DenseVectorWithPhrase {
+                            content,
+                            distance_phrase: None,
+                            token_counts: EmbeddingTokenCounts::default(),
+                        })
+                        .or_else(|| created_vectors.next())
+                })
+                .collect()
+        }
+        false => vec![None; embedding_content_and_boosts.len()],
+    };
+
+    // A degenerate vector would leave its chunk stored but unsearchable, so those chunks fail on
+    // their own and the rest of the batch goes ahead
+    let created_vectors: Vec<(uuid::Uuid, &[f32])> =
+        izip!(ingestion_data.iter(), embedding_vectors.iter())
+            .filter(|(data, _)| !precomputed_dense_vectors.contains_key(&data.chunk_metadata.id))
+            .filter_map(|(data, vector)| {
+                vector
+                    .as_ref()
+                    .map(|vector| (data.chunk_metadata.id, vector.content.as_slice()))
+            })
+            .collect();
+    let rejected_vectors: HashMap<uuid::Uuid, String> = izip!(
+        created_vectors.iter(),
+        check_vector_norms(
+            payload.dataset_id,
+            &created_vectors
+                .iter()
+                .map(|(_, vector)| *vector)
+                .collect::<Vec<&[f32]>>(),
+            &dataset_config,
+        )
+    )
+    .filter_map(|((chunk_id, _), result)| result.err().map(|err| (*chunk_id, err.to_string())))
+    .collect();
+
+    if !rejected_vectors.is_empty() {
+        log::error!(
+            "Model server returned {} unusable vectors, failing their chunks",
+            rejected_vectors.len()
+        );
+        if !upsert_by_tracking_id_being_used && !qdrant_only {
+            bulk_revert_insert_chunk_metadata_query(
+                rejected_vectors.keys().copied().collect(),
+                web_pool.clone(),
             )
-            .await
-            {
-                Ok(vectors) => Ok(vectors),
+            .await?;
+        }
+    }
+    let (ingestion_data, embedding_vectors): (Vec<ChunkData>, Vec<Option<DenseVectorWithPhrase>>) =
+        izip!(ingestion_data, embedding_vectors)
+            .filter(|(data, _)| !rejected_vectors.contains_key(&data.chunk_metadata.id))
+            .unzip();
+    let inserted_chunk_metadatas: Vec<ChunkData> = inserted_chunk_metadatas
+        .into_iter()
+        .filter(|data| !rejected_vectors.contains_key(&data.chunk_metadata.id))
+        .collect();
+    let inserted_chunk_metadata_ids: Vec<uuid::Uuid> = inserted_chunk_metadata_ids
+        .into_iter()
+        .filter(|chunk_id| !rejected_vectors.contains_key(chunk_id))
+        .collect();
+    let rejected_chunks: Vec<(uuid::Uuid, String)> = rejected_empty_chunks
+        .into_iter()
+        .chain(rejected_vectors)
+        .collect();
+
+    if inserted_chunk_metadatas.is_empty() {
+        return Ok((vec![], preprocessing_events, rejected_chunks, vec![]));
+    }
+
+    let field_vectors =
+        if dataset_config.SEMANTIC_ENABLED && !dataset_config.EMBEDDING_VECTOR_FIELDS.is_empty() {
+            let metadatas = ingestion_data
+                .iter()
+                .map(|data| {
+                    if metadata_only_chunks.contains(&data.chunk_metadata.id) {
+                        None
+                    } else {
+                        data.chunk_metadata.metadata.clone()
+                    }
+                })
+                .collect();
+            match get_vector_field_vectors(metadatas, &context, reqwest_client.clone()).await {
+                Ok(vectors) => vectors,
                 Err(err) => {
+                    log::error!("Failed to create field vectors: {:?}", err);
                     if !upsert_by_tracking_id_being_used {
                         bulk_revert_insert_chunk_metadata_query(
                             inserted_chunk_metadata_ids.clone(),
@@ -636,17 +1027,37 @@ pub async fn bulk_upload_chunks(
                         )
                         .await?;
                     }
-                    log::error!("Failed to create embeddings: {:?}", err);
-                    Err(ServiceError::InternalServerError(format!(
-                        "Failed to create embeddings: {:?}",
-                        err
-                    )))
+                    return Err(err);
                 }
-            }?;
-            vectors.into_iter().map(Some).collect()
-        }
-        false => vec![None; embedding_content_and_boosts.len()],
-    };
+            }
+        } else {
+            vec![vec![]; ingestion_data.len()]
+        };
+
+    // Chunks get a vector from the dual-write target model for every content vector, including
+    // the ones precomputed by the chunk handler
+    let dual_write_inputs = izip!(ingestion_data.iter(), embedding_vectors.iter())
+        .map(|(data, vector)| {
+            vector
+                .as_ref()
+                .map(|_| (data.embedding_content.clone(), data.semantic_boost.clone()))
+        })
+        .collect();
+    let dual_write_vectors =
+        match get_dual_write_vectors(dual_write_inputs, &context, reqwest_client.clone()).await {
+            Ok(vectors) => vectors,
+            Err(err) => {
+                log::error!("Failed to create dual-write vectors: {:?}", err);
+                if !upsert_by_tracking_id_being_used {
+                    bulk_revert_insert_chunk_metadata_query(
+                        inserted_chunk_metadata_ids.clone(),
+                        web_pool.clone(),
+                    )
+                    .await?;
+                }
+                return Err(err);
+            }
+        };
 
     let content_and_boosts: Vec<(String, Option<FullTextBoost>, Option<SemanticBoost>)> =
         ingestion_data
@@ -654,40 +1065,92 @@ pub async fn bulk_upload_chunks(
             .map(|data| {
                 (
                     data.content.clone(),
-                    data.fulltext_boost.clone(),
+                    filter_boost_for_embed_type(
+                        data.fulltext_boost.clone(),
+                        "doc",
+                        &dataset_config,
+                    ),
                     data.semantic_boost.clone(),
                 )
             })
             .collect();
 
+    let sparse_deferred: Vec<bool> = izip!(ingestion_data.iter(), content_and_boosts.iter())
+        .map(|(data, (_, boost, _))| {
+            !precomputed_sparse_vectors.contains_key(&data.chunk_metadata.id)
+                && !metadata_only_chunks.contains(&data.chunk_metadata.id)
+                && should_defer_sparse_encoding(&dataset_config, boost.as_ref())
+        })
+        .collect();
+
+    let mut sparse_vector_stats = vec![];
     let splade_vectors = if dataset_config.FULLTEXT_ENABLED {
+        let content_and_boosts_to_encode: Vec<(String, Option<FullTextBoost>)> = izip!(
+            ingestion_data.iter(),
+            content_and_boosts.iter(),
+            sparse_deferred.iter()
+        )
+        .filter(|(data, _, deferred)| {
+            !precomputed_sparse_vectors.contains_key(&data.chunk_metadata.id)
+                && !metadata_only_chunks.contains(&data.chunk_metadata.id)
+                && !**deferred
+        })
+        .map(|(_, (content, boost, _), _)| (content.clone(), boost.clone()))
+        .collect();
+
         log::info!(
             "Creating sparse vectors for {} chunks",
-            content_and_boosts.len()
+            content_and_boosts_to_encode.len()
         );
-        match get_sparse_vectors(
-            content_and_boosts
-                .iter()
-                .map(|(content, boost, _)| (content.clone(), boost.clone()))
-                .collect(),
-            "doc",
-            reqwest_client,
-        )
-        .await
-        {
-            Ok(vectors) => Ok(vectors),
-            Err(err) => {
-                log::error!("Failed to create sparse vectors: {:?}", err);
-                if !upsert_by_tracking_id_being_used {
-                    bulk_revert_insert_chunk_metadata_query(
-                        inserted_chunk_metadata_ids.clone(),
-                        web_pool.clone(),
-                    )
-                    .await?;
+        let vectors = if content_and_boosts_to_encode.is_empty() {
+            Ok(vec![])
+        } else {
+            let requested_vectors = content_and_boosts_to_encode.len();
+            match get_sparse_vectors(
+                content_and_boosts_to_encode,
+                "doc",
+                &context,
+                reqwest_client,
+            )
+            .await
+            .and_then(|vectors| check_created_vector_count(vectors, requested_vectors, "sparse"))
+            {
+                Ok(vectors) => {
+                    sparse_vector_stats.extend(record_sparse_vector_stats(
+                        SparseVectorStrategy::Splade,
+                        &vectors,
+                    ));
+                    Ok(vectors)
+                }
+                Err(err) => {
+                    log::error!("Failed to create sparse vectors: {:?}", err);
+                    if !upsert_by_tracking_id_being_used {
+                        bulk_revert_insert_chunk_metadata_query(
+                            inserted_chunk_metadata_ids.clone(),
+                            web_pool.clone(),
+                        )
+                        .await?;
+                    }
+                    Err(err)
                 }
-                Err(err)
             }
-        }
+        };
+
+        vectors.map(|vectors| {
+            let mut created_vectors = vectors.into_iter();
+            izip!(ingestion_data.iter(), sparse_deferred.iter())
+                .map(|(data, deferred)| {
+                    if metadata_only_chunks.contains(&data.chunk_metadata.id) {
+                        return vec![(0, 0.0)];
+                    }
+                    precomputed_sparse_vectors
+                        .get(&data.chunk_metadata.id)
+                        .cloned()
+                        .or_else(|| (!deferred).then(|| created_vectors.next()).flatten())
+                        .unwrap_or(vec![(0, 0.0)])
+                })
+                .collect()
+        })
     } else {
         let content_size = content_and_boosts.len();
 
@@ -699,18 +1162,39 @@ pub async fn bulk_upload_chunks(
     let bm25_vectors = if dataset_config.BM25_ENABLED
         && std::env::var("BM25_ACTIVE").unwrap_or("false".to_string()) == "true"
     {
-        get_bm25_embeddings(
-            content_and_boosts
-                .iter()
-                .map(|(content, boost, _)| (content.clone(), boost.clone()))
+        let chunks_and_boost: Vec<(String, Option<FullTextBoost>)> = content_and_boosts
+            .iter()
+            .map(|(content, boost, _)| (content.clone(), boost.clone()))
+            .collect();
+        let bm25_vectors = get_bm25_embeddings_async(chunks_and_boost.clone(), &context).await?;
+        record_bm25_term_tokens(
+            payload.dataset_id,
+            chunks_and_boost
+                .into_iter()
+                .zip(ingestion_data.iter())
+                .filter(|(_, data)| !metadata_only_chunks.contains(&data.chunk_metadata.id))
+                .map(|(chunk_and_boost, _)| chunk_and_boost)
                 .collect(),
-            dataset_config.BM25_AVG_LEN,
-            dataset_config.BM25_B,
-            dataset_config.BM25_K,
+            &dataset_config,
+            web_pool.clone(),
         )
-        .into_iter()
-        .map(Some)
-        .collect()
+        .await;
+        sparse_vector_stats.extend(record_sparse_vector_stats(
+            SparseVectorStrategy::Bm25,
+            bm25_vectors
+                .iter()
+                .zip(ingestion_data.iter())
+                .filter(|(_, data)| !metadata_only_chunks.contains(&data.chunk_metadata.id))
+                .map(|(vector, _)| vector),
+        ));
+
+        bm25_vectors
+            .into_iter()
+            .zip(ingestion_data.iter())
+            .map(|(vector, data)| {
+                (!metadata_only_chunks.contains(&data.chunk_metadata.id)).then_some(vector)
+            })
+            .collect()
     } else {
         vec![None; content_and_boosts.len()]
     };
@@ -719,10 +1203,21 @@ pub async fn bulk_upload_chunks(
         inserted_chunk_metadatas.clone(),
         embedding_vectors.iter(),
         splade_vectors.iter(),
-        bm25_vectors.iter()
+        bm25_vectors.iter(),
+        sparse_deferred.iter(),
+        field_vectors.iter(),
+        dual_write_vectors.iter()
     ))
     .then(
-        |(chunk_data, embedding_vector, splade_vector, bm25_vector)| async {
+        |(
+            chunk_data,
+            embedding_vector,
+            splade_vector,
+            bm25_vector,
+            sparse_deferred,
+            field_vectors,
+            dual_write_vector,
+        )| async {
             let mut qdrant_point_id = chunk_data.chunk_metadata.qdrant_point_id;
             if qdrant_only {
                 if let Some(tracking_id) = chunk_data.clone().chunk_metadata.tracking_id {
@@ -754,36 +1249,53 @@ pub async fn bulk_upload_chunks(
                 None
             };
 
-            let payload = QdrantPayload::new(
+            let content_hash =
+                get_chunk_content_hash(&chunk_data.content, &chunk_data.embedding_content);
+            let mut payload: Payload = QdrantPayload::new(
                 chunk_data.chunk_metadata,
                 chunk_data.group_ids,
                 None,
                 group_tag_set,
-            );
+            )
+            .into();
+            payload.insert(CONTENT_HASH_PAYLOAD_KEY, content_hash);
 
             let mut vector_payload = HashMap::from([(
                 "sparse_vectors".to_string(),
                 Vector::from(splade_vector.clone()),
             )]);
+            if *sparse_deferred {
+                payload.insert(SPARSE_ENCODED_PAYLOAD_KEY, false);
+            }
 
-            if let Some(vector) = embedding_vector.clone() {
-                let vector_name = match vector.len() {
-                    384 => "384_vectors",
-                    512 => "512_vectors",
-                    768 => "768_vectors",
-                    1024 => "1024_vectors",
-                    3072 => "3072_vectors",
-                    1536 => "1536_vectors",
-                    _ => {
-                        return Err(ServiceError::BadRequest(
-                            "Invalid embedding vector size".into(),
-                        ))
-                    }
-                };
-                vector_payload.insert(
-                    vector_name.to_string().clone(),
-                    Vector::from(vector.clone()),
-                );
+            if let Some(DenseVectorWithPhrase {
+                content: vector,
+                distance_phrase,
+                ..
+            }) = embedding_vector.clone()
+            {
+                if let Some((phrase_vector, distance_factor)) = distance_phrase {
+                    vector_payload.insert(
+                        get_distance_phrase_vector_name(phrase_vector.len()),
+                        Vector::from(phrase_vector),
+                    );
+                    payload.insert(DISTANCE_FACTOR_PAYLOAD_KEY, distance_factor as f64);
+                }
+
+                insert_dense_vectors(
+                    &mut vector_payload,
+                    &mut payload,
+                    vector,
+                    dual_write_vector.clone(),
+                    &dataset_config,
+                )?;
+
+                for (field_index, field_vector) in field_vectors {
+                    vector_payload.insert(
+                        get_vector_field_vector_name(field_vector.len(), *field_index),
+                        Vector::from(field_vector.clone()),
+                    );
+                }
             }
 
             if let Some(bm25_vector) = bm25_vector.clone() {
@@ -840,7 +1352,12 @@ pub async fn bulk_upload_chunks(
         .await?;
     }
 
-    Ok(inserted_chunk_metadata_ids)
+    Ok((
+        inserted_chunk_metadata_ids,
+        preprocessing_events,
+        rejected_chunks,
+        sparse_vector_stats,
+    ))
 }
 
 async fn upload_chunk(
@@ -860,10 +1377,9 @@ async fn upload_chunk(
         }
     }
 
-    let content = match payload.chunk.convert_html_to_text.unwrap_or(true) {
-        true => convert_html_to_text(&(payload.chunk.chunk_html.clone().unwrap_or_default())),
-        false => payload.chunk.chunk_html.clone().unwrap_or_default(),
-    };
+    // The content already went through the dataset's EMPTY_CONTENT_POLICY in bulk_upload_chunks
+    let metadata_only = ingestion_data.content.trim().is_empty()
+        && dataset_config.EMPTY_CONTENT_POLICY == EmptyContentPolicy::MetadataOnly;
 
     let pre_parsed_content = ingestion_data.embedding_content;
     let semantic_content = match payload.chunk.convert_html_to_text.unwrap_or(true) {
@@ -874,7 +1390,11 @@ async fn upload_chunk(
     // Only embed the things we get returned from here, this reduces the number of times we embed data that are just duplicates
     let content_and_boosts: Vec<(String, Option<FullTextBoost>)> = vec![(
         ingestion_data.content.clone(),
-        ingestion_data.fulltext_boost.clone(),
+        filter_boost_for_embed_type(
+            ingestion_data.fulltext_boost.clone(),
+            "doc",
+            &dataset_config,
+        ),
     )];
 
     let chunk_tag_set = payload.chunk.tag_set.clone().map(|tag_set| {
@@ -927,13 +1447,14 @@ async fn upload_chunk(
         num_value: payload.chunk.num_value,
     };
 
-    if content.is_empty() {
+    if ingestion_data.content.trim().is_empty() && !metadata_only {
         return Err(ServiceError::BadRequest(
             "Chunk must not have empty chunk_html".into(),
         ));
     }
 
-    let embedding_vector = match dataset_config.SEMANTIC_ENABLED {
+    let context = EmbedContext::from_dataset_config(&dataset_config);
+    let embedding_vector = match dataset_config.SEMANTIC_ENABLED && !metadata_only {
         true => {
             let embedding = match payload.chunk.split_avg.unwrap_or(false) {
                 true => {
@@ -945,7 +1466,7 @@ async fn upload_chunk(
                             .map(|chunk| (chunk.clone(), payload.chunk.semantic_boost.clone()))
                             .collect(),
                         "doc",
-                        dataset_config.clone(),
+                        &context,
                         reqwest_client.clone(),
                     )
                     .await?;
@@ -959,15 +1480,18 @@ async fn upload_chunk(
                             payload.chunk.semantic_boost.clone(),
                         )],
                         "doc",
-                        dataset_config.clone(),
+                        &context,
                         reqwest_client.clone(),
                     )
                     .await
-                    .map_err(|err| {
-                        ServiceError::InternalServerError(format!(
+                    .map_err(|err| match err {
+                        ServiceError::ModelRejected(message) => {
+                            ServiceError::ModelRejected(message)
+                        }
+                        err => ServiceError::InternalServerError(format!(
                             "Failed to create embedding: {:?}",
                             err
-                        ))
+                        )),
                     })?;
 
                     embedding_vectors
@@ -978,12 +1502,38 @@ async fn upload_chunk(
                         .clone()
                 }
             };
+            ensure_vector_norms(dataset_id, &[&embedding], &dataset_config)?;
             Some(embedding)
         }
         false => None,
     };
 
-    let splade_vector = if dataset_config.FULLTEXT_ENABLED {
+    let field_vectors = if dataset_config.SEMANTIC_ENABLED
+        && !metadata_only
+        && !dataset_config.EMBEDDING_VECTOR_FIELDS.is_empty()
+    {
+        get_vector_field_vectors(
+            vec![ingestion_data.chunk_metadata.metadata.clone()],
+            &context,
+            reqwest_client.clone(),
+        )
+        .await?
+        .pop()
+        .unwrap_or_default()
+    } else {
+        vec![]
+    };
+
+    let sparse_deferred = !metadata_only
+        && should_defer_sparse_encoding(
+            &dataset_config,
+            content_and_boosts[0]
+                .1
+                .as_ref()
+                .filter(|boost| !boost.phrase.is_empty()),
+        );
+
+    let splade_vector = if dataset_config.FULLTEXT_ENABLED && !sparse_deferred && !metadata_only {
         let content_and_boosts: Vec<(String, Option<FullTextBoost>)> = content_and_boosts
             .clone()
             .into_iter()
@@ -998,8 +1548,12 @@ async fn upload_chunk(
             })
             .collect();
 
-        match get_sparse_vectors(content_and_boosts.clone(), "doc", reqwest_client).await {
-            Ok(vectors) => Ok(vectors.first().expect("First vector must exist").clone()),
+        match get_sparse_vectors(content_and_boosts.clone(), "doc", &context, reqwest_client).await
+        {
+            Ok(vectors) => {
+                record_sparse_vector_stats(SparseVectorStrategy::Splade, &vectors);
+                Ok(vectors.first().expect("First vector must exist").clone())
+            }
             Err(err) => Err(err),
         }
     } else {
@@ -1007,19 +1561,21 @@ async fn upload_chunk(
     }?;
 
     let bm25_vector = if dataset_config.BM25_ENABLED
+        && !metadata_only
         && std::env::var("BM25_ACTIVE").unwrap_or("false".to_string()) == "true"
     {
-        Some(
-            get_bm25_embeddings(
-                content_and_boosts,
-                dataset_config.BM25_AVG_LEN,
-                dataset_config.BM25_B,
-                dataset_config.BM25_K,
-            )
-            .first()
-            .expect("Vector Must exist")
-            .clone(),
+        let bm25_vectors = get_bm25_embeddings_async(content_and_boosts.clone(), &context).await?;
+        record_sparse_vector_stats(SparseVectorStrategy::Bm25, &bm25_vectors);
+        let bm25_vector = bm25_vectors.first().expect("Vector Must exist").clone();
+        record_bm25_term_tokens(
+            dataset_id,
+            content_and_boosts,
+            &dataset_config,
+            web_pool.clone(),
         )
+        .await;
+
+        Some(bm25_vector)
     } else {
         None
     };
@@ -1085,34 +1641,33 @@ async fn upload_chunk(
             }
         };
 
-        let qdrant_payload =
-            QdrantPayload::new(chunk_metadata, payload.chunk.group_ids, None, group_tag_set);
-
-        let vector_name = match &embedding_vector {
-            Some(embedding_vector) => match embedding_vector.len() {
-                384 => Some("384_vectors"),
-                512 => Some("512_vectors"),
-                768 => Some("768_vectors"),
-                1024 => Some("1024_vectors"),
-                3072 => Some("3072_vectors"),
-                1536 => Some("1536_vectors"),
-                _ => {
-                    return Err(ServiceError::BadRequest(
-                        "Invalid embedding vector size".into(),
-                    ))
-                }
-            },
-            None => None,
-        };
+        let mut qdrant_payload: Payload =
+            QdrantPayload::new(chunk_metadata, payload.chunk.group_ids, None, group_tag_set).into();
+        qdrant_payload.insert(
+            CONTENT_HASH_PAYLOAD_KEY,
+            get_chunk_content_hash(&ingestion_data.content, &semantic_content),
+        );
+        if sparse_deferred {
+            qdrant_payload.insert(SPARSE_ENCODED_PAYLOAD_KEY, false);
+        }
 
         let mut vector_payload =
             HashMap::from([("sparse_vectors".to_string(), Vector::from(splade_vector))]);
 
+        // Chunks retried one at a time only get their dual-write vector from the backfill
         if let Some(embedding_vector) = embedding_vector.clone() {
-            if let Some(vector_name) = vector_name {
+            insert_dense_vectors(
+                &mut vector_payload,
+                &mut qdrant_payload,
+                embedding_vector,
+                None,
+                &dataset_config,
+            )?;
+
+            for (field_index, field_vector) in field_vectors {
                 vector_payload.insert(
-                    vector_name.to_string(),
-                    Vector::from(embedding_vector.clone()),
+                    get_vector_field_vector_name(field_vector.len(), field_index),
+                    Vector::from(field_vector),
                 );
             }
         }
@@ -1154,10 +1709,18 @@ async fn upload_chunk(
 }
 
 async fn update_chunk(
-    payload: UpdateIngestionMessage,
+    mut payload: UpdateIngestionMessage,
     web_pool: actix_web::web::Data<models::Pool>,
     dataset_config: DatasetConfiguration,
 ) -> Result<(), ServiceError> {
+    // Updates do not report preprocessing events, clipped phrases are only logged
+    clip_boost_phrases(
+        payload.chunk_metadata.id,
+        payload.chunk_metadata.tracking_id.clone(),
+        &mut payload.fulltext_boost,
+        &mut payload.semantic_boost,
+        &dataset_config,
+    );
     let content = match payload.convert_html_to_text.unwrap_or(true) {
         true => convert_html_to_text(
             &(payload
@@ -1173,55 +1736,143 @@ async fn update_chunk(
             .unwrap_or_default(),
     };
 
-    if content.is_empty() {
-        return Err(ServiceError::BadRequest(
-            "Chunk must not have empty chunk_html".into(),
-        ));
-    }
+    // A metadata only chunk keeps the dense vector it had before the update
+    let (content, metadata_only) = match resolve_empty_content(
+        &content,
+        &payload
+            .chunk_metadata
+            .chunk_html
+            .clone()
+            .unwrap_or_default(),
+        payload.chunk_metadata.metadata.as_ref(),
+        &dataset_config,
+    ) {
+        Some(EmptyContentResolution::Reject) => {
+            return Err(ServiceError::BadRequest(
+                "Chunk must not have empty chunk_html".into(),
+            ));
+        }
+        Some(EmptyContentResolution::Fallback(fallback_text)) => (fallback_text, false),
+        Some(EmptyContentResolution::MetadataOnly) => (content, true),
+        None => (content, false),
+    };
 
     let chunk_metadata = payload.chunk_metadata.clone();
-
-    let embedding_vector = match dataset_config.SEMANTIC_ENABLED {
-        true => {
+    let embedding_content = get_templated_embedding_content(
+        content.to_string(),
+        payload.chunk_metadata.link.as_ref(),
+        payload
+            .chunk_metadata
+            .tag_set
+            .clone()
+            .map(|tag_set| tag_set.into_iter().flatten().collect::<Vec<String>>()),
+        payload.chunk_metadata.metadata.as_ref(),
+        &dataset_config,
+    );
+    let content = get_fulltext_embedding_content(
+        content,
+        payload.chunk_metadata.metadata.as_ref(),
+        &dataset_config,
+    );
+    let content_hash = get_chunk_content_hash(&content, &embedding_content);
+    // Messages queued before vector updates were planned regenerate everything
+    let vector_update = payload.vector_update.unwrap_or(ChunkVectorUpdate::FULL);
+
+    let context = EmbedContext::from_dataset_config(&dataset_config);
+    let embedding_vector = match dataset_config.SEMANTIC_ENABLED && !metadata_only {
+        true if payload.dense_vector.is_some() => payload.dense_vector.clone(),
+        true if vector_update.regenerate_dense => {
             let embedding = get_dense_vector(
-                content.to_string(),
+                embedding_content,
                 payload.semantic_boost.clone(),
                 "doc",
-                dataset_config.clone(),
+                &context,
             )
             .await
             .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+            ensure_vector_norms(payload.dataset_id, &[&embedding], &dataset_config)?;
             Some(embedding)
         }
-        false => None,
+        _ => None,
     };
 
-    let splade_vector = if dataset_config.FULLTEXT_ENABLED {
+    // Field texts come from the metadata, which the content hash does not cover, so they are
+    // embedded again on every update
+    let field_vectors = if dataset_config.SEMANTIC_ENABLED
+        && !metadata_only
+        && !dataset_config.EMBEDDING_VECTOR_FIELDS.is_empty()
+    {
+        Some(
+            get_vector_field_vectors(
+                vec![payload.chunk_metadata.metadata.clone()],
+                &context,
+                reqwest::Client::new(),
+            )
+            .await?
+            .pop()
+            .unwrap_or_default(),
+        )
+    } else {
+        None
+    };
+    let field_vectors_config = dataset_config.clone();
+
+    if let Some(semantic_boost) = payload
+        .semantic_boost
+        .as_ref()
+        .filter(|_| dataset_config.SEMANTIC_ENABLED && vector_update.recompute_phrase)
+    {
+        let phrase_vector = get_distance_phrase_vector(semantic_boost, &context)
+            .await
+            .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+        update_distance_phrase_vector_query(
+            chunk_metadata.qdrant_point_id,
+            phrase_vector,
+            semantic_boost.distance_factor,
+            &dataset_config,
+        )
+        .await?;
+    }
+
+    let fulltext_boost =
+        filter_boost_for_embed_type(payload.fulltext_boost.clone(), "doc", &dataset_config);
+
+    let splade_vector = if let Some(sparse_vector) = payload.sparse_vector.clone() {
+        Some(sparse_vector)
+    } else if !vector_update.regenerate_sparse {
+        None
+    } else if dataset_config.FULLTEXT_ENABLED && !metadata_only {
         let reqwest_client = reqwest::Client::new();
 
         match get_sparse_vectors(
-            vec![(content.clone(), payload.fulltext_boost.clone())],
+            vec![(content.clone(), fulltext_boost.clone())],
             "doc",
+            &context,
             reqwest_client,
         )
         .await
         {
-            Ok(v) => v.first().unwrap_or(&vec![(0, 0.0)]).clone(),
-            Err(_) => vec![(0, 0.0)],
+            Ok(v) => Some(v.first().unwrap_or(&vec![(0, 0.0)]).clone()),
+            Err(_) => Some(vec![(0, 0.0)]),
         }
     } else {
-        vec![(0, 0.0)]
+        Some(vec![(0, 0.0)])
     };
 
-    let bm25_vector = if dataset_config.BM25_ENABLED
+    let bm25_vector = if vector_update.regenerate_sparse
+        && !metadata_only
+        && dataset_config.BM25_ENABLED
         && std::env::var("BM25_ACTIVE").unwrap_or("false".to_string()) == "true"
     {
-        let vecs = get_bm25_embeddings(
-            vec![(content, payload.fulltext_boost.clone())],
-            dataset_config.BM25_AVG_LEN,
-            dataset_config.BM25_B,
-            dataset_config.BM25_K,
-        );
+        let chunks_and_boost = vec![(content, fulltext_boost)];
+        let vecs = get_bm25_embeddings_async(chunks_and_boost.clone(), &context).await?;
+        record_bm25_term_tokens(
+            payload.dataset_id,
+            chunks_and_boost,
+            &dataset_config,
+            web_pool.clone(),
+        )
+        .await;
 
         vecs.first().cloned()
     } else {
@@ -1253,6 +1904,7 @@ async fn update_chunk(
             payload.dataset_id,
             splade_vector,
             bm25_vector,
+            Some(content_hash),
             dataset_config,
             web_pool.clone(),
         )
@@ -1275,6 +1927,7 @@ async fn update_chunk(
             payload.dataset_id,
             splade_vector,
             bm25_vector,
+            Some(content_hash),
             dataset_config,
             web_pool.clone(),
         )
@@ -1282,6 +1935,15 @@ async fn update_chunk(
         .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
     }
 
+    if let Some(field_vectors) = field_vectors {
+        update_vector_field_vectors_query(
+            payload.chunk_metadata.qdrant_point_id,
+            field_vectors,
+            &field_vectors_config,
+        )
+        .await?;
+    }
+
     // If boosts are changed, reflect changes to chunk_boosts table
     if payload.fulltext_boost.is_some() || payload.semantic_boost.is_some() {
         update_chunk_boost_query(
@@ -1304,6 +1966,7 @@ pub async fn readd_error_to_queue(
     message: IngestionMessage,
     error: ServiceError,
     redis_pool: actix_web::web::Data<models::RedisPool>,
+    web_pool: actix_web::web::Data<models::Pool>,
     event_queue: actix_web::web::Data<EventQueue>,
 ) -> Result<(), ServiceError> {
     if let ServiceError::DuplicateTrackingId(_) = error {
@@ -1311,7 +1974,7 @@ pub async fn readd_error_to_queue(
         return Ok(());
     }
 
-    if let IngestionMessage::BulkUpload(mut payload) = message {
+    if let IngestionMessage::BulkUpload(payload) = message {
         let old_payload_message = serde_json::to_string(&payload).map_err(|_| {
             ServiceError::InternalServerError("Failed to reserialize input for retry".to_string())
         })?;
@@ -1327,67 +1990,146 @@ pub async fn readd_error_to_queue(
             .arg(old_payload_message.clone())
             .query_async::<redis::aio::MultiplexedConnection, usize>(&mut *redis_conn)
             .await;
+        // Don't hold onto the connection while waiting out the retry delay
+        drop(redis_conn);
+
+        let retry_payloads =
+            match plan_ingestion_retry(payload, &error, get_embedding_dead_letter_attempts()) {
+                IngestionRetry::Retry(payload) => vec![payload],
+                IngestionRetry::Split(payloads) => {
+                    log::info!(
+                        "Model server rejected a batch, retrying its {} chunks one by one",
+                        payloads.len()
+                    );
+                    payloads
+                }
+                IngestionRetry::DeadLetter(payload) => {
+                    log::error!(
+                    "Model server rejected chunk {} times, moving it to the dead-letter table {:?}",
+                    payload.rejected_attempts,
+                    error
+                );
+                    let chunk_ids = payload
+                        .ingestion_messages
+                        .iter()
+                        .map(|m| m.ingest_specific_chunk_metadata.id)
+                        .collect();
 
-        payload.attempt_number += 1;
+                    insert_embedding_dead_letters_query(
+                        get_dead_letters_from_payload(&payload, &error)?,
+                        web_pool,
+                    )
+                    .await?;
 
-        if payload.attempt_number == 10 {
-            log::error!("Failed to insert data 10 times quitting {:?}", error);
-            let count = payload.ingestion_messages.len();
-            let chunk_ids = payload
-                .ingestion_messages
-                .iter()
-                .map(|m| m.ingest_specific_chunk_metadata.id)
-                .collect();
+                    event_queue
+                        .send(ClickHouseEvent::WorkerEvent(
+                            WorkerEvent::from_details(
+                                payload.dataset_id,
+                                models::EventType::BulkChunkUploadFailed {
+                                    chunk_ids,
+                                    error: format!("Chunk was dead-lettered: {:?}", error),
+                                },
+                            )
+                            .into(),
+                        ))
+                        .await;
 
-            event_queue
-                .send(ClickHouseEvent::WorkerEvent(
-                    WorkerEvent::from_details(
-                        payload.dataset_id,
-                        models::EventType::BulkChunkUploadFailed {
-                            chunk_ids,
-                            error: format!("Failed to upload {:} chunks: {:?}", count, error),
-                        },
-                    )
-                    .into(),
-                ))
-                .await;
+                    return Ok(());
+                }
+                IngestionRetry::GiveUp(payload) => {
+                    log::error!(
+                        "Failed to insert data {} times quitting {:?}",
+                        payload.attempt_number,
+                        error
+                    );
+                    let count = payload.ingestion_messages.len();
+                    let chunk_ids = payload
+                        .ingestion_messages
+                        .iter()
+                        .map(|m| m.ingest_specific_chunk_metadata.id)
+                        .collect();
+
+                    event_queue
+                        .send(ClickHouseEvent::WorkerEvent(
+                            WorkerEvent::from_details(
+                                payload.dataset_id,
+                                models::EventType::BulkChunkUploadFailed {
+                                    chunk_ids,
+                                    error: format!(
+                                        "Failed to upload {:} chunks: {:?}",
+                                        count, error
+                                    ),
+                                },
+                            )
+                            .into(),
+                        ))
+                        .await;
 
-            let mut redis_conn = redis_pool
-                .get()
-                .await
-                .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+                    let mut redis_conn = redis_pool
+                        .get()
+                        .await
+                        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+                    redis::cmd("lpush")
+                        .arg("dead_letters")
+                        .arg(old_payload_message)
+                        .query_async::<redis::aio::MultiplexedConnection, ()>(&mut *redis_conn)
+                        .await
+                        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+                    return Err(ServiceError::InternalServerError(format!(
+                        "Failed to create new qdrant point: {:?}",
+                        error
+                    )));
+                }
+            };
 
-            redis::cmd("lpush")
-                .arg("dead_letters")
-                .arg(old_payload_message)
-                .query_async::<redis::aio::MultiplexedConnection, ()>(&mut *redis_conn)
-                .await
-                .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+        let new_payload_messages = retry_payloads
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(|_| {
+                ServiceError::InternalServerError(
+                    "Failed to reserialize input for retry".to_string(),
+                )
+            })?;
 
-            return Err(ServiceError::InternalServerError(format!(
-                "Failed to create new qdrant point: {:?}",
-                error
-            )));
+        let attempt_number = retry_payloads
+            .first()
+            .map(|payload| payload.attempt_number)
+            .unwrap_or_default();
+        let requeue_policy = RequeuePolicy::from_env();
+        let retry_delay = get_requeue_delay(
+            &error,
+            attempt_number,
+            &requeue_policy,
+            rand::random::<f64>(),
+        );
+        log::error!(
+            "Failed to insert data, re-adding {:?} retry: {:?} in {:?}",
+            error,
+            attempt_number,
+            retry_delay
+        );
+        tokio::time::sleep(retry_delay).await;
+        if let Some(dataset_id) = retry_payloads.first().map(|payload| payload.dataset_id) {
+            wait_for_redelivery_slot(
+                dataset_id,
+                retry_payloads.len(),
+                &requeue_policy,
+                redis_pool.clone(),
+            )
+            .await;
         }
 
-        let new_payload_message = serde_json::to_string(&payload).map_err(|_| {
-            ServiceError::InternalServerError("Failed to reserialize input for retry".to_string())
-        })?;
-
         let mut redis_conn = redis_pool
             .get()
             .await
             .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
 
-        log::error!(
-            "Failed to insert data, re-adding {:?} retry: {:?}",
-            error,
-            payload.attempt_number
-        );
-
         redis::cmd("lpush")
             .arg("ingestion")
-            .arg(&new_payload_message)
+            .arg(&new_payload_messages)
             .query_async(&mut *redis_conn)
             .await
             .map_err(|err| ServiceError::BadRequest(err.to_string()))?
@@ -5,10 +5,13 @@ use qdrant_client::{
     Qdrant,
 };
 use trieve_server::{
-    data::models::{MigratePointMessage, MigrationMode},
+    data::models::{ContentLanguage, MigratePointMessage, MigrationMode},
     errors::ServiceError,
     get_env,
-    operators::{model_operator::get_bm25_embeddings, qdrant_operator::get_qdrant_connection},
+    operators::{
+        model_operator::{get_bm25_embeddings, Bm25Stopwords},
+        qdrant_operator::get_qdrant_connection,
+    },
 };
 
 #[allow(clippy::print_stdout)]
@@ -178,7 +181,16 @@ pub async fn migrate_bm25(
             };
 
             // calculate bm25
-            let bm25_embeddings = get_bm25_embeddings(vec![(content, None)], average_len, b, k);
+            let bm25_embeddings = get_bm25_embeddings(
+                vec![(content, None)],
+                average_len,
+                b,
+                k,
+                1,
+                40,
+                ContentLanguage::English,
+                &Bm25Stopwords::Keep,
+            );
 
             let bm25_embedding = bm25_embeddings.first().expect("BM25 Vectors");
 
@@ -0,0 +1,68 @@
+use diesel_async::pooled_connection::{AsyncDieselConnectionManager, ManagerConfig};
+use qdrant_client::qdrant::{Condition, Filter};
+use trieve_server::{
+    data::models::DatasetConfiguration,
+    errors::ServiceError,
+    establish_connection, get_env,
+    operators::{
+        dataset_operator::get_dataset_by_id_query, qdrant_operator::encode_pending_sparse_vectors,
+    },
+};
+
+#[tokio::main]
+async fn main() -> Result<(), ServiceError> {
+    dotenvy::dotenv().ok();
+    env_logger::builder()
+        .target(env_logger::Target::Stdout)
+        .filter_level(log::LevelFilter::Info)
+        .init();
+
+    let dataset_id: uuid::Uuid = std::env::var("DATASET_ID")
+        .map_err(|_| ServiceError::BadRequest("DATASET_ID is not set".to_string()))?
+        .parse()
+        .map_err(|_| ServiceError::BadRequest("DATASET_ID must be a uuid".to_string()))?;
+
+    let database_url = get_env!("DATABASE_URL", "DATABASE_URL is not set");
+
+    let mut config = ManagerConfig::default();
+    config.custom_setup = Box::new(establish_connection);
+
+    let mgr = AsyncDieselConnectionManager::<diesel_async::AsyncPgConnection>::new_with_config(
+        database_url,
+        config,
+    );
+
+    let pool = diesel_async::pooled_connection::deadpool::Pool::builder(mgr)
+        .max_size(1)
+        .build()
+        .expect("Failed to create diesel_async pool");
+
+    let pool = actix_web::web::Data::new(pool.clone());
+
+    let dataset = get_dataset_by_id_query(dataset_id, pool).await?;
+    let dataset_config = DatasetConfiguration::from_json(dataset.server_configuration);
+
+    if !dataset_config.FULLTEXT_ENABLED {
+        return Err(ServiceError::BadRequest(
+            "Full text search is not enabled for this dataset".to_string(),
+        ));
+    }
+
+    let mut filter = Filter::default();
+    filter
+        .must
+        .push(Condition::matches("dataset_id", dataset_id.to_string()));
+
+    log::info!("Backfilling sparse vectors for dataset {}", dataset_id);
+    let encoded = encode_pending_sparse_vectors(filter, &dataset_config, |encoded| {
+        log::info!("Encoded {} sparse vectors so far", encoded);
+    })
+    .await?;
+    log::info!(
+        "Finished backfilling {} sparse vectors for dataset {}",
+        encoded,
+        dataset_id
+    );
+
+    Ok(())
+}
@@ -5,8 +5,8 @@ use crate::errors::ServiceError;
 use crate::get_env;
 use crate::handlers::analytics_handler::CTRDataRequestBody;
 use crate::handlers::chunk_handler::{
-    AutocompleteReqPayload, ChunkFilter, CrawlOpenAPIOptions, FullTextBoost, ParsedQuery,
-    ScoringOptions, SearchChunksReqPayload, SemanticBoost,
+    AutocompleteReqPayload, ChunkFilter, CrawlOpenAPIOptions, FullTextBoost, FusionOptions,
+    ParsedQuery, ScoringOptions, SearchChunksReqPayload, SemanticBoost,
 };
 use crate::handlers::chunk_handler::{CrawlInterval, ScrollChunksReqPayload};
 use crate::handlers::file_handler::{
@@ -869,6 +869,19 @@ pub struct ScoreChunkDTO {
     pub metadata: Vec<ChunkMetadataTypes>,
     pub highlights: Option<Vec<String>>,
     pub score: f64,
+    /// Whether the chunk lacked fields the dataset's RERANK_TEMPLATE refers to when it was reranked, only present for reranked results of datasets with a RERANK_TEMPLATE
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rerank_template_incomplete: Option<bool>,
+    /// The part of the chunk's text the reranker scored most relevant to the query, to show as its snippet. Only present for reranked results of datasets with RERANK_HIGHLIGHT_WINDOWS set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preferred_highlight: Option<HighlightRegion>,
+}
+
+/// Char offsets into a chunk's text, the chunk_html with its tags stripped. `end` is exclusive.
+#[derive(Serialize, Deserialize, Debug, ToSchema, Clone, Copy, PartialEq)]
+pub struct HighlightRegion {
+    pub start: usize,
+    pub end: usize,
 }
 
 #[derive(Serialize, Deserialize, Debug, ToSchema, Clone)]
@@ -1224,6 +1237,14 @@ impl ChunkMetadataTypes {
         }
     }
 
+    pub fn id(&self) -> uuid::Uuid {
+        match self {
+            ChunkMetadataTypes::Metadata(metadata) => metadata.id,
+            ChunkMetadataTypes::ID(slim_metadata) => slim_metadata.id,
+            ChunkMetadataTypes::Content(content_metadata) => content_metadata.id,
+        }
+    }
+
     pub fn qdrant_point_id(&self) -> uuid::Uuid {
         match self {
             ChunkMetadataTypes::Metadata(metadata) => metadata.qdrant_point_id,
@@ -2029,7 +2050,37 @@ pub enum EventType {
     #[display(fmt = "file_upload_failed")]
     FileUploadFailed { file_id: uuid::Uuid, error: String },
     #[display(fmt = "chunks_uploaded")]
-    ChunksUploaded { chunk_ids: Vec<uuid::Uuid> },
+    ChunksUploaded {
+        chunk_ids: Vec<uuid::Uuid>,
+        /// Number of chunks whose content was truncated before being embedded
+        truncated_chunks: usize,
+        /// Number of chunks whose content was blank after removing html, their chunk_preprocessed events record the EMPTY_CONTENT_POLICY action
+        emptied_chunks: usize,
+        /// Size and weight statistics of the batch's sparse vectors, only set when the ingestion worker runs with INGESTION_REPORT_VERBOSE
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sparse_vector_stats: Option<Vec<SparseVectorStats>>,
+    },
+    #[display(fmt = "chunk_preprocessed")]
+    ChunkPreprocessed {
+        chunk_id: uuid::Uuid,
+        tracking_id: Option<String>,
+        /// The step of the embedding pipeline which altered the content
+        stage: PreprocessingStage,
+        kind: PreprocessingEventKind,
+        /// Length of the content in chars before preprocessing
+        original_length: usize,
+        /// Length of the content in chars after preprocessing, for emptied chunks the length of the fallback text
+        resulting_length: usize,
+        /// What was done with an emptied chunk: reject, fallback or metadata_only
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        action: Option<EmptyContentPolicy>,
+        /// Estimated tokens of a truncated chunk before it was clipped
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        original_tokens: Option<usize>,
+        /// Estimated tokens of a truncated chunk after it was clipped, what the provider is billed for
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        resulting_tokens: Option<usize>,
+    },
     #[display(fmt = "chunk_updated")]
     ChunkUpdated { chunk_id: uuid::Uuid },
     #[display(fmt = "bulk_chunks_deleted")]
@@ -2077,12 +2128,110 @@ pub enum EventType {
     },
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Display, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PreprocessingStage {
+    #[display(fmt = "html_removal")]
+    HtmlRemoval,
+    #[display(fmt = "dense")]
+    Dense,
+    #[display(fmt = "sparse")]
+    Sparse,
+    #[display(fmt = "boost_phrase")]
+    BoostPhrase,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Display, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PreprocessingEventKind {
+    #[display(fmt = "truncated")]
+    Truncated,
+    #[display(fmt = "emptied")]
+    Emptied,
+}
+
+/// What ingestion does with a chunk whose text is blank once its html is removed, e.g. a chunk
+/// which is only an image or an iframe.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Display, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EmptyContentPolicy {
+    /// Fail the chunk instead of embedding an empty string
+    #[default]
+    #[display(fmt = "reject")]
+    Reject,
+    /// Embed the alt and title attributes of the html, or else the metadata field
+    /// EMPTY_CONTENT_FALLBACK_FIELD. Chunks with neither are rejected.
+    #[display(fmt = "fallback")]
+    Fallback,
+    /// Store the chunk without dense or sparse vectors, it can only be found through filters
+    #[display(fmt = "metadata_only")]
+    MetadataOnly,
+}
+
+/// Records that preprocessing for the embedding servers changed a chunk's content in a way the
+/// user would not expect, e.g. clipping it to the model's input budget.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PreprocessingEvent {
+    pub chunk_id: uuid::Uuid,
+    pub tracking_id: Option<String>,
+    pub stage: PreprocessingStage,
+    pub kind: PreprocessingEventKind,
+    pub original_length: usize,
+    pub resulting_length: usize,
+    /// The EMPTY_CONTENT_POLICY applied to an emptied chunk
+    pub action: Option<EmptyContentPolicy>,
+    /// Estimated tokens before and after clipping of a truncated chunk
+    pub original_tokens: Option<usize>,
+    pub resulting_tokens: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Display, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SparseVectorStrategy {
+    #[display(fmt = "splade")]
+    Splade,
+    #[display(fmt = "bm25")]
+    Bm25,
+}
+
+/// Summary of the sparse vectors created for one ingestion batch. Terms with a zero weight are
+/// not counted as active.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct SparseVectorStats {
+    pub strategy: SparseVectorStrategy,
+    pub vectors: usize,
+    pub min_active_terms: usize,
+    pub mean_active_terms: f32,
+    pub max_active_terms: usize,
+    /// Largest weight of any term in the batch
+    pub max_weight: f32,
+    /// Mean weight of the active terms in the batch
+    pub mean_weight: f32,
+}
+
+impl From<PreprocessingEvent> for EventType {
+    fn from(event: PreprocessingEvent) -> Self {
+        EventType::ChunkPreprocessed {
+            chunk_id: event.chunk_id,
+            tracking_id: event.tracking_id,
+            stage: event.stage,
+            kind: event.kind,
+            original_length: event.original_length,
+            resulting_length: event.resulting_length,
+            action: event.action,
+            original_tokens: event.original_tokens,
+            resulting_tokens: event.resulting_tokens,
+        }
+    }
+}
+
 impl EventType {
     pub fn get_all_event_types() -> Vec<EventTypeRequest> {
         vec![
             EventTypeRequest::FileUploaded,
             EventTypeRequest::FileUploadFailed,
             EventTypeRequest::ChunksUploaded,
+            EventTypeRequest::ChunkPreprocessed,
             EventTypeRequest::ChunkActionFailed,
             EventTypeRequest::ChunkUpdated,
             EventTypeRequest::BulkChunksDeleted,
@@ -2270,6 +2419,55 @@ impl DatasetUsageCount {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Display, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DatasetCloneStatus {
+    #[display(fmt = "queued")]
+    Queued,
+    #[display(fmt = "running")]
+    Running,
+    #[display(fmt = "completed")]
+    Completed,
+    #[display(fmt = "failed")]
+    Failed,
+}
+
+/// Progress of copying the chunks of one dataset into another.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "source_dataset_id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "target_dataset_id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "status": "running",
+    "reuse_vectors": true,
+    "reembed_reason": null,
+    "total_chunks": 1000,
+    "cloned_chunks": 300,
+    "error": null,
+    "created_at": "2021-01-01 00:00:00.000",
+    "updated_at": "2021-01-01 00:00:00.000",
+}))]
+pub struct DatasetCloneJob {
+    pub id: uuid::Uuid,
+    pub source_dataset_id: uuid::Uuid,
+    pub target_dataset_id: uuid::Uuid,
+    pub status: DatasetCloneStatus,
+    /// Whether the stored dense and sparse vectors are copied. Only true when the embedding configuration of both datasets is identical, otherwise the chunks are queued for ingestion into the target dataset and embedded again.
+    pub reuse_vectors: bool,
+    /// Why the chunks are embedded again instead of reusing the source dataset's vectors.
+    pub reembed_reason: Option<String>,
+    /// Number of chunks in the source dataset when the clone was queued.
+    pub total_chunks: i32,
+    /// Number of chunks copied so far. When the chunks are embedded again, this counts the chunks queued for ingestion.
+    pub cloned_chunks: i32,
+    /// Id of the first source point not cloned yet, a job which was interrupted resumes from it. None before the first page and after the last one.
+    #[serde(default)]
+    pub next_offset: Option<uuid::Uuid>,
+    pub error: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 #[schema(example = json!({
     "dataset": {
@@ -2300,6 +2498,165 @@ impl DatasetAndUsage {
     }
 }
 
+/// Language of text as an ISO 639-1 code. Selects the stemmer bm25 tokenizes with and, for
+/// queries, the entry of `EMBEDDING_QUERY_PREFIXES` used.
+#[derive(
+    Debug, Serialize, Deserialize, ToSchema, Display, Clone, Copy, PartialEq, Eq, Hash, Default,
+)]
+pub enum ContentLanguage {
+    #[default]
+    #[serde(rename = "en")]
+    #[display(fmt = "en")]
+    English,
+    #[serde(rename = "de")]
+    #[display(fmt = "de")]
+    German,
+    #[serde(rename = "fr")]
+    #[display(fmt = "fr")]
+    French,
+    #[serde(rename = "es")]
+    #[display(fmt = "es")]
+    Spanish,
+    #[serde(rename = "it")]
+    #[display(fmt = "it")]
+    Italian,
+    #[serde(rename = "nl")]
+    #[display(fmt = "nl")]
+    Dutch,
+    #[serde(rename = "pt")]
+    #[display(fmt = "pt")]
+    Portuguese,
+    #[serde(rename = "sv")]
+    #[display(fmt = "sv")]
+    Swedish,
+    #[serde(rename = "da")]
+    #[display(fmt = "da")]
+    Danish,
+    #[serde(rename = "no")]
+    #[display(fmt = "no")]
+    Norwegian,
+    #[serde(rename = "fi")]
+    #[display(fmt = "fi")]
+    Finnish,
+    #[serde(rename = "ru")]
+    #[display(fmt = "ru")]
+    Russian,
+    #[serde(rename = "ar")]
+    #[display(fmt = "ar")]
+    Arabic,
+    #[serde(rename = "el")]
+    #[display(fmt = "el")]
+    Greek,
+    #[serde(rename = "hu")]
+    #[display(fmt = "hu")]
+    Hungarian,
+    #[serde(rename = "ro")]
+    #[display(fmt = "ro")]
+    Romanian,
+    #[serde(rename = "ta")]
+    #[display(fmt = "ta")]
+    Tamil,
+    #[serde(rename = "tr")]
+    #[display(fmt = "tr")]
+    Turkish,
+}
+
+/// Pooling strategy hint sent with embedding requests. Only honored by servers which accept a
+/// per-request `pooling` parameter (self-hosted text-embeddings-inference and infinity
+/// deployments). OpenAI compatible APIs should leave this unset.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Display, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddingPooling {
+    #[display(fmt = "cls")]
+    Cls,
+    #[display(fmt = "mean")]
+    Mean,
+    #[display(fmt = "last_token")]
+    LastToken,
+}
+
+/// Request and response schema spoken by the dataset's embedding server.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Display, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingProvider {
+    /// OpenAI's `/embeddings` schema, also served by text-embeddings-inference, infinity and most
+    /// gateways
+    #[default]
+    #[display(fmt = "openai")]
+    OpenAI,
+    /// Cohere's `/embed` schema, which takes `texts` with an `input_type` and returns `embeddings`
+    #[display(fmt = "cohere")]
+    Cohere,
+    /// Vertex AI's `:predict` schema for Google's text embedding models, which takes `instances`
+    /// with a `task_type` and returns `predictions`. Authenticated with OAuth access tokens
+    /// instead of a static key.
+    #[display(fmt = "vertex")]
+    Vertex,
+}
+
+/// How scores returned by the reranker are mapped before they are returned with the chunks.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Display, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RerankScoreNormalization {
+    /// Return the scores as the reranker produced them
+    #[default]
+    #[display(fmt = "none")]
+    None,
+    /// Clamp every score into [0, 1]
+    #[display(fmt = "clamp")]
+    Clamp,
+    /// Map every score with the logistic function, for rerankers which return raw logits
+    #[display(fmt = "sigmoid")]
+    Sigmoid,
+    /// Scale the reranked batch so its lowest score is 0 and its highest is 1
+    #[display(fmt = "minmax")]
+    MinMax,
+}
+
+/// How the semantic and fulltext result sets of a hybrid search are merged into one candidate
+/// list before reranking.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Display, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HybridFusion {
+    /// Reciprocal rank fusion, sums `weight / (60 + rank)` over the result sets a chunk appears in
+    #[default]
+    #[display(fmt = "rrf")]
+    Rrf,
+    /// Min-max normalizes the scores of each result set and sums them weighted
+    #[display(fmt = "weighted_sum")]
+    WeightedSum,
+}
+
+/// Whether dense vectors are scaled to unit length before being stored or searched with.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Display, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DenseNormalization {
+    /// Normalize only for cosine datasets, where it does not change rankings
+    #[default]
+    #[display(fmt = "auto")]
+    Auto,
+    /// Always normalize, including for dot product, euclidean and manhattan datasets
+    #[display(fmt = "always")]
+    Always,
+    #[display(fmt = "never")]
+    Never,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Display, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DenseQuantization {
+    #[default]
+    #[display(fmt = "none")]
+    None,
+    /// Keep only the sign of every dimension, stored as 1.0 or -1.0
+    #[display(fmt = "binary")]
+    Binary,
+    /// Scale every vector so its largest dimension maps to 127 and round to int8. The stored
+    /// vector is the int8 values multiplied back by the per-vector scale
+    #[display(fmt = "int8")]
+    Int8,
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema, Display, Clone, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum DistanceMetric {
@@ -2314,6 +2671,42 @@ pub enum DistanceMetric {
     Dot,
 }
 
+/// Which of the two dense named vectors of a collection holds a dataset's searchable vectors.
+/// Datasets migrating to a new embedding model write the target model's vectors into the other
+/// slot and flip to it at cutover.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Display, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DenseVectorSlot {
+    #[default]
+    #[display(fmt = "primary")]
+    Primary,
+    #[display(fmt = "secondary")]
+    Secondary,
+}
+
+impl DenseVectorSlot {
+    pub fn other(&self) -> Self {
+        match self {
+            DenseVectorSlot::Primary => DenseVectorSlot::Secondary,
+            DenseVectorSlot::Secondary => DenseVectorSlot::Primary,
+        }
+    }
+}
+
+/// The embedding model a dataset is migrating to. Its vectors must have the dataset's
+/// EMBEDDING_SIZE since both models share the dataset's collection.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, PartialEq)]
+#[schema(example = json!({
+    "base_url": "https://api.openai.com/v1",
+    "model_name": "text-embedding-3-large"
+}))]
+pub struct EmbeddingDualWriteTarget {
+    /// Base url of the target model's embedding server
+    pub base_url: String,
+    /// Name of the target model
+    pub model_name: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 #[schema(example=json!({
     "LLM_BASE_URL": "https://api.openai.com/v1",
@@ -2380,6 +2773,58 @@ pub struct DatasetConfiguration {
     pub MAX_LIMIT: u64,
     pub PUBLIC_DATASET: PublicDatasetOptions,
     pub DISABLE_ANALYTICS: bool,
+    pub EMBEDDING_MAX_TOKENS: Option<usize>,
+    pub EMBEDDING_POOLING: Option<EmbeddingPooling>,
+    pub EMBEDDING_TEMPLATE: Option<String>,
+    pub APPLY_BOOSTS_TO_QUERIES: bool,
+    pub APPLY_BOOSTS_TO_DOCUMENTS: bool,
+    pub EMBEDDING_PART_SEPARATOR: String,
+    pub EMBEDDING_PII_REDACTION_ENABLED: bool,
+    pub EMBEDDING_PII_PATTERNS: Option<Vec<String>>,
+    pub DENSE_VECTOR_NORMALIZATION: DenseNormalization,
+    pub DENSE_VECTOR_QUANTIZATION: DenseQuantization,
+    pub EMBEDDING_DIMENSION: Option<usize>,
+    pub EMBEDDING_RESPONSE_POINTER: Option<String>,
+    pub RERANKER_RESPONSE_POINTER: Option<String>,
+    pub DENSE_VECTOR_DECIMAL_PLACES: Option<u32>,
+    pub RERANKER_SCORE_NORMALIZATION: RerankScoreNormalization,
+    pub SEPARATE_DISTANCE_PHRASE_VECTORS: bool,
+    pub SCORE_DECIMAL_PLACES: Option<u32>,
+    pub BM25_MIN_TOKEN_LENGTH: usize,
+    pub LAZY_SPARSE_ENCODING: bool,
+    pub EMBEDDING_DOC_PREFIX: String,
+    pub EMBEDDING_RATE_LIMIT_WEIGHT: f64,
+    pub EMBEDDING_MIN_NORM: f64,
+    pub EMBEDDING_MAX_NORM_DEVIATION: Option<f64>,
+    pub HYBRID_FUSION: HybridFusion,
+    pub HYBRID_FUSION_SEMANTIC_WEIGHT: f32,
+    pub EMPTY_CONTENT_POLICY: EmptyContentPolicy,
+    pub EMPTY_CONTENT_FALLBACK_FIELD: String,
+    pub BM25_LANGUAGE: ContentLanguage,
+    pub EMBEDDING_QUERY_PREFIXES: Option<HashMap<String, String>>,
+    pub QUERY_LANGUAGE_DETECTION: bool,
+    pub EMBED_METADATA_FIELDS: Vec<String>,
+    pub EMBED_METADATA_FIELDS_IN_FULLTEXT: bool,
+    pub EMBEDDING_VECTOR_FIELDS: Vec<String>,
+    pub EMBEDDING_AUDIT_LOG_ENABLED: bool,
+    pub EMBEDDING_TIMEOUT_MS: Option<u64>,
+    pub EMBEDDING_BATCH_SIZE: usize,
+    pub BOOST_PHRASE_MAX_TOKENS: usize,
+    pub EMBEDDING_VECTOR_SLOT: DenseVectorSlot,
+    pub EMBEDDING_DUAL_WRITE_TARGET: Option<EmbeddingDualWriteTarget>,
+    pub BM25_TERM_TOKENS_ENABLED: bool,
+    pub BM25_TERM_TOKENS_MAX_ENTRIES: usize,
+    pub EMBEDDING_CHARS_PER_TOKEN: usize,
+    pub RERANKER_MAX_TOKENS: Option<usize>,
+    pub RERANK_TEMPLATE: Option<String>,
+    pub EMBEDDING_PROVIDER: EmbeddingProvider,
+    pub RERANK_HIGHLIGHT_WINDOWS: usize,
+    pub RERANK_HIGHLIGHT_WINDOW_SIZE: usize,
+    #[serde(skip_serializing)]
+    pub EMBEDDING_API_KEY: String,
+    pub BM25_MAX_TOKEN_LENGTH: usize,
+    pub BM25_STOPWORDS: bool,
+    pub BM25_STOPWORD_LIST: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
@@ -2465,7 +2910,7 @@ pub struct DatasetConfigurationDTO {
     pub SEMANTIC_ENABLED: Option<bool>,
     /// Whether or not to insert chunks into Postgres
     pub QDRANT_ONLY: Option<bool>,
-    /// The prefix to use for the embedding query
+    /// The prefix to use for the embedding query. Left empty at dataset creation, it is filled from the preset for known models such as bge and e5. Set it to "none" to use no prefix.
     pub EMBEDDING_QUERY_PREFIX: Option<String>,
     /// Whether to use the message to query prompt
     pub USE_MESSAGE_TO_QUERY_PROMPT: Option<bool>,
@@ -2491,6 +2936,109 @@ pub struct DatasetConfigurationDTO {
     pub PUBLIC_DATASET: Option<PublicDatasetOptions>,
     /// Whether to disable analytics
     pub DISABLE_ANALYTICS: Option<bool>,
+    /// The maximum number of input tokens the embedding model accepts, inputs are clipped to fit. OpenAI's embedding models are clipped at a real token boundary, other models by EMBEDDING_CHARS_PER_TOKEN. When unset OpenAI's models are clipped to their 8191 token limit, other models keep the 12000 character limit of document inputs and the 20000 character limit of queries.
+    pub EMBEDDING_MAX_TOKENS: Option<usize>,
+    /// Pooling strategy hint sent to the embedding server, the server default is used when unset
+    pub EMBEDDING_POOLING: Option<EmbeddingPooling>,
+    /// Template used to flatten a chunk into the text which gets embedded, e.g. "{title}. {description}. Tags: {tag_set}". Placeholders are filled from the chunk's metadata fields as well as content, link and tag_set, a metadata. prefix picks the metadata field of the same name and {{ and }} are literal braces. Not used for chunks with semantic_content set. If not set, the chunk content is embedded as is.
+    pub EMBEDDING_TEMPLATE: Option<String>,
+    /// Whether fulltext and semantic boosts passed in search requests are applied to the query vectors. Defaults to true.
+    pub APPLY_BOOSTS_TO_QUERIES: Option<bool>,
+    /// Whether fulltext and semantic boosts set on chunks are applied to the chunk vectors at ingest. Defaults to true.
+    pub APPLY_BOOSTS_TO_DOCUMENTS: Option<bool>,
+    /// Separator placed between the parts of multi-part content before it is embedded. Defaults to a newline.
+    pub EMBEDDING_PART_SEPARATOR: Option<String>,
+    /// Redact credit card numbers, SSNs, email addresses and any EMBEDDING_PII_PATTERNS matches from text before it is sent to the embedding server. Redaction changes the embedded text and lowers recall for queries mentioning the redacted values, so it is a compliance tradeoff. Defaults to false.
+    pub EMBEDDING_PII_REDACTION_ENABLED: Option<bool>,
+    /// Additional regex patterns which are redacted when EMBEDDING_PII_REDACTION_ENABLED is set.
+    pub EMBEDDING_PII_PATTERNS: Option<Vec<String>>,
+    /// Whether dense vectors are normalized to unit length. auto normalizes only for the cosine distance metric, set always to force normalization for other metrics. Defaults to auto.
+    pub DENSE_VECTOR_NORMALIZATION: Option<DenseNormalization>,
+    /// Quantization applied to dense vectors before they are stored or searched with. binary is incompatible with the euclidean and manhattan distance metrics, int8 stores every vector on an int8 grid with a per-vector scale. Doc and query vectors are quantized the same way. Defaults to none.
+    pub DENSE_VECTOR_QUANTIZATION: Option<DenseQuantization>,
+    /// Dimension dense vectors are truncated to, for matryoshka models like text-embedding-3-large or nomic-embed which return more dimensions than the dataset stores. Longer vectors are cut to this many components and L2 renormalized, vectors already at this dimension are left as they are. Must match EMBEDDING_SIZE, which defaults to it. Defaults to none, which stores vectors at the dimension the model returns.
+    pub EMBEDDING_DIMENSION: Option<usize>,
+    /// JSON pointer, e.g. /data, to the OpenAI style embeddings response inside the body returned by the embedding server. Set this when a gateway wraps the response in an envelope. When unset the body is parsed directly.
+    pub EMBEDDING_RESPONSE_POINTER: Option<String>,
+    /// JSON pointer, e.g. /data, to the rerank response inside the body returned by the reranker. Set this when a gateway wraps the response in an envelope. When unset the body is parsed directly.
+    pub RERANKER_RESPONSE_POINTER: Option<String>,
+    /// Round every dimension of dense vectors to this many decimal places before they are stored or searched with. Unset keeps full f32 precision.
+    pub DENSE_VECTOR_DECIMAL_PLACES: Option<u32>,
+    /// How reranker scores are normalized before being returned. clamp clips scores into [0, 1], sigmoid maps raw logits into (0, 1) and minmax scales each reranked batch onto [0, 1]. All of them keep the reranked order. Defaults to none, which returns the scores as is.
+    pub RERANKER_SCORE_NORMALIZATION: Option<RerankScoreNormalization>,
+    /// Store the vector of a chunk's semantic_boost phrase as its own named vector next to the content vector instead of adding it into the content vector. Search adds the phrase similarity times the distance_factor stored with the point to the content similarity, so the factor can change without re-embedding. On dot product datasets this ranks the same as the combined vector. Requires a collection created with distance phrase vectors. Defaults to false.
+    pub SEPARATE_DISTANCE_PHRASE_VECTORS: Option<bool>,
+    /// Round the scores of returned chunks to this many decimal places, so serialized scores are stable across platforms. Unset returns full f64 precision.
+    pub SCORE_DECIMAL_PLACES: Option<u32>,
+    /// Tokens shorter than this many characters after stemming are dropped from BM25 vectors. Applied to both documents and queries. Defaults to 1, which keeps every token.
+    pub BM25_MIN_TOKEN_LENGTH: Option<usize>,
+    /// Skip computing sparse vectors at ingest. They are computed on the first fulltext or hybrid search over the chunks, or by the sparse-backfill job. Chunks with a fulltext boost are still encoded at ingest.
+    pub LAZY_SPARSE_ENCODING: Option<bool>,
+    /// The prefix to use for embedding documents. Filled from the model preset like `EMBEDDING_QUERY_PREFIX`, set it to "none" to use no prefix.
+    pub EMBEDDING_DOC_PREFIX: Option<String>,
+    /// Weight of the dataset in the embedding rate limit it shares with other datasets using the same provider and api key. While others are waiting, its share of the budget is its weight over the total weight of the waiting datasets. Defaults to 1.
+    pub EMBEDDING_RATE_LIMIT_WEIGHT: Option<f64>,
+    /// Dense vectors with an L2 norm at or below this are rejected as degenerate (e.g. all zeros) and their chunks fail instead of being stored unsearchable. Defaults to 1e-6, set to 0 to only reject exact zero vectors.
+    pub EMBEDDING_MIN_NORM: Option<f64>,
+    /// Rejects dense vectors whose L2 norm is more than this factor above or below the running mean norm of the dataset, e.g. 4.0. Off by default so models which return unnormalized vectors are not blocked.
+    pub EMBEDDING_MAX_NORM_DEVIATION: Option<f64>,
+    /// How hybrid search merges the semantic and fulltext results before reranking, rrf or weighted_sum. The fused score is the retrieval score of a chunk. The cross encoder overwrites it, so it only decides the candidate order and the ranking of chunks which are not reranked. Defaults to rrf.
+    pub HYBRID_FUSION: Option<HybridFusion>,
+    /// Weight of the semantic results when fusing hybrid search results, between 0 and 1. The fulltext results get the remaining weight. Defaults to 0.5.
+    pub HYBRID_FUSION_SEMANTIC_WEIGHT: Option<f32>,
+    /// What ingestion does with chunks whose text is blank after removing html, e.g. a chunk which is only an image. reject fails the chunk, fallback embeds the alt and title attributes of the html or the metadata field EMPTY_CONTENT_FALLBACK_FIELD, metadata_only stores the chunk without dense or sparse vectors. Defaults to reject.
+    pub EMPTY_CONTENT_POLICY: Option<EmptyContentPolicy>,
+    /// Metadata field embedded for chunks with blank text when EMPTY_CONTENT_POLICY is fallback and the html has no alt or title attributes. Defaults to title.
+    pub EMPTY_CONTENT_FALLBACK_FIELD: Option<String>,
+    /// Language the bm25 tokenizer stems chunks and queries in, as an ISO 639-1 code. Searches only stem in another language when they set query_language. Defaults to en.
+    pub BM25_LANGUAGE: Option<ContentLanguage>,
+    /// Query prefixes by ISO 639-1 language code for models with language specific instructions. The prefix of the query's language replaces EMBEDDING_QUERY_PREFIX.
+    pub EMBEDDING_QUERY_PREFIXES: Option<HashMap<String, String>>,
+    /// Detect the language of queries which set no query_language and use its entry of EMBEDDING_QUERY_PREFIXES. The detected language doesn't change the bm25 stemmer, since chunks are stemmed in BM25_LANGUAGE. Defaults to false.
+    pub QUERY_LANGUAGE_DETECTION: Option<bool>,
+    /// Fields of a chunk's metadata which are appended to its content as "key: value" lines before it is embedded, in the order listed, e.g. ["brand", "category"]. Fields a chunk doesn't have are skipped. Defaults to none.
+    pub EMBED_METADATA_FIELDS: Option<Vec<String>>,
+    /// Also append the EMBED_METADATA_FIELDS to the text the sparse and BM25 vectors are made from. Defaults to false.
+    pub EMBED_METADATA_FIELDS_IN_FULLTEXT: Option<bool>,
+    /// Metadata fields embedded as their own named vectors next to the content vector, e.g. ["title", "body"]. Dense searches combine the similarity to each vector with the request's vector_field_weights. Chunks without a field skip its vector. Defaults to none.
+    pub EMBEDDING_VECTOR_FIELDS: Option<Vec<String>>,
+    /// Whether to record every call to the model servers in the embedding audit log, without the content sent. Defaults to false.
+    pub EMBEDDING_AUDIT_LOG_ENABLED: Option<bool>,
+    /// Timeout in milliseconds of each request to the embedding server, covering connecting and the whole response. A request which times out fails with a 408 instead of being retried. Defaults to 30000 for chunks and 5000 for queries.
+    pub EMBEDDING_TIMEOUT_MS: Option<u64>,
+    /// Most inputs sent in one request to the embedding and sparse servers. Dense requests are also capped by what the provider accepts. Defaults to 30.
+    pub EMBEDDING_BATCH_SIZE: Option<usize>,
+    /// Most estimated tokens allowed in the phrase of a chunk's fulltext_boost or semantic_boost. Chunks with a longer phrase are rejected with a 400, phrases which reach ingestion some other way are clipped to it. Defaults to 256.
+    pub BOOST_PHRASE_MAX_TOKENS: Option<usize>,
+    /// Which dense named vector search reads, flipped by the embedding model cutover. Defaults to primary.
+    pub EMBEDDING_VECTOR_SLOT: Option<DenseVectorSlot>,
+    /// Embedding model the dataset is migrating to. While set, ingestion also embeds chunks with this model and stores the vectors next to the active ones, which doubles embedding cost until the cutover.
+    pub EMBEDDING_DUAL_WRITE_TARGET: Option<EmbeddingDualWriteTarget>,
+    /// Whether to record which stems the BM25 term ids of ingested chunks were hashed from, so ids can be resolved back to readable tokens. Defaults to false.
+    pub BM25_TERM_TOKENS_ENABLED: Option<bool>,
+    /// Most term id and token pairs kept for the dataset when BM25_TERM_TOKENS_ENABLED is set, the least recently seen are dropped first. Defaults to 100000.
+    pub BM25_TERM_TOKENS_MAX_ENTRIES: Option<usize>,
+    /// Characters per token assumed when estimating the tokens of embedding inputs, boost phrases and rerank documents against their limits. Inputs of OpenAI's embedding models are counted with the model's own tokenizer instead. Lower it for text which tokenizes densely, such as code or CJK text. Defaults to 3.
+    pub EMBEDDING_CHARS_PER_TOKEN: Option<usize>,
+    /// Most tokens of each document sent to the reranker, longer documents are clipped at a token boundary. Defaults to none, which sends documents whole and leaves truncation to the reranker.
+    pub RERANKER_MAX_TOKENS: Option<usize>,
+    /// Template used to flatten a chunk into the text which is sent to the reranker, e.g. "{title} — {description}". Takes the same placeholders as EMBEDDING_TEMPLATE, the content placeholder being the chunk's text. Fields a chunk lacks render as empty and the chunk is flagged with rerank_template_incomplete. If not set, the chunk's text is reranked as is.
+    pub RERANK_TEMPLATE: Option<String>,
+    /// Schema of the embedding server at EMBEDDING_BASE_URL, either "openai" for OpenAI compatible servers, "cohere" for Cohere's embed API or "vertex" for Google's embedding models on Vertex AI. For "vertex", EMBEDDING_BASE_URL is the models path of the project and region, e.g. https://us-central1-aiplatform.googleapis.com/v1/projects/{project}/locations/us-central1/publishers/google/models, and EMBEDDING_MODEL_NAME the model, e.g. text-embedding-004. Defaults to "openai".
+    pub EMBEDDING_PROVIDER: Option<EmbeddingProvider>,
+    /// Number of overlapping windows the text of each reranked chunk on the returned page is split into. The windows are scored with the reranker and the best one is returned as the chunk's preferred_highlight. Defaults to 0, which disables it.
+    pub RERANK_HIGHLIGHT_WINDOWS: Option<usize>,
+    /// Length in chars of the windows scored for RERANK_HIGHLIGHT_WINDOWS. Defaults to 200.
+    pub RERANK_HIGHLIGHT_WINDOW_SIZE: Option<usize>,
+    #[serde(skip_serializing)]
+    /// The API key for the embedding and sparse servers, falls back to the server's OPENAI_API_KEY when empty. With the "vertex" EMBEDDING_PROVIDER this is the service account JSON key the access tokens are requested with, when empty the server's GOOGLE_APPLICATION_CREDENTIALS key file or else the metadata server of the instance is used, which is only allowed for an EMBEDDING_BASE_URL on https://{region}-aiplatform.googleapis.com.
+    pub EMBEDDING_API_KEY: Option<String>,
+    /// Words of this many bytes of UTF-8 or more are dropped from BM25 vectors before stemming. Applied to both documents and queries. Raise it for long compound words, e.g. German, or scripts of several bytes per character such as CJK. Defaults to 40.
+    pub BM25_MAX_TOKEN_LENGTH: Option<usize>,
+    /// Whether the BM25 tokenizer drops stopwords before stemming, so common words like "the" and "and" get no sparse dimension. Defaults to false.
+    pub BM25_STOPWORDS: Option<bool>,
+    /// Stopwords dropped when BM25_STOPWORDS is true. Defaults to the stopword list of BM25_LANGUAGE.
+    pub BM25_STOPWORD_LIST: Option<Vec<String>>,
 }
 
 impl From<DatasetConfigurationDTO> for DatasetConfiguration {
@@ -2506,7 +3054,7 @@ impl From<DatasetConfigurationDTO> for DatasetConfiguration {
             MESSAGE_TO_QUERY_PROMPT: dto.MESSAGE_TO_QUERY_PROMPT.unwrap_or("Write a 1-2 sentence semantic search query along the lines of a hypothetical response to: \n\n".to_string()),
             RAG_PROMPT: dto.RAG_PROMPT.unwrap_or("Use the following retrieved documents to respond briefly and accurately:".to_string()),
             N_RETRIEVALS_TO_INCLUDE: dto.N_RETRIEVALS_TO_INCLUDE.unwrap_or(8),
-            EMBEDDING_SIZE: dto.EMBEDDING_SIZE.unwrap_or(1536),
+            EMBEDDING_SIZE: dto.EMBEDDING_SIZE.or(dto.EMBEDDING_DIMENSION).unwrap_or(1536),
             DISTANCE_METRIC: dto.DISTANCE_METRIC.unwrap_or(DistanceMetric::Cosine),
             LLM_DEFAULT_MODEL: dto.LLM_DEFAULT_MODEL.unwrap_or("gpt-3.5-turbo-1106".to_string()),
             BM25_ENABLED: dto.BM25_ENABLED.unwrap_or(true),
@@ -2534,6 +3082,57 @@ impl From<DatasetConfigurationDTO> for DatasetConfiguration {
                 .unwrap_or_default()
             },
             DISABLE_ANALYTICS: dto.DISABLE_ANALYTICS.unwrap_or(false),
+            EMBEDDING_MAX_TOKENS: dto.EMBEDDING_MAX_TOKENS,
+            EMBEDDING_POOLING: dto.EMBEDDING_POOLING,
+            EMBEDDING_TEMPLATE: dto.EMBEDDING_TEMPLATE,
+            APPLY_BOOSTS_TO_QUERIES: dto.APPLY_BOOSTS_TO_QUERIES.unwrap_or(true),
+            APPLY_BOOSTS_TO_DOCUMENTS: dto.APPLY_BOOSTS_TO_DOCUMENTS.unwrap_or(true),
+            EMBEDDING_PART_SEPARATOR: dto.EMBEDDING_PART_SEPARATOR.unwrap_or("\n".to_string()),
+            EMBEDDING_PII_REDACTION_ENABLED: dto.EMBEDDING_PII_REDACTION_ENABLED.unwrap_or(false),
+            EMBEDDING_PII_PATTERNS: dto.EMBEDDING_PII_PATTERNS,
+            DENSE_VECTOR_NORMALIZATION: dto.DENSE_VECTOR_NORMALIZATION.unwrap_or(DenseNormalization::Auto),
+            DENSE_VECTOR_QUANTIZATION: dto.DENSE_VECTOR_QUANTIZATION.unwrap_or(DenseQuantization::None),
+            EMBEDDING_DIMENSION: dto.EMBEDDING_DIMENSION,
+            EMBEDDING_RESPONSE_POINTER: dto.EMBEDDING_RESPONSE_POINTER,
+            RERANKER_RESPONSE_POINTER: dto.RERANKER_RESPONSE_POINTER,
+            DENSE_VECTOR_DECIMAL_PLACES: dto.DENSE_VECTOR_DECIMAL_PLACES,
+            RERANKER_SCORE_NORMALIZATION: dto.RERANKER_SCORE_NORMALIZATION.unwrap_or(RerankScoreNormalization::None),
+            SEPARATE_DISTANCE_PHRASE_VECTORS: dto.SEPARATE_DISTANCE_PHRASE_VECTORS.unwrap_or(false),
+            SCORE_DECIMAL_PLACES: dto.SCORE_DECIMAL_PLACES,
+            BM25_MIN_TOKEN_LENGTH: dto.BM25_MIN_TOKEN_LENGTH.unwrap_or(1),
+            LAZY_SPARSE_ENCODING: dto.LAZY_SPARSE_ENCODING.unwrap_or(false),
+            EMBEDDING_DOC_PREFIX: dto.EMBEDDING_DOC_PREFIX.unwrap_or("".to_string()),
+            EMBEDDING_RATE_LIMIT_WEIGHT: dto.EMBEDDING_RATE_LIMIT_WEIGHT.unwrap_or(1.0),
+            EMBEDDING_MIN_NORM: dto.EMBEDDING_MIN_NORM.unwrap_or(1e-6),
+            EMBEDDING_MAX_NORM_DEVIATION: dto.EMBEDDING_MAX_NORM_DEVIATION,
+            HYBRID_FUSION: dto.HYBRID_FUSION.unwrap_or(HybridFusion::Rrf),
+            HYBRID_FUSION_SEMANTIC_WEIGHT: dto.HYBRID_FUSION_SEMANTIC_WEIGHT.unwrap_or(0.5),
+            EMPTY_CONTENT_POLICY: dto.EMPTY_CONTENT_POLICY.unwrap_or(EmptyContentPolicy::Reject),
+            EMPTY_CONTENT_FALLBACK_FIELD: dto.EMPTY_CONTENT_FALLBACK_FIELD.unwrap_or("title".to_string()),
+            BM25_LANGUAGE: dto.BM25_LANGUAGE.unwrap_or(ContentLanguage::English),
+            EMBEDDING_QUERY_PREFIXES: dto.EMBEDDING_QUERY_PREFIXES,
+            QUERY_LANGUAGE_DETECTION: dto.QUERY_LANGUAGE_DETECTION.unwrap_or(false),
+            EMBED_METADATA_FIELDS: dto.EMBED_METADATA_FIELDS.unwrap_or(vec![]),
+            EMBED_METADATA_FIELDS_IN_FULLTEXT: dto.EMBED_METADATA_FIELDS_IN_FULLTEXT.unwrap_or(false),
+            EMBEDDING_VECTOR_FIELDS: dto.EMBEDDING_VECTOR_FIELDS.unwrap_or(vec![]),
+            EMBEDDING_AUDIT_LOG_ENABLED: dto.EMBEDDING_AUDIT_LOG_ENABLED.unwrap_or(false),
+            EMBEDDING_TIMEOUT_MS: dto.EMBEDDING_TIMEOUT_MS,
+            EMBEDDING_BATCH_SIZE: dto.EMBEDDING_BATCH_SIZE.unwrap_or(30),
+            BOOST_PHRASE_MAX_TOKENS: dto.BOOST_PHRASE_MAX_TOKENS.unwrap_or(256),
+            EMBEDDING_VECTOR_SLOT: dto.EMBEDDING_VECTOR_SLOT.unwrap_or(DenseVectorSlot::Primary),
+            EMBEDDING_DUAL_WRITE_TARGET: dto.EMBEDDING_DUAL_WRITE_TARGET,
+            BM25_TERM_TOKENS_ENABLED: dto.BM25_TERM_TOKENS_ENABLED.unwrap_or(false),
+            BM25_TERM_TOKENS_MAX_ENTRIES: dto.BM25_TERM_TOKENS_MAX_ENTRIES.unwrap_or(100000),
+            EMBEDDING_CHARS_PER_TOKEN: dto.EMBEDDING_CHARS_PER_TOKEN.unwrap_or(3),
+            RERANKER_MAX_TOKENS: dto.RERANKER_MAX_TOKENS,
+            RERANK_TEMPLATE: dto.RERANK_TEMPLATE,
+            EMBEDDING_PROVIDER: dto.EMBEDDING_PROVIDER.unwrap_or(EmbeddingProvider::OpenAI),
+            RERANK_HIGHLIGHT_WINDOWS: dto.RERANK_HIGHLIGHT_WINDOWS.unwrap_or(0),
+            RERANK_HIGHLIGHT_WINDOW_SIZE: dto.RERANK_HIGHLIGHT_WINDOW_SIZE.unwrap_or(200),
+            EMBEDDING_API_KEY: dto.EMBEDDING_API_KEY.unwrap_or("".to_string()),
+            BM25_MAX_TOKEN_LENGTH: dto.BM25_MAX_TOKEN_LENGTH.unwrap_or(40),
+            BM25_STOPWORDS: dto.BM25_STOPWORDS.unwrap_or(false),
+            BM25_STOPWORD_LIST: dto.BM25_STOPWORD_LIST,
         }
     }
 }
@@ -2583,6 +3182,57 @@ impl From<DatasetConfiguration> for DatasetConfigurationDTO {
                 }),
             }),
             DISABLE_ANALYTICS: Some(config.DISABLE_ANALYTICS),
+            EMBEDDING_MAX_TOKENS: config.EMBEDDING_MAX_TOKENS,
+            EMBEDDING_POOLING: config.EMBEDDING_POOLING,
+            EMBEDDING_TEMPLATE: config.EMBEDDING_TEMPLATE,
+            APPLY_BOOSTS_TO_QUERIES: Some(config.APPLY_BOOSTS_TO_QUERIES),
+            APPLY_BOOSTS_TO_DOCUMENTS: Some(config.APPLY_BOOSTS_TO_DOCUMENTS),
+            EMBEDDING_PART_SEPARATOR: Some(config.EMBEDDING_PART_SEPARATOR),
+            EMBEDDING_PII_REDACTION_ENABLED: Some(config.EMBEDDING_PII_REDACTION_ENABLED),
+            EMBEDDING_PII_PATTERNS: config.EMBEDDING_PII_PATTERNS,
+            DENSE_VECTOR_NORMALIZATION: Some(config.DENSE_VECTOR_NORMALIZATION),
+            DENSE_VECTOR_QUANTIZATION: Some(config.DENSE_VECTOR_QUANTIZATION),
+            EMBEDDING_DIMENSION: config.EMBEDDING_DIMENSION,
+            EMBEDDING_RESPONSE_POINTER: config.EMBEDDING_RESPONSE_POINTER,
+            RERANKER_RESPONSE_POINTER: config.RERANKER_RESPONSE_POINTER,
+            DENSE_VECTOR_DECIMAL_PLACES: config.DENSE_VECTOR_DECIMAL_PLACES,
+            RERANKER_SCORE_NORMALIZATION: Some(config.RERANKER_SCORE_NORMALIZATION),
+            SEPARATE_DISTANCE_PHRASE_VECTORS: Some(config.SEPARATE_DISTANCE_PHRASE_VECTORS),
+            SCORE_DECIMAL_PLACES: config.SCORE_DECIMAL_PLACES,
+            BM25_MIN_TOKEN_LENGTH: Some(config.BM25_MIN_TOKEN_LENGTH),
+            LAZY_SPARSE_ENCODING: Some(config.LAZY_SPARSE_ENCODING),
+            EMBEDDING_DOC_PREFIX: Some(config.EMBEDDING_DOC_PREFIX),
+            EMBEDDING_RATE_LIMIT_WEIGHT: Some(config.EMBEDDING_RATE_LIMIT_WEIGHT),
+            EMBEDDING_MIN_NORM: Some(config.EMBEDDING_MIN_NORM),
+            EMBEDDING_MAX_NORM_DEVIATION: config.EMBEDDING_MAX_NORM_DEVIATION,
+            HYBRID_FUSION: Some(config.HYBRID_FUSION),
+            HYBRID_FUSION_SEMANTIC_WEIGHT: Some(config.HYBRID_FUSION_SEMANTIC_WEIGHT),
+            EMPTY_CONTENT_POLICY: Some(config.EMPTY_CONTENT_POLICY),
+            EMPTY_CONTENT_FALLBACK_FIELD: Some(config.EMPTY_CONTENT_FALLBACK_FIELD),
+            BM25_LANGUAGE: Some(config.BM25_LANGUAGE),
+            EMBEDDING_QUERY_PREFIXES: config.EMBEDDING_QUERY_PREFIXES,
+            QUERY_LANGUAGE_DETECTION: Some(config.QUERY_LANGUAGE_DETECTION),
+            EMBED_METADATA_FIELDS: Some(config.EMBED_METADATA_FIELDS),
+            EMBED_METADATA_FIELDS_IN_FULLTEXT: Some(config.EMBED_METADATA_FIELDS_IN_FULLTEXT),
+            EMBEDDING_VECTOR_FIELDS: Some(config.EMBEDDING_VECTOR_FIELDS),
+            EMBEDDING_AUDIT_LOG_ENABLED: Some(config.EMBEDDING_AUDIT_LOG_ENABLED),
+            EMBEDDING_TIMEOUT_MS: config.EMBEDDING_TIMEOUT_MS,
+            EMBEDDING_BATCH_SIZE: Some(config.EMBEDDING_BATCH_SIZE),
+            BOOST_PHRASE_MAX_TOKENS: Some(config.BOOST_PHRASE_MAX_TOKENS),
+            EMBEDDING_VECTOR_SLOT: Some(config.EMBEDDING_VECTOR_SLOT),
+            EMBEDDING_DUAL_WRITE_TARGET: config.EMBEDDING_DUAL_WRITE_TARGET,
+            BM25_TERM_TOKENS_ENABLED: Some(config.BM25_TERM_TOKENS_ENABLED),
+            BM25_TERM_TOKENS_MAX_ENTRIES: Some(config.BM25_TERM_TOKENS_MAX_ENTRIES),
+            EMBEDDING_CHARS_PER_TOKEN: Some(config.EMBEDDING_CHARS_PER_TOKEN),
+            RERANKER_MAX_TOKENS: config.RERANKER_MAX_TOKENS,
+            RERANK_TEMPLATE: config.RERANK_TEMPLATE,
+            EMBEDDING_PROVIDER: Some(config.EMBEDDING_PROVIDER),
+            RERANK_HIGHLIGHT_WINDOWS: Some(config.RERANK_HIGHLIGHT_WINDOWS),
+            RERANK_HIGHLIGHT_WINDOW_SIZE: Some(config.RERANK_HIGHLIGHT_WINDOW_SIZE),
+            EMBEDDING_API_KEY: Some(config.EMBEDDING_API_KEY),
+            BM25_MAX_TOKEN_LENGTH: Some(config.BM25_MAX_TOKEN_LENGTH),
+            BM25_STOPWORDS: Some(config.BM25_STOPWORDS),
+            BM25_STOPWORD_LIST: config.BM25_STOPWORD_LIST,
         }
     }
 }
@@ -2627,6 +3277,57 @@ impl Default for DatasetConfiguration {
                 extra_params: None,
             },
             DISABLE_ANALYTICS: false,
+            EMBEDDING_MAX_TOKENS: None,
+            EMBEDDING_POOLING: None,
+            EMBEDDING_TEMPLATE: None,
+            APPLY_BOOSTS_TO_QUERIES: true,
+            APPLY_BOOSTS_TO_DOCUMENTS: true,
+            EMBEDDING_PART_SEPARATOR: "\n".to_string(),
+            EMBEDDING_PII_REDACTION_ENABLED: false,
+            EMBEDDING_PII_PATTERNS: None,
+            DENSE_VECTOR_NORMALIZATION: DenseNormalization::Auto,
+            DENSE_VECTOR_QUANTIZATION: DenseQuantization::None,
+            EMBEDDING_DIMENSION: None,
+            EMBEDDING_RESPONSE_POINTER: None,
+            RERANKER_RESPONSE_POINTER: None,
+            DENSE_VECTOR_DECIMAL_PLACES: None,
+            RERANKER_SCORE_NORMALIZATION: RerankScoreNormalization::None,
+            SEPARATE_DISTANCE_PHRASE_VECTORS: false,
+            SCORE_DECIMAL_PLACES: None,
+            BM25_MIN_TOKEN_LENGTH: 1,
+            LAZY_SPARSE_ENCODING: false,
+            EMBEDDING_DOC_PREFIX: "".to_string(),
+            EMBEDDING_RATE_LIMIT_WEIGHT: 1.0,
+            EMBEDDING_MIN_NORM: 1e-6,
+            EMBEDDING_MAX_NORM_DEVIATION: None,
+            HYBRID_FUSION: HybridFusion::Rrf,
+            HYBRID_FUSION_SEMANTIC_WEIGHT: 0.5,
+            EMPTY_CONTENT_POLICY: EmptyContentPolicy::Reject,
+            EMPTY_CONTENT_FALLBACK_FIELD: "title".to_string(),
+            BM25_LANGUAGE: ContentLanguage::English,
+            EMBEDDING_QUERY_PREFIXES: None,
+            QUERY_LANGUAGE_DETECTION: false,
+            EMBED_METADATA_FIELDS: vec![],
+            EMBED_METADATA_FIELDS_IN_FULLTEXT: false,
+            EMBEDDING_VECTOR_FIELDS: vec![],
+            EMBEDDING_AUDIT_LOG_ENABLED: false,
+            EMBEDDING_TIMEOUT_MS: None,
+            EMBEDDING_BATCH_SIZE: 30,
+            BOOST_PHRASE_MAX_TOKENS: 256,
+            EMBEDDING_VECTOR_SLOT: DenseVectorSlot::Primary,
+            EMBEDDING_DUAL_WRITE_TARGET: None,
+            BM25_TERM_TOKENS_ENABLED: false,
+            BM25_TERM_TOKENS_MAX_ENTRIES: 100000,
+            EMBEDDING_CHARS_PER_TOKEN: 3,
+            RERANKER_MAX_TOKENS: None,
+            RERANK_TEMPLATE: None,
+            EMBEDDING_PROVIDER: EmbeddingProvider::OpenAI,
+            RERANK_HIGHLIGHT_WINDOWS: 0,
+            RERANK_HIGHLIGHT_WINDOW_SIZE: 200,
+            EMBEDDING_API_KEY: "".to_string(),
+            BM25_MAX_TOKEN_LENGTH: 40,
+            BM25_STOPWORDS: false,
+            BM25_STOPWORD_LIST: None,
         }
     }
 }
@@ -2913,6 +3614,225 @@ impl DatasetConfiguration {
                 .unwrap_or(&json!(false))
                 .as_bool()
                 .unwrap_or(false),
+            EMBEDDING_MAX_TOKENS: configuration
+                .get("EMBEDDING_MAX_TOKENS")
+                .and_then(|v| v.as_u64()).map(|u| u as usize),
+            EMBEDDING_POOLING: configuration
+                .get("EMBEDDING_POOLING")
+                .and_then(|v| serde_json::from_value(v.clone()).ok()),
+            EMBEDDING_TEMPLATE: configuration
+                .get("EMBEDDING_TEMPLATE")
+                .and_then(|v| v.as_str()).map(|s| s.to_string()),
+            APPLY_BOOSTS_TO_QUERIES: configuration
+                .get("APPLY_BOOSTS_TO_QUERIES")
+                .unwrap_or(&json!(true))
+                .as_bool()
+                .unwrap_or(true),
+            APPLY_BOOSTS_TO_DOCUMENTS: configuration
+                .get("APPLY_BOOSTS_TO_DOCUMENTS")
+                .unwrap_or(&json!(true))
+                .as_bool()
+                .unwrap_or(true),
+            EMBEDDING_PART_SEPARATOR: configuration
+                .get("EMBEDDING_PART_SEPARATOR")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+                .unwrap_or("\n".to_string()),
+            EMBEDDING_PII_REDACTION_ENABLED: configuration
+                .get("EMBEDDING_PII_REDACTION_ENABLED")
+                .unwrap_or(&json!(false))
+                .as_bool()
+                .unwrap_or(false),
+            EMBEDDING_PII_PATTERNS: configuration
+                .get("EMBEDDING_PII_PATTERNS")
+                .and_then(|v| serde_json::from_value(v.clone()).ok()),
+            DENSE_VECTOR_NORMALIZATION: configuration
+                .get("DENSE_VECTOR_NORMALIZATION")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or(DenseNormalization::Auto),
+            DENSE_VECTOR_QUANTIZATION: configuration
+                .get("DENSE_VECTOR_QUANTIZATION")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or(DenseQuantization::None),
+            EMBEDDING_DIMENSION: configuration
+                .get("EMBEDDING_DIMENSION")
+                .and_then(|v| v.as_u64()).map(|u| u as usize),
+            EMBEDDING_RESPONSE_POINTER: configuration
+                .get("EMBEDDING_RESPONSE_POINTER")
+                .and_then(|v| v.as_str()).map(|s| s.to_string()),
+            RERANKER_RESPONSE_POINTER: configuration
+                .get("RERANKER_RESPONSE_POINTER")
+                .and_then(|v| v.as_str()).map(|s| s.to_string()),
+            DENSE_VECTOR_DECIMAL_PLACES: configuration
+                .get("DENSE_VECTOR_DECIMAL_PLACES")
+                .and_then(|v| v.as_u64()).map(|u| u as u32),
+            RERANKER_SCORE_NORMALIZATION: configuration
+                .get("RERANKER_SCORE_NORMALIZATION")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or(RerankScoreNormalization::None),
+            SEPARATE_DISTANCE_PHRASE_VECTORS: configuration
+                .get("SEPARATE_DISTANCE_PHRASE_VECTORS")
+                .unwrap_or(&json!(false))
+                .as_bool()
+                .unwrap_or(false),
+            SCORE_DECIMAL_PLACES: configuration
+                .get("SCORE_DECIMAL_PLACES")
+                .and_then(|v| v.as_u64())
+                .map(|u| u as u32),
+            BM25_MIN_TOKEN_LENGTH: configuration
+                .get("BM25_MIN_TOKEN_LENGTH")
+                .unwrap_or(&json!(1))
+                .as_u64()
+                .map(|u| u as usize)
+                .unwrap_or(1),
+            LAZY_SPARSE_ENCODING: configuration
+                .get("LAZY_SPARSE_ENCODING")
+                .unwrap_or(&json!(false))
+                .as_bool()
+                .unwrap_or(false),
+            EMBEDDING_DOC_PREFIX: configuration
+                .get("EMBEDDING_DOC_PREFIX")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+                .unwrap_or("".to_string()),
+            EMBEDDING_RATE_LIMIT_WEIGHT: configuration
+                .get("EMBEDDING_RATE_LIMIT_WEIGHT")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(1.0),
+            EMBEDDING_MIN_NORM: configuration
+                .get("EMBEDDING_MIN_NORM")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(1e-6),
+            EMBEDDING_MAX_NORM_DEVIATION: configuration
+                .get("EMBEDDING_MAX_NORM_DEVIATION")
+                .and_then(|v| v.as_f64()),
+            HYBRID_FUSION: configuration
+                .get("HYBRID_FUSION")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or(HybridFusion::Rrf),
+            HYBRID_FUSION_SEMANTIC_WEIGHT: configuration
+                .get("HYBRID_FUSION_SEMANTIC_WEIGHT")
+                .and_then(|v| v.as_f64().map(|f| f as f32))
+                .unwrap_or(0.5),
+            EMPTY_CONTENT_POLICY: configuration
+                .get("EMPTY_CONTENT_POLICY")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or(EmptyContentPolicy::Reject),
+            EMPTY_CONTENT_FALLBACK_FIELD: configuration
+                .get("EMPTY_CONTENT_FALLBACK_FIELD")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+                .unwrap_or("title".to_string()),
+            BM25_LANGUAGE: configuration
+                .get("BM25_LANGUAGE")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or(ContentLanguage::English),
+            EMBEDDING_QUERY_PREFIXES: configuration
+                .get("EMBEDDING_QUERY_PREFIXES")
+                .and_then(|v| serde_json::from_value(v.clone()).ok()),
+            QUERY_LANGUAGE_DETECTION: configuration
+                .get("QUERY_LANGUAGE_DETECTION")
+                .unwrap_or(&json!(false))
+                .as_bool()
+                .unwrap_or(false),
+            EMBED_METADATA_FIELDS: configuration
+                .get("EMBED_METADATA_FIELDS")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or(vec![]),
+            EMBED_METADATA_FIELDS_IN_FULLTEXT: configuration
+                .get("EMBED_METADATA_FIELDS_IN_FULLTEXT")
+                .unwrap_or(&json!(false))
+                .as_bool()
+                .unwrap_or(false),
+            EMBEDDING_VECTOR_FIELDS: configuration
+                .get("EMBEDDING_VECTOR_FIELDS")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or(vec![]),
+            EMBEDDING_AUDIT_LOG_ENABLED: configuration
+                .get("EMBEDDING_AUDIT_LOG_ENABLED")
+                .unwrap_or(&json!(false))
+                .as_bool()
+                .unwrap_or(false),
+            EMBEDDING_TIMEOUT_MS: configuration
+                .get("EMBEDDING_TIMEOUT_MS")
+                .and_then(|v| v.as_u64()),
+            EMBEDDING_BATCH_SIZE: configuration
+                .get("EMBEDDING_BATCH_SIZE")
+                .unwrap_or(&json!(30))
+                .as_u64()
+                .map(|u| u as usize)
+                .unwrap_or(30),
+            BOOST_PHRASE_MAX_TOKENS: configuration
+                .get("BOOST_PHRASE_MAX_TOKENS")
+                .unwrap_or(&json!(256))
+                .as_u64()
+                .map(|u| u as usize)
+                .unwrap_or(256),
+            EMBEDDING_VECTOR_SLOT: configuration
+                .get("EMBEDDING_VECTOR_SLOT")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or(DenseVectorSlot::Primary),
+            EMBEDDING_DUAL_WRITE_TARGET: configuration
+                .get("EMBEDDING_DUAL_WRITE_TARGET")
+                .and_then(|v| serde_json::from_value(v.clone()).ok()),
+            BM25_TERM_TOKENS_ENABLED: configuration
+                .get("BM25_TERM_TOKENS_ENABLED")
+                .unwrap_or(&json!(false))
+                .as_bool()
+                .unwrap_or(false),
+            BM25_TERM_TOKENS_MAX_ENTRIES: configuration
+                .get("BM25_TERM_TOKENS_MAX_ENTRIES")
+                .unwrap_or(&json!(100000))
+                .as_u64()
+                .map(|u| u as usize)
+                .unwrap_or(100000),
+            EMBEDDING_CHARS_PER_TOKEN: configuration
+                .get("EMBEDDING_CHARS_PER_TOKEN")
+                .unwrap_or(&json!(3))
+                .as_u64()
+                .map(|u| u as usize)
+                .unwrap_or(3),
+            RERANKER_MAX_TOKENS: configuration
+                .get("RERANKER_MAX_TOKENS")
+                .and_then(|v| v.as_u64()).map(|u| u as usize),
+            RERANK_TEMPLATE: configuration
+                .get("RERANK_TEMPLATE")
+                .and_then(|v| v.as_str()).map(|s| s.to_string()),
+            EMBEDDING_PROVIDER: configuration
+                .get("EMBEDDING_PROVIDER")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or(EmbeddingProvider::OpenAI),
+            RERANK_HIGHLIGHT_WINDOWS: configuration
+                .get("RERANK_HIGHLIGHT_WINDOWS")
+                .unwrap_or(&json!(0))
+                .as_u64()
+                .map(|u| u as usize)
+                .unwrap_or(0),
+            RERANK_HIGHLIGHT_WINDOW_SIZE: configuration
+                .get("RERANK_HIGHLIGHT_WINDOW_SIZE")
+                .unwrap_or(&json!(200))
+                .as_u64()
+                .map(|u| u as usize)
+                .unwrap_or(200),
+            EMBEDDING_API_KEY: configuration
+                .get("EMBEDDING_API_KEY")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+                .unwrap_or("".to_string()),
+            BM25_MAX_TOKEN_LENGTH: configuration
+                .get("BM25_MAX_TOKEN_LENGTH")
+                .unwrap_or(&json!(40))
+                .as_u64()
+                .map(|u| u as usize)
+                .unwrap_or(40),
+            BM25_STOPWORDS: configuration
+                .get("BM25_STOPWORDS")
+                .unwrap_or(&json!(false))
+                .as_bool()
+                .unwrap_or(false),
+            BM25_STOPWORD_LIST: configuration
+                .get("BM25_STOPWORD_LIST")
+                .and_then(|v| serde_json::from_value(v.clone()).ok()),
         }
     }
 
@@ -2956,6 +3876,57 @@ impl DatasetConfiguration {
                 "extra_params": extra_params_json
             },
             "DISABLE_ANALYTICS": self.DISABLE_ANALYTICS,
+            "EMBEDDING_MAX_TOKENS": self.EMBEDDING_MAX_TOKENS,
+            "EMBEDDING_POOLING": self.EMBEDDING_POOLING,
+            "EMBEDDING_TEMPLATE": self.EMBEDDING_TEMPLATE,
+            "APPLY_BOOSTS_TO_QUERIES": self.APPLY_BOOSTS_TO_QUERIES,
+            "APPLY_BOOSTS_TO_DOCUMENTS": self.APPLY_BOOSTS_TO_DOCUMENTS,
+            "EMBEDDING_PART_SEPARATOR": self.EMBEDDING_PART_SEPARATOR,
+            "EMBEDDING_PII_REDACTION_ENABLED": self.EMBEDDING_PII_REDACTION_ENABLED,
+            "EMBEDDING_PII_PATTERNS": self.EMBEDDING_PII_PATTERNS,
+            "DENSE_VECTOR_NORMALIZATION": self.DENSE_VECTOR_NORMALIZATION,
+            "DENSE_VECTOR_QUANTIZATION": self.DENSE_VECTOR_QUANTIZATION,
+            "EMBEDDING_DIMENSION": self.EMBEDDING_DIMENSION,
+            "EMBEDDING_RESPONSE_POINTER": self.EMBEDDING_RESPONSE_POINTER,
+            "RERANKER_RESPONSE_POINTER": self.RERANKER_RESPONSE_POINTER,
+            "DENSE_VECTOR_DECIMAL_PLACES": self.DENSE_VECTOR_DECIMAL_PLACES,
+            "RERANKER_SCORE_NORMALIZATION": self.RERANKER_SCORE_NORMALIZATION,
+            "SEPARATE_DISTANCE_PHRASE_VECTORS": self.SEPARATE_DISTANCE_PHRASE_VECTORS,
+            "SCORE_DECIMAL_PLACES": self.SCORE_DECIMAL_PLACES,
+            "BM25_MIN_TOKEN_LENGTH": self.BM25_MIN_TOKEN_LENGTH,
+            "LAZY_SPARSE_ENCODING": self.LAZY_SPARSE_ENCODING,
+            "EMBEDDING_DOC_PREFIX": self.EMBEDDING_DOC_PREFIX,
+            "EMBEDDING_RATE_LIMIT_WEIGHT": self.EMBEDDING_RATE_LIMIT_WEIGHT,
+            "EMBEDDING_MIN_NORM": self.EMBEDDING_MIN_NORM,
+            "EMBEDDING_MAX_NORM_DEVIATION": self.EMBEDDING_MAX_NORM_DEVIATION,
+            "HYBRID_FUSION": self.HYBRID_FUSION,
+            "HYBRID_FUSION_SEMANTIC_WEIGHT": self.HYBRID_FUSION_SEMANTIC_WEIGHT,
+            "EMPTY_CONTENT_POLICY": self.EMPTY_CONTENT_POLICY,
+            "EMPTY_CONTENT_FALLBACK_FIELD": self.EMPTY_CONTENT_FALLBACK_FIELD,
+            "BM25_LANGUAGE": self.BM25_LANGUAGE,
+            "EMBEDDING_QUERY_PREFIXES": self.EMBEDDING_QUERY_PREFIXES,
+            "QUERY_LANGUAGE_DETECTION": self.QUERY_LANGUAGE_DETECTION,
+            "EMBED_METADATA_FIELDS": self.EMBED_METADATA_FIELDS,
+            "EMBED_METADATA_FIELDS_IN_FULLTEXT": self.EMBED_METADATA_FIELDS_IN_FULLTEXT,
+            "EMBEDDING_VECTOR_FIELDS": self.EMBEDDING_VECTOR_FIELDS,
+            "EMBEDDING_AUDIT_LOG_ENABLED": self.EMBEDDING_AUDIT_LOG_ENABLED,
+            "EMBEDDING_TIMEOUT_MS": self.EMBEDDING_TIMEOUT_MS,
+            "EMBEDDING_BATCH_SIZE": self.EMBEDDING_BATCH_SIZE,
+            "BOOST_PHRASE_MAX_TOKENS": self.BOOST_PHRASE_MAX_TOKENS,
+            "EMBEDDING_VECTOR_SLOT": self.EMBEDDING_VECTOR_SLOT,
+            "EMBEDDING_DUAL_WRITE_TARGET": self.EMBEDDING_DUAL_WRITE_TARGET,
+            "BM25_TERM_TOKENS_ENABLED": self.BM25_TERM_TOKENS_ENABLED,
+            "BM25_TERM_TOKENS_MAX_ENTRIES": self.BM25_TERM_TOKENS_MAX_ENTRIES,
+            "EMBEDDING_CHARS_PER_TOKEN": self.EMBEDDING_CHARS_PER_TOKEN,
+            "RERANKER_MAX_TOKENS": self.RERANKER_MAX_TOKENS,
+            "RERANK_TEMPLATE": self.RERANK_TEMPLATE,
+            "EMBEDDING_PROVIDER": self.EMBEDDING_PROVIDER,
+            "RERANK_HIGHLIGHT_WINDOWS": self.RERANK_HIGHLIGHT_WINDOWS,
+            "RERANK_HIGHLIGHT_WINDOW_SIZE": self.RERANK_HIGHLIGHT_WINDOW_SIZE,
+            "EMBEDDING_API_KEY": self.EMBEDDING_API_KEY,
+            "BM25_MAX_TOKEN_LENGTH": self.BM25_MAX_TOKEN_LENGTH,
+            "BM25_STOPWORDS": self.BM25_STOPWORDS,
+            "BM25_STOPWORD_LIST": self.BM25_STOPWORD_LIST,
         })
     }
 }
@@ -3185,6 +4156,174 @@ impl DatasetConfigurationDTO {
             DISABLE_ANALYTICS: self
                 .DISABLE_ANALYTICS
                 .unwrap_or(curr_dataset_config.DISABLE_ANALYTICS),
+            EMBEDDING_MAX_TOKENS: self
+                .EMBEDDING_MAX_TOKENS
+                .or(curr_dataset_config.EMBEDDING_MAX_TOKENS),
+            EMBEDDING_POOLING: self
+                .EMBEDDING_POOLING
+                .clone()
+                .or(curr_dataset_config.EMBEDDING_POOLING),
+            EMBEDDING_TEMPLATE: self
+                .EMBEDDING_TEMPLATE
+                .clone()
+                .or(curr_dataset_config.EMBEDDING_TEMPLATE),
+            APPLY_BOOSTS_TO_QUERIES: self
+                .APPLY_BOOSTS_TO_QUERIES
+                .unwrap_or(curr_dataset_config.APPLY_BOOSTS_TO_QUERIES),
+            APPLY_BOOSTS_TO_DOCUMENTS: self
+                .APPLY_BOOSTS_TO_DOCUMENTS
+                .unwrap_or(curr_dataset_config.APPLY_BOOSTS_TO_DOCUMENTS),
+            EMBEDDING_PART_SEPARATOR: self
+                .EMBEDDING_PART_SEPARATOR
+                .clone()
+                .unwrap_or(curr_dataset_config.EMBEDDING_PART_SEPARATOR),
+            EMBEDDING_PII_REDACTION_ENABLED: self
+                .EMBEDDING_PII_REDACTION_ENABLED
+                .unwrap_or(curr_dataset_config.EMBEDDING_PII_REDACTION_ENABLED),
+            EMBEDDING_PII_PATTERNS: self
+                .EMBEDDING_PII_PATTERNS
+                .clone()
+                .or(curr_dataset_config.EMBEDDING_PII_PATTERNS),
+            DENSE_VECTOR_NORMALIZATION: self
+                .DENSE_VECTOR_NORMALIZATION
+                .unwrap_or(curr_dataset_config.DENSE_VECTOR_NORMALIZATION),
+            DENSE_VECTOR_QUANTIZATION: self
+                .DENSE_VECTOR_QUANTIZATION
+                .unwrap_or(curr_dataset_config.DENSE_VECTOR_QUANTIZATION),
+            EMBEDDING_DIMENSION: self
+                .EMBEDDING_DIMENSION
+                .or(curr_dataset_config.EMBEDDING_DIMENSION),
+            EMBEDDING_RESPONSE_POINTER: self
+                .EMBEDDING_RESPONSE_POINTER
+                .clone()
+                .or(curr_dataset_config.EMBEDDING_RESPONSE_POINTER),
+            RERANKER_RESPONSE_POINTER: self
+                .RERANKER_RESPONSE_POINTER
+                .clone()
+                .or(curr_dataset_config.RERANKER_RESPONSE_POINTER),
+            DENSE_VECTOR_DECIMAL_PLACES: self
+                .DENSE_VECTOR_DECIMAL_PLACES
+                .or(curr_dataset_config.DENSE_VECTOR_DECIMAL_PLACES),
+            RERANKER_SCORE_NORMALIZATION: self
+                .RERANKER_SCORE_NORMALIZATION
+                .unwrap_or(curr_dataset_config.RERANKER_SCORE_NORMALIZATION),
+            SEPARATE_DISTANCE_PHRASE_VECTORS: self
+                .SEPARATE_DISTANCE_PHRASE_VECTORS
+                .unwrap_or(curr_dataset_config.SEPARATE_DISTANCE_PHRASE_VECTORS),
+            SCORE_DECIMAL_PLACES: self
+                .SCORE_DECIMAL_PLACES
+                .or(curr_dataset_config.SCORE_DECIMAL_PLACES),
+            BM25_MIN_TOKEN_LENGTH: self
+                .BM25_MIN_TOKEN_LENGTH
+                .unwrap_or(curr_dataset_config.BM25_MIN_TOKEN_LENGTH),
+            LAZY_SPARSE_ENCODING: self
+                .LAZY_SPARSE_ENCODING
+                .unwrap_or(curr_dataset_config.LAZY_SPARSE_ENCODING),
+            EMBEDDING_DOC_PREFIX: self
+                .EMBEDDING_DOC_PREFIX
+                .clone()
+                .unwrap_or(curr_dataset_config.EMBEDDING_DOC_PREFIX),
+            EMBEDDING_RATE_LIMIT_WEIGHT: self
+                .EMBEDDING_RATE_LIMIT_WEIGHT
+                .unwrap_or(curr_dataset_config.EMBEDDING_RATE_LIMIT_WEIGHT),
+            EMBEDDING_MIN_NORM: self
+                .EMBEDDING_MIN_NORM
+                .unwrap_or(curr_dataset_config.EMBEDDING_MIN_NORM),
+            EMBEDDING_MAX_NORM_DEVIATION: self
+                .EMBEDDING_MAX_NORM_DEVIATION
+                .or(curr_dataset_config.EMBEDDING_MAX_NORM_DEVIATION),
+            HYBRID_FUSION: self
+                .HYBRID_FUSION
+                .unwrap_or(curr_dataset_config.HYBRID_FUSION),
+            HYBRID_FUSION_SEMANTIC_WEIGHT: self
+                .HYBRID_FUSION_SEMANTIC_WEIGHT
+                .unwrap_or(curr_dataset_config.HYBRID_FUSION_SEMANTIC_WEIGHT),
+            EMPTY_CONTENT_POLICY: self
+                .EMPTY_CONTENT_POLICY
+                .unwrap_or(curr_dataset_config.EMPTY_CONTENT_POLICY),
+            EMPTY_CONTENT_FALLBACK_FIELD: self
+                .EMPTY_CONTENT_FALLBACK_FIELD
+                .clone()
+                .unwrap_or(curr_dataset_config.EMPTY_CONTENT_FALLBACK_FIELD),
+            BM25_LANGUAGE: self
+                .BM25_LANGUAGE
+                .unwrap_or(curr_dataset_config.BM25_LANGUAGE),
+            EMBEDDING_QUERY_PREFIXES: self
+                .EMBEDDING_QUERY_PREFIXES
+                .clone()
+                .or(curr_dataset_config.EMBEDDING_QUERY_PREFIXES),
+            QUERY_LANGUAGE_DETECTION: self
+                .QUERY_LANGUAGE_DETECTION
+                .unwrap_or(curr_dataset_config.QUERY_LANGUAGE_DETECTION),
+            EMBED_METADATA_FIELDS: self
+                .EMBED_METADATA_FIELDS
+                .clone()
+                .unwrap_or(curr_dataset_config.EMBED_METADATA_FIELDS),
+            EMBED_METADATA_FIELDS_IN_FULLTEXT: self
+                .EMBED_METADATA_FIELDS_IN_FULLTEXT
+                .unwrap_or(curr_dataset_config.EMBED_METADATA_FIELDS_IN_FULLTEXT),
+            EMBEDDING_VECTOR_FIELDS: self
+                .EMBEDDING_VECTOR_FIELDS
+                .clone()
+                .unwrap_or(curr_dataset_config.EMBEDDING_VECTOR_FIELDS),
+            EMBEDDING_AUDIT_LOG_ENABLED: self
+                .EMBEDDING_AUDIT_LOG_ENABLED
+                .unwrap_or(curr_dataset_config.EMBEDDING_AUDIT_LOG_ENABLED),
+            EMBEDDING_TIMEOUT_MS: self
+                .EMBEDDING_TIMEOUT_MS
+                .or(curr_dataset_config.EMBEDDING_TIMEOUT_MS),
+            EMBEDDING_BATCH_SIZE: self
+                .EMBEDDING_BATCH_SIZE
+                .unwrap_or(curr_dataset_config.EMBEDDING_BATCH_SIZE),
+            BOOST_PHRASE_MAX_TOKENS: self
+                .BOOST_PHRASE_MAX_TOKENS
+                .unwrap_or(curr_dataset_config.BOOST_PHRASE_MAX_TOKENS),
+            EMBEDDING_VECTOR_SLOT: self
+                .EMBEDDING_VECTOR_SLOT
+                .unwrap_or(curr_dataset_config.EMBEDDING_VECTOR_SLOT),
+            EMBEDDING_DUAL_WRITE_TARGET: self
+                .EMBEDDING_DUAL_WRITE_TARGET
+                .clone()
+                .or(curr_dataset_config.EMBEDDING_DUAL_WRITE_TARGET),
+            BM25_TERM_TOKENS_ENABLED: self
+                .BM25_TERM_TOKENS_ENABLED
+                .unwrap_or(curr_dataset_config.BM25_TERM_TOKENS_ENABLED),
+            BM25_TERM_TOKENS_MAX_ENTRIES: self
+                .BM25_TERM_TOKENS_MAX_ENTRIES
+                .unwrap_or(curr_dataset_config.BM25_TERM_TOKENS_MAX_ENTRIES),
+            EMBEDDING_CHARS_PER_TOKEN: self
+                .EMBEDDING_CHARS_PER_TOKEN
+                .unwrap_or(curr_dataset_config.EMBEDDING_CHARS_PER_TOKEN),
+            RERANKER_MAX_TOKENS: self
+                .RERANKER_MAX_TOKENS
+                .or(curr_dataset_config.RERANKER_MAX_TOKENS),
+            RERANK_TEMPLATE: self
+                .RERANK_TEMPLATE
+                .clone()
+                .or(curr_dataset_config.RERANK_TEMPLATE),
+            EMBEDDING_PROVIDER: self
+                .EMBEDDING_PROVIDER
+                .unwrap_or(curr_dataset_config.EMBEDDING_PROVIDER),
+            RERANK_HIGHLIGHT_WINDOWS: self
+                .RERANK_HIGHLIGHT_WINDOWS
+                .unwrap_or(curr_dataset_config.RERANK_HIGHLIGHT_WINDOWS),
+            RERANK_HIGHLIGHT_WINDOW_SIZE: self
+                .RERANK_HIGHLIGHT_WINDOW_SIZE
+                .unwrap_or(curr_dataset_config.RERANK_HIGHLIGHT_WINDOW_SIZE),
+            EMBEDDING_API_KEY: self
+                .EMBEDDING_API_KEY
+                .clone()
+                .unwrap_or(curr_dataset_config.EMBEDDING_API_KEY),
+            BM25_MAX_TOKEN_LENGTH: self
+                .BM25_MAX_TOKEN_LENGTH
+                .unwrap_or(curr_dataset_config.BM25_MAX_TOKEN_LENGTH),
+            BM25_STOPWORDS: self
+                .BM25_STOPWORDS
+                .unwrap_or(curr_dataset_config.BM25_STOPWORDS),
+            BM25_STOPWORD_LIST: self
+                .BM25_STOPWORD_LIST
+                .clone()
+                .or(curr_dataset_config.BM25_STOPWORD_LIST),
         }
     }
 }
@@ -3699,6 +4838,12 @@ impl ApiKeyRequestParams {
             remove_stop_words: self.remove_stop_words.or(payload.remove_stop_words),
             user_id: payload.user_id,
             typo_options: self.typo_options.or(payload.typo_options),
+            query_vector: payload.query_vector,
+            query_sparse_vector: payload.query_sparse_vector,
+            latency_budget_ms: payload.latency_budget_ms,
+            fusion_options: payload.fusion_options,
+            query_language: payload.query_language,
+            vector_field_weights: payload.vector_field_weights,
         }
     }
 
@@ -3761,6 +4906,7 @@ impl ApiKeyRequestParams {
             user_id: payload.user_id,
             typo_options: self.typo_options.or(payload.typo_options),
             sort_options: payload.sort_options,
+            scoring_options: payload.scoring_options,
         }
     }
 
@@ -3778,6 +4924,7 @@ impl ApiKeyRequestParams {
             group_tracking_id: payload.group_tracking_id,
             search_type: self.search_type.unwrap_or(payload.search_type),
             sort_options: payload.sort_options,
+            scoring_options: payload.scoring_options,
             highlight_options: self.highlight_options.or(payload.highlight_options),
             score_threshold: self.score_threshold.or(payload.score_threshold),
             slim_chunks: self.slim_chunks.or(payload.slim_chunks),
@@ -5640,6 +6787,132 @@ impl From<ChunkBoost> for ChunkBoostChangeset {
     }
 }
 
+/// A chunk whose embedding the model server kept rejecting. It is not retried until it is re-driven.
+#[derive(Debug, Serialize, Deserialize, Selectable, Queryable, Insertable, Clone, ToSchema)]
+#[schema(example = json!({
+    "id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "dataset_id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "chunk_id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "tracking_id": "tracking_id",
+    "attempts": 3,
+    "error_message": "Input contains unsupported content",
+    "ingestion_message": {},
+    "created_at": "2021-01-01 00:00:00.000",
+}))]
+#[diesel(table_name = embedding_dead_letters)]
+pub struct EmbeddingDeadLetter {
+    pub id: uuid::Uuid,
+    pub dataset_id: uuid::Uuid,
+    pub chunk_id: uuid::Uuid,
+    pub tracking_id: Option<String>,
+    /// Number of times the model server rejected the chunk
+    pub attempts: i32,
+    /// Error returned by the model server on the last attempt
+    pub error_message: String,
+    /// The queued chunk, used to re-drive it
+    pub ingestion_message: serde_json::Value,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+impl EmbeddingDeadLetter {
+    pub fn from_details(
+        dataset_id: uuid::Uuid,
+        chunk_id: uuid::Uuid,
+        tracking_id: Option<String>,
+        attempts: i32,
+        error_message: String,
+        ingestion_message: serde_json::Value,
+    ) -> Self {
+        EmbeddingDeadLetter {
+            id: uuid::Uuid::new_v4(),
+            dataset_id,
+            chunk_id,
+            tracking_id,
+            attempts,
+            error_message,
+            ingestion_message,
+            created_at: chrono::Utc::now().naive_local(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Selectable, Queryable, Insertable, Clone, ToSchema)]
+#[schema(example = json!({
+    "id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "dataset_id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "operation": "embedding",
+    "origin": "https://api.openai.com",
+    "item_count": 2,
+    "total_chars": 120,
+    "content_digest": "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+    "outcome": "success",
+    "created_at": "2021-01-01 00:00:00.000",
+}))]
+#[diesel(table_name = embedding_audit_log)]
+pub struct EmbeddingAuditEntry {
+    pub id: uuid::Uuid,
+    pub dataset_id: uuid::Uuid,
+    /// Kind of model call, one of `embedding`, `sparse_embedding` or `rerank`
+    pub operation: String,
+    /// Scheme, host and port of the model server, without path or credentials
+    pub origin: String,
+    /// Number of texts sent
+    pub item_count: i32,
+    /// Characters across all texts sent
+    pub total_chars: i64,
+    /// blake3 digest of the texts sent, the texts themselves are never stored
+    pub content_digest: String,
+    /// One of `success`, `unavailable`, `bad_response`, `rejected`, `timeout` or `error`
+    pub outcome: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+impl EmbeddingAuditEntry {
+    pub fn from_details(
+        dataset_id: uuid::Uuid,
+        operation: String,
+        origin: String,
+        item_count: i32,
+        total_chars: i64,
+        content_digest: String,
+        outcome: String,
+    ) -> Self {
+        EmbeddingAuditEntry {
+            id: uuid::Uuid::new_v4(),
+            dataset_id,
+            operation,
+            origin,
+            item_count,
+            total_chars,
+            content_digest,
+            outcome,
+            created_at: chrono::Utc::now().naive_local(),
+        }
+    }
+}
+
+/// A stem a dataset's BM25 term id was hashed from. Ids can collide, so an id may have several.
+#[derive(Debug, Serialize, Deserialize, Selectable, Queryable, Insertable, Clone)]
+#[diesel(table_name = bm25_term_tokens)]
+pub struct Bm25TermToken {
+    pub dataset_id: uuid::Uuid,
+    /// The term id, a `u32` widened to fit postgres' signed integers
+    pub term_id: i64,
+    pub token: String,
+    pub last_seen_at: chrono::NaiveDateTime,
+}
+
+impl Bm25TermToken {
+    pub fn from_details(dataset_id: uuid::Uuid, term_id: u32, token: String) -> Self {
+        Bm25TermToken {
+            dataset_id,
+            term_id: term_id as i64,
+            token,
+            last_seen_at: chrono::Utc::now().naive_local(),
+        }
+    }
+}
+
 #[derive(Debug, ToSchema, Serialize, Deserialize, Row)]
 #[schema(example = json!({
     "search_type": "search",
@@ -6558,6 +7831,8 @@ pub enum EventTypeRequest {
     FileUploadFailed,
     #[display(fmt = "chunks_uploaded")]
     ChunksUploaded,
+    #[display(fmt = "chunk_preprocessed")]
+    ChunkPreprocessed,
     #[display(fmt = "chunk_action_failed")]
     ChunkActionFailed,
     #[display(fmt = "chunk_updated")]
@@ -7109,6 +8384,12 @@ impl<'de> Deserialize<'de> for SearchChunksReqPayload {
             remove_stop_words: Option<bool>,
             user_id: Option<String>,
             typo_options: Option<TypoOptions>,
+            query_vector: Option<Vec<f32>>,
+            query_sparse_vector: Option<Vec<(u32, f32)>>,
+            latency_budget_ms: Option<u64>,
+            fusion_options: Option<FusionOptions>,
+            query_language: Option<ContentLanguage>,
+            vector_field_weights: Option<HashMap<String, f32>>,
             #[serde(flatten)]
             other: std::collections::HashMap<String, serde_json::Value>,
         }
@@ -7141,6 +8422,12 @@ impl<'de> Deserialize<'de> for SearchChunksReqPayload {
             remove_stop_words: helper.remove_stop_words,
             user_id: helper.user_id,
             typo_options: helper.typo_options,
+            query_vector: helper.query_vector,
+            query_sparse_vector: helper.query_sparse_vector,
+            latency_budget_ms: helper.latency_budget_ms,
+            fusion_options: helper.fusion_options,
+            query_language: helper.query_language,
+            vector_field_weights: helper.vector_field_weights,
         })
     }
 }
@@ -7218,6 +8505,7 @@ impl<'de> Deserialize<'de> for SearchWithinGroupReqPayload {
             get_total_pages: Option<bool>,
             filters: Option<ChunkFilter>,
             sort_options: Option<SortOptions>,
+            scoring_options: Option<ScoringOptions>,
             highlight_options: Option<HighlightOptions>,
             score_threshold: Option<f32>,
             slim_chunks: Option<bool>,
@@ -7251,6 +8539,7 @@ impl<'de> Deserialize<'de> for SearchWithinGroupReqPayload {
             get_total_pages: helper.get_total_pages,
             filters: helper.filters,
             sort_options,
+            scoring_options: helper.scoring_options,
             highlight_options,
             score_threshold: helper.score_threshold,
             slim_chunks: helper.slim_chunks,
@@ -7285,6 +8574,7 @@ impl<'de> Deserialize<'de> for SearchOverGroupsReqPayload {
             user_id: Option<String>,
             typo_options: Option<TypoOptions>,
             sort_options: Option<SortOptions>,
+            scoring_options: Option<ScoringOptions>,
             #[serde(flatten)]
             other: std::collections::HashMap<String, serde_json::Value>,
         }
@@ -7313,6 +8603,7 @@ impl<'de> Deserialize<'de> for SearchOverGroupsReqPayload {
             use_quote_negated_terms: helper.use_quote_negated_terms,
             typo_options: helper.typo_options,
             sort_options,
+            scoring_options: helper.scoring_options,
             remove_stop_words: helper.remove_stop_words,
             user_id: helper.user_id,
         })
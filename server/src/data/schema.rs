@@ -1,5 +1,14 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    bm25_term_tokens (dataset_id, term_id, token) {
+        dataset_id -> Uuid,
+        term_id -> Int8,
+        token -> Text,
+        last_seen_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     chunk_boosts (chunk_id) {
         chunk_id -> Uuid,
@@ -120,6 +129,33 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    embedding_audit_log (id) {
+        id -> Uuid,
+        dataset_id -> Uuid,
+        operation -> Text,
+        origin -> Text,
+        item_count -> Int4,
+        total_chars -> Int8,
+        content_digest -> Text,
+        outcome -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    embedding_dead_letters (id) {
+        id -> Uuid,
+        dataset_id -> Uuid,
+        chunk_id -> Uuid,
+        tracking_id -> Nullable<Text>,
+        attempts -> Int4,
+        error_message -> Text,
+        ingestion_message -> Jsonb,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     files (id) {
         id -> Uuid,
@@ -307,6 +343,7 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(bm25_term_tokens -> datasets (dataset_id));
 diesel::joinable!(chunk_boosts -> chunk_metadata (chunk_id));
 diesel::joinable!(chunk_group -> datasets (dataset_id));
 diesel::joinable!(chunk_group_bookmarks -> chunk_group (group_id));
@@ -319,6 +356,8 @@ diesel::joinable!(dataset_event_counts -> datasets (dataset_uuid));
 diesel::joinable!(dataset_tags -> datasets (dataset_id));
 diesel::joinable!(dataset_usage_counts -> datasets (dataset_id));
 diesel::joinable!(datasets -> organizations (organization_id));
+diesel::joinable!(embedding_audit_log -> datasets (dataset_id));
+diesel::joinable!(embedding_dead_letters -> datasets (dataset_id));
 diesel::joinable!(files -> datasets (dataset_id));
 diesel::joinable!(groups_from_files -> chunk_group (group_id));
 diesel::joinable!(groups_from_files -> files (file_id));
@@ -335,6 +374,7 @@ diesel::joinable!(user_organizations -> organizations (organization_id));
 diesel::joinable!(user_organizations -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    bm25_term_tokens,
     chunk_boosts,
     chunk_group,
     chunk_group_bookmarks,
@@ -346,6 +386,8 @@ diesel::allow_tables_to_appear_in_same_query!(
     dataset_tags,
     dataset_usage_counts,
     datasets,
+    embedding_audit_log,
+    embedding_dead_letters,
     files,
     groups_from_files,
     invitations,
@@ -1,5 +1,6 @@
 use actix_web::{
     error::{JsonPayloadError, ResponseError},
+    http::StatusCode,
     HttpResponse,
 };
 use derive_more::Display;
@@ -23,6 +24,17 @@ pub enum ServiceError {
     #[display(fmt = "BadRequest: {_0}")]
     BadRequest(String),
 
+    #[display(fmt = "BadRequest: Model server rejected the request: {_0}")]
+    ModelRejected(String),
+
+    /// A model server could not be reached or is temporarily unable to serve requests
+    #[display(fmt = "Service Unavailable: {_0}")]
+    UpstreamUnavailable(String),
+
+    /// A model server answered with a response that can't be used
+    #[display(fmt = "Bad Gateway: {_0}")]
+    UpstreamBadResponse(String),
+
     #[display(fmt = "BadRequest: Duplicate Tracking Id Found")]
     DuplicateTrackingId(String),
 
@@ -46,49 +58,48 @@ pub enum ServiceError {
 
 // impl ResponseError trait allows to convert our errors into http responses with appropriate data
 impl ResponseError for ServiceError {
-    fn error_response(&self) -> HttpResponse {
+    fn status_code(&self) -> StatusCode {
         match self {
-            ServiceError::InternalServerError(ref message) => HttpResponse::InternalServerError()
-                .json(ErrorResponseBody {
-                    message: message.to_string(),
-                }),
-            ServiceError::BadRequest(ref message) => {
-                HttpResponse::BadRequest().json(ErrorResponseBody {
-                    message: message.to_string(),
-                })
+            ServiceError::InternalServerError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ServiceError::BadRequest(_)
+            | ServiceError::ModelRejected(_)
+            | ServiceError::DuplicateTrackingId(_)
+            | ServiceError::JsonDeserializeError(_) => StatusCode::BAD_REQUEST,
+            ServiceError::UpstreamUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ServiceError::UpstreamBadResponse(_) => StatusCode::BAD_GATEWAY,
+            ServiceError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ServiceError::Forbidden => StatusCode::FORBIDDEN,
+            ServiceError::NotFound(_) => StatusCode::NOT_FOUND,
+            ServiceError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ServiceError::RequestTimeout(_) => StatusCode::REQUEST_TIMEOUT,
+        }
+    }
+
+    // Every variant answers with an `ErrorResponseBody`, so search and ingest errors have the
+    // same shape
+    fn error_response(&self) -> HttpResponse {
+        let message = match self {
+            ServiceError::InternalServerError(ref message)
+            | ServiceError::BadRequest(ref message)
+            | ServiceError::UpstreamUnavailable(ref message)
+            | ServiceError::UpstreamBadResponse(ref message)
+            | ServiceError::RequestTimeout(ref message)
+            | ServiceError::PayloadTooLarge(ref message) => message.to_string(),
+            ServiceError::ModelRejected(ref message) => {
+                format!("Model server rejected the request: {}", message)
             }
             ServiceError::DuplicateTrackingId(ref id) => {
-                HttpResponse::BadRequest().json(ErrorResponseBody {
-                    message: format!("Stoped overwriting data, Duplicate Tracking Id {:?}", id),
-                })
-            }
-            ServiceError::Unauthorized => HttpResponse::Unauthorized().json(ErrorResponseBody {
-                message: "Unauthorized".to_string(),
-            }),
-            ServiceError::Forbidden => HttpResponse::Forbidden().json(ErrorResponseBody {
-                message: "Forbidden".to_string(),
-            }),
-            ServiceError::RequestTimeout(ref message) => {
-                HttpResponse::RequestTimeout().json(ErrorResponseBody {
-                    message: message.to_string(),
-                })
-            }
-            ServiceError::NotFound(ref message) => {
-                HttpResponse::NotFound().json(ErrorResponseBody {
-                    message: format!("Not Found: {}", message),
-                })
+                format!("Stoped overwriting data, Duplicate Tracking Id {:?}", id)
             }
+            ServiceError::Unauthorized => "Unauthorized".to_string(),
+            ServiceError::Forbidden => "Forbidden".to_string(),
+            ServiceError::NotFound(ref message) => format!("Not Found: {}", message),
             ServiceError::JsonDeserializeError(ref message) => {
-                HttpResponse::BadRequest().json(ErrorResponseBody {
-                    message: format!("Json Deserialization Error: {}", message),
-                })
-            }
-            ServiceError::PayloadTooLarge(ref message) => {
-                HttpResponse::PayloadTooLarge().json(ErrorResponseBody {
-                    message: message.to_string(),
-                })
+                format!("Json Deserialization Error: {}", message)
             }
-        }
+        };
+
+        HttpResponse::build(self.status_code()).json(ErrorResponseBody { message })
     }
 }
 
@@ -162,3 +173,65 @@ pub fn custom_json_error_handler(
     );
     ServiceError::JsonDeserializeError(detailed_error_message).into()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_error_responses() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .expect("Runtime builds");
+        let cases = [
+            (
+                ServiceError::UpstreamUnavailable("Model server is down".to_string()),
+                503,
+                "Model server is down",
+            ),
+            (
+                ServiceError::UpstreamBadResponse("Failed reading response".to_string()),
+                502,
+                "Failed reading response",
+            ),
+            (
+                ServiceError::BadRequest("query_vector has 2 dimensions".to_string()),
+                400,
+                "query_vector has 2 dimensions",
+            ),
+            (
+                ServiceError::ModelRejected("Invalid model".to_string()),
+                400,
+                "Model server rejected the request: Invalid model",
+            ),
+            (
+                ServiceError::InternalServerError("Failed to serialize".to_string()),
+                500,
+                "Failed to serialize",
+            ),
+            (
+                ServiceError::RequestTimeout("rerank exceeded the latency budget".to_string()),
+                408,
+                "rerank exceeded the latency budget",
+            ),
+            (
+                ServiceError::NotFound("Chunk".to_string()),
+                404,
+                "Not Found: Chunk",
+            ),
+            (ServiceError::Unauthorized, 401, "Unauthorized"),
+        ];
+
+        for (error, status, message) in cases {
+            let response = error.error_response();
+            assert_eq!(response.status().as_u16(), status, "{}", error);
+            assert_eq!(error.status_code().as_u16(), status);
+
+            let body = runtime
+                .block_on(actix_web::body::to_bytes(response.into_body()))
+                .expect("Body is readable");
+            let body: serde_json::Value = serde_json::from_slice(&body).expect("Body is json");
+            assert_eq!(body, serde_json::json!({ "message": message }));
+        }
+    }
+}
@@ -1,12 +1,13 @@
 use super::auth_handler::{AdminOnly, LoggedUser};
 use crate::data::models::{
     escape_quotes, ChatMessageProxy, ChunkMetadata, ChunkMetadataStringTagSet, ChunkMetadataTypes,
-    ChunkMetadataWithScore, ConditionType, ContextOptions, CountSearchMethod,
-    DatasetAndOrgWithSubAndPlan, DatasetConfiguration, GeoInfo, HighlightOptions, ImageConfig,
-    IngestSpecificChunkMetadata, Pool, QdrantChunkMetadata, QueryTypes, RagQueryEventClickhouse,
-    RecommendType, RecommendationEventClickhouse, RecommendationStrategy, RedisPool, ScoreChunk,
-    ScoreChunkDTO, SearchMethod, SearchQueryEventClickhouse, SlimChunkMetadataWithScore,
-    SortByField, SortOptions, TypoOptions, UnifiedId, UpdateSpecificChunkMetadata,
+    ChunkMetadataWithScore, ConditionType, ContentLanguage, ContextOptions, CountSearchMethod,
+    DatasetAndOrgWithSubAndPlan, DatasetConfiguration, EmbeddingDeadLetter, GeoInfo,
+    HighlightOptions, HybridFusion, ImageConfig, IngestSpecificChunkMetadata, Pool,
+    QdrantChunkMetadata, QueryTypes, RagQueryEventClickhouse, RecommendType,
+    RecommendationEventClickhouse, RecommendationStrategy, RedisPool, ScoreChunk, ScoreChunkDTO,
+    SearchMethod, SearchQueryEventClickhouse, SlimChunkMetadataWithScore, SortByField, SortOptions,
+    TypoOptions, UnifiedId, UpdateSpecificChunkMetadata,
 };
 use crate::errors::ServiceError;
 use crate::get_env;
@@ -16,13 +17,24 @@ use crate::operators::clickhouse_operator::{get_latency_from_header, ClickHouseE
 use crate::operators::dataset_operator::{
     get_dataset_usage_query, ChunkDeleteMessage, DeleteMessage,
 };
+use crate::operators::dead_letter_operator::{
+    get_embedding_dead_letters_query, redrive_embedding_dead_letters_query,
+};
+use crate::operators::embedding_audit_operator::with_embedding_audit_log;
+use crate::operators::model_operator::{
+    apply_query_language, ensure_vector_norms, filter_boost_for_embed_type, get_dense_vectors,
+    get_embedding_rate_limit_wait, get_fulltext_embedding_content, get_sparse_vectors,
+    get_templated_embedding_content, resolve_embedding_base_url, validate_boost_phrases,
+    EmbedContext, IngestionPacing,
+};
 use crate::operators::parse_operator::convert_html_to_text;
 use crate::operators::qdrant_operator::{
-    point_ids_exists_in_qdrant, recommend_qdrant_query, scroll_dataset_points,
+    get_chunk_content_hash, get_point_content_hash_query, plan_chunk_vector_update,
+    point_ids_exists_in_qdrant, recommend_qdrant_query, scroll_dataset_points, ChunkVectorUpdate,
 };
 use crate::operators::search_operator::{
-    assemble_qdrant_filter, autocomplete_chunks_query, count_chunks_query, search_chunks_query,
-    search_hybrid_chunks,
+    assemble_qdrant_filter, autocomplete_chunks_query, count_chunks_query, explain_search_query,
+    search_chunks_query, search_hybrid_chunks, SearchExplainResponse,
 };
 use crate::operators::{chunk_operator::*, crawl_operator};
 use actix::Arbiter;
@@ -42,8 +54,9 @@ use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use simple_server_timing_header::Timer;
+use std::collections::HashMap;
 use tokio_stream::StreamExt;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 
 /// Boost the presence of certain tokens for fulltext (SPLADE) and keyword (BM25) search. I.e. boosting title phrases to priortize title matches or making sure that the listing for AirBNB itself ranks higher than companies who make software for AirBNB hosts by boosting the in-document-frequency of the AirBNB token (AKA word) for its official listing. Conceptually it multiples the in-document-importance second value in the tuples of the SPLADE or BM25 sparse vector of the chunk_html innerText for all tokens present in the boost phrase by the boost factor like so: (token, in-document-importance) -> (token, in-document-importance*boost_factor).
 #[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
@@ -73,6 +86,15 @@ pub struct ScoringOptions {
     pub semantic_boost: Option<SemanticBoost>,
 }
 
+/// Fusion options override how a hybrid search merges its semantic and fulltext results before reranking. Unset values fall back to the HYBRID_FUSION and HYBRID_FUSION_SEMANTIC_WEIGHT of the dataset.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct FusionOptions {
+    /// rrf (reciprocal rank fusion) or weighted_sum (min-max normalized scores of each result set, summed with their weights).
+    pub strategy: Option<HybridFusion>,
+    /// Weight of the semantic results between 0 and 1, the fulltext results get the remaining weight.
+    pub semantic_weight: Option<f32>,
+}
+
 /// Request payload for creating a new chunk
 #[derive(Serialize, Deserialize, Debug, ToSchema, Clone, Default)]
 #[schema(title = "single", example = json!({
@@ -165,6 +187,12 @@ pub struct SingleQueuedChunkResponse {
     pub chunk_metadata: ChunkMetadata,
     /// The current position the last access item is in the queue
     pub pos_in_queue: i32,
+    /// Vectors created for the chunk, only present when `return_embeddings=true` is passed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embeddings: Option<Vec<InlineChunkEmbedding>>,
+    /// Milliseconds to wait before sending more chunks, only present while the ingestion queue or the embedding rate limit is backed up
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, ToSchema)]
@@ -196,6 +224,204 @@ pub struct BatchQueuedChunkResponse {
     pub chunk_metadata: Vec<ChunkMetadata>,
     /// The current position the last access item is in the queue
     pub pos_in_queue: i32,
+    /// Vectors created for the chunk, only present when `return_embeddings=true` is passed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embeddings: Option<Vec<InlineChunkEmbedding>>,
+    /// Milliseconds to wait before sending more chunks, only present while the ingestion queue or the embedding rate limit is backed up
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct InlineChunkEmbedding {
+    /// Id of the chunk the vectors were created for
+    pub chunk_id: uuid::Uuid,
+    /// Dense vector created from the chunk's semantic content, with any semantic_boost applied. Not present if semantic search is disabled for the dataset.
+    pub dense_vector: Option<Vec<f32>>,
+    /// Sparse (SPLADE) vector as (index, value) pairs, only present when `return_sparse_embeddings=true` is passed and fulltext search is enabled for the dataset.
+    pub sparse_vector: Option<Vec<(u32, f32)>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchExplainQuery {
+    /// Dry run the search and return a SearchExplainResponse instead of results. The query embedding and sparse encoding calls are made and candidates are retrieved, but the rerank payloads are only built, not sent. Returns the resolved model urls, the exact texts after prefixing, clipping and PII redaction, vector dimensions and timings, with secrets redacted. Explained searches are not recorded in analytics. Only single queries can be explained.
+    pub explain: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReturnEmbeddingsQuery {
+    /// Include the vectors created for each chunk in the response. The vectors are created before the chunks are queued and the same vectors are written to the vector store. Only the first `INLINE_EMBEDDINGS_LIMIT` (default 10) chunks of a batch get inline vectors, the rest are embedded by the ingestion worker as usual.
+    pub return_embeddings: Option<bool>,
+    /// Also include the sparse (SPLADE) vector for each chunk. Only has an effect if return_embeddings is true.
+    pub return_sparse_embeddings: Option<bool>,
+}
+
+fn get_inline_embeddings_limit() -> usize {
+    std::env::var("INLINE_EMBEDDINGS_LIMIT")
+        .unwrap_or("10".to_string())
+        .parse()
+        .unwrap_or(10)
+}
+
+fn get_embedding_content(
+    chunk: &ChunkReqPayload,
+    dataset_config: &DatasetConfiguration,
+) -> (String, String) {
+    let content = if chunk.convert_html_to_text.unwrap_or(true) {
+        convert_html_to_text(&(chunk.chunk_html.clone().unwrap_or_default()))
+    } else {
+        chunk.chunk_html.clone().unwrap_or_default()
+    };
+    let embedding_content = chunk.semantic_content.clone().unwrap_or_else(|| {
+        get_templated_embedding_content(
+            content.clone(),
+            chunk.link.as_ref(),
+            chunk.tag_set.clone(),
+            chunk.metadata.as_ref(),
+            dataset_config,
+        )
+    });
+    let content = get_fulltext_embedding_content(content, chunk.metadata.as_ref(), dataset_config);
+
+    (content, embedding_content)
+}
+
+type InlineVectors = (Option<Vec<f32>>, Option<Vec<(u32, f32)>>);
+
+/// Creates the dense and optionally sparse vectors for (content, embedding_content, semantic_boost, fulltext_boost) tuples in the same way the ingestion worker would.
+async fn create_inline_vectors(
+    contents_and_boosts: Vec<(String, String, Option<SemanticBoost>, Option<FullTextBoost>)>,
+    return_sparse: bool,
+    dataset_id: uuid::Uuid,
+    dataset_config: DatasetConfiguration,
+) -> Result<Vec<InlineVectors>, ServiceError> {
+    if contents_and_boosts.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let reqwest_client = reqwest::Client::new();
+    let context = EmbedContext::from_dataset_config(&dataset_config);
+
+    let dense_vectors = if dataset_config.SEMANTIC_ENABLED {
+        let dense_vectors = get_dense_vectors(
+            contents_and_boosts
+                .iter()
+                .map(|(_, embedding_content, semantic_boost, _)| {
+                    (
+                        embedding_content.clone(),
+                        semantic_boost
+                            .clone()
+                            .filter(|boost| !boost.phrase.is_empty()),
+                    )
+                })
+                .collect(),
+            "doc",
+            &context,
+            reqwest_client.clone(),
+        )
+        .await?;
+        // The worker stores these as they are, so a degenerate vector has to fail here
+        ensure_vector_norms(
+            dataset_id,
+            &dense_vectors
+                .iter()
+                .map(|vector| vector.as_slice())
+                .collect::<Vec<&[f32]>>(),
+            &dataset_config,
+        )?;
+
+        dense_vectors.into_iter().map(Some).collect()
+    } else {
+        vec![None; contents_and_boosts.len()]
+    };
+
+    let sparse_vectors = if return_sparse && dataset_config.FULLTEXT_ENABLED {
+        get_sparse_vectors(
+            contents_and_boosts
+                .iter()
+                .map(|(content, _, _, fulltext_boost)| {
+                    (
+                        content.clone(),
+                        filter_boost_for_embed_type(
+                            fulltext_boost
+                                .clone()
+                                .filter(|boost| !boost.phrase.is_empty()),
+                            "doc",
+                            &dataset_config,
+                        ),
+                    )
+                })
+                .collect(),
+            "doc",
+            &context,
+            reqwest_client,
+        )
+        .await?
+        .into_iter()
+        .map(Some)
+        .collect()
+    } else {
+        vec![None; contents_and_boosts.len()]
+    };
+
+    Ok(dense_vectors.into_iter().zip(sparse_vectors).collect())
+}
+
+/// Creates the vectors for up to `INLINE_EMBEDDINGS_LIMIT` of the ingestion messages, attaching them to the messages such that the ingestion worker stores the exact vectors returned to the caller.
+async fn embed_ingestion_messages_inline(
+    ingestion_messages: &mut [UploadIngestionMessage],
+    return_sparse: bool,
+    dataset_id: uuid::Uuid,
+    dataset_config: DatasetConfiguration,
+) -> Result<Vec<InlineChunkEmbedding>, ServiceError> {
+    let messages_to_embed = ingestion_messages
+        .iter_mut()
+        // Blank chunks are left to the worker, which applies the dataset's EMPTY_CONTENT_POLICY
+        .filter(|message| {
+            !get_embedding_content(&message.chunk, &dataset_config)
+                .0
+                .trim()
+                .is_empty()
+        })
+        .take(get_inline_embeddings_limit())
+        .collect::<Vec<&mut UploadIngestionMessage>>();
+
+    let inline_vectors = create_inline_vectors(
+        messages_to_embed
+            .iter()
+            .map(|message| {
+                let (content, embedding_content) =
+                    get_embedding_content(&message.chunk, &dataset_config);
+                (
+                    content,
+                    embedding_content,
+                    message.chunk.semantic_boost.clone(),
+                    message.chunk.fulltext_boost.clone(),
+                )
+            })
+            .collect(),
+        return_sparse,
+        dataset_id,
+        dataset_config,
+    )
+    .await?;
+
+    Ok(messages_to_embed
+        .into_iter()
+        .zip(inline_vectors)
+        .map(|(message, (dense_vector, sparse_vector))| {
+            message.dense_vector.clone_from(&dense_vector);
+            message.sparse_vector.clone_from(&sparse_vector);
+
+            InlineChunkEmbedding {
+                chunk_id: message.ingest_specific_chunk_metadata.id,
+                dense_vector,
+                sparse_vector,
+            }
+        })
+        .collect())
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -204,11 +430,18 @@ pub struct UploadIngestionMessage {
     pub chunk: ChunkReqPayload,
     pub dataset_id: uuid::Uuid,
     pub upsert_by_tracking_id: bool,
+    #[serde(default)]
+    pub dense_vector: Option<Vec<f32>>,
+    #[serde(default)]
+    pub sparse_vector: Option<Vec<(u32, f32)>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct BulkUploadIngestionMessage {
     pub attempt_number: usize,
+    /// Attempts on which the model server rejected the chunk, see `plan_ingestion_retry`
+    #[serde(default)]
+    pub rejected_attempts: usize,
     pub dataset_id: uuid::Uuid,
     pub ingestion_messages: Vec<UploadIngestionMessage>,
 }
@@ -291,10 +524,12 @@ pub enum CreateChunkReqPayloadEnum {
         (status = 200, description = "JSON response payload containing the created chunk", body = ReturnQueuedChunk),
         (status = 426, description = "Error when upgrade is needed to process more chunks", body = ErrorResponseBody),
         (status = 413, description = "Error when more than 120 chunks are provided in bulk", body = ErrorResponseBody),
+        (status = 429, description = "Error when the ingestion queue is too backed up to accept more chunks, the Retry-After header says how many seconds to wait", body = ErrorResponseBody),
         (status = 400, description = "Error typically due to deserialization issues", body = ErrorResponseBody),
     ),
     params(
         ("TR-Dataset" = uuid::Uuid, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+        ReturnEmbeddingsQuery,
     ),
     security(
         ("ApiKey" = ["admin"]),
@@ -302,6 +537,7 @@ pub enum CreateChunkReqPayloadEnum {
 )]
 pub async fn create_chunk(
     create_chunk_data: web::Json<CreateChunkReqPayloadEnum>,
+    return_embeddings_query: web::Query<ReturnEmbeddingsQuery>,
     pool: web::Data<Pool>,
     _user: AdminOnly,
     dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
@@ -348,6 +584,16 @@ pub async fn create_chunk(
         .into());
     }
 
+    let dataset_config =
+        DatasetConfiguration::from_json(dataset_org_plan_sub.dataset.server_configuration.clone());
+    for chunk in chunks.iter() {
+        validate_boost_phrases(
+            chunk.fulltext_boost.as_ref(),
+            chunk.semantic_boost.as_ref(),
+            &dataset_config,
+        )?;
+    }
+
     let chunks = chunks.into_iter().map(|chunk| {
         let non_empty_tracking_id = chunk
             .tracking_id
@@ -362,12 +608,53 @@ pub async fn create_chunk(
     let (upsert_chunks, non_upsert_chunks): (Vec<ChunkReqPayload>, Vec<ChunkReqPayload>) =
         chunks.partition(|chunk| chunk.upsert_by_tracking_id.unwrap_or(false));
 
-    let (non_upsert_chunk_ingestion_message, non_upsert_chunk_metadatas) =
+    let pacing = IngestionPacing::from_env();
+    let rate_limit_wait = get_embedding_rate_limit_wait(&resolve_embedding_base_url(
+        &dataset_config.EMBEDDING_BASE_URL,
+    )?);
+
+    let (mut non_upsert_chunk_ingestion_message, non_upsert_chunk_metadatas) =
         create_chunk_metadata(non_upsert_chunks, dataset_org_plan_sub.dataset.id).await?;
 
-    let (upsert_chunk_ingestion_message, upsert_chunk_metadatas) =
+    let (mut upsert_chunk_ingestion_message, upsert_chunk_metadatas) =
         create_chunk_metadata(upsert_chunks, dataset_org_plan_sub.dataset.id).await?;
 
+    let embeddings = if return_embeddings_query.return_embeddings.unwrap_or(false) {
+        let return_sparse = return_embeddings_query
+            .return_sparse_embeddings
+            .unwrap_or(false);
+
+        let mut embeddings = embed_ingestion_messages_inline(
+            &mut non_upsert_chunk_ingestion_message.ingestion_messages,
+            return_sparse,
+            dataset_org_plan_sub.dataset.id,
+            dataset_config.clone(),
+        )
+        .await?;
+        let remaining_inline_limit = get_inline_embeddings_limit().saturating_sub(embeddings.len());
+        if remaining_inline_limit > 0 {
+            let upsert_messages_len = upsert_chunk_ingestion_message
+                .ingestion_messages
+                .len()
+                .min(remaining_inline_limit);
+            embeddings.extend(
+                embed_ingestion_messages_inline(
+                    &mut upsert_chunk_ingestion_message.ingestion_messages[..upsert_messages_len],
+                    return_sparse,
+                    dataset_org_plan_sub.dataset.id,
+                    dataset_config,
+                )
+                .await?,
+            );
+        }
+
+        timer.add("created inline embeddings");
+
+        Some(embeddings)
+    } else {
+        None
+    };
+
     let chunk_metadatas = non_upsert_chunk_metadatas
         .clone()
         .into_iter()
@@ -381,6 +668,29 @@ pub async fn create_chunk(
 
     timer.add("got redis connection");
 
+    if pacing.reject_queue_depth.is_some() {
+        let queue_depth: usize = redis::cmd("llen")
+            .arg("ingestion")
+            .query_async(&mut *redis_conn)
+            .await
+            .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+        if pacing.should_reject(queue_depth) {
+            let retry_after = pacing
+                .retry_after(queue_depth, rate_limit_wait)
+                .unwrap_or_default();
+            return Ok(HttpResponse::TooManyRequests()
+                .insert_header((
+                    "Retry-After",
+                    retry_after.as_secs_f64().ceil().max(1.0).to_string(),
+                ))
+                .json(json!({
+                    "message": "The ingestion queue is backed up, retry after the Retry-After header's seconds",
+                    "retry_after_ms": retry_after.as_millis() as u64,
+                })));
+        }
+    }
+
     let mut pos_in_queue = 0;
     if !non_upsert_chunk_metadatas.is_empty() {
         let serialized_message: String = serde_json::to_string(&non_upsert_chunk_ingestion_message)
@@ -409,6 +719,10 @@ pub async fn create_chunk(
             .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
     }
 
+    let retry_after_ms = pacing
+        .retry_after(pos_in_queue.max(0) as usize, rate_limit_wait)
+        .map(|retry_after| retry_after.as_millis() as u64);
+
     let response = match create_chunk_data.into_inner() {
         CreateChunkReqPayloadEnum::Single(_) => ReturnQueuedChunk::Single(SingleQueuedChunkResponse {
             chunk_metadata: chunk_metadatas
@@ -418,10 +732,14 @@ pub async fn create_chunk(
                 ))?
                 .clone(),
             pos_in_queue,
+            embeddings,
+            retry_after_ms,
         }),
         CreateChunkReqPayloadEnum::Batch(_) => ReturnQueuedChunk::Batch(BatchQueuedChunkResponse {
             chunk_metadata: chunk_metadatas,
             pos_in_queue,
+            embeddings,
+            retry_after_ms,
         }),
     };
 
@@ -636,11 +954,22 @@ pub struct UpdateIngestionMessage {
     pub convert_html_to_text: Option<bool>,
     pub fulltext_boost: Option<FullTextBoost>,
     pub semantic_boost: Option<SemanticBoost>,
+    #[serde(default)]
+    pub dense_vector: Option<Vec<f32>>,
+    #[serde(default)]
+    pub sparse_vector: Option<Vec<(u32, f32)>>,
+    /// Which vectors to create again, all of them when missing
+    #[serde(default)]
+    pub vector_update: Option<ChunkVectorUpdate>,
 }
 
+/// Header telling whether an update created new vectors for the chunk. It is `false` when
+/// neither the content nor a boost changed and only the metadata was updated.
+pub const VECTORS_REGENERATED_HEADER: &str = "TR-Vectors-Regenerated";
+
 /// Update Chunk
 ///
-/// Update a chunk. If you try to change the tracking_id of the chunk to have the same tracking_id as an existing chunk, the request will fail. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+/// Update a chunk. If you try to change the tracking_id of the chunk to have the same tracking_id as an existing chunk, the request will fail. Vectors are only created again when the embedded content or a boost changes, the TR-Vectors-Regenerated response header tells whether they were. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
 #[utoipa::path(
     put,
     path = "/chunk",
@@ -648,11 +977,13 @@ pub struct UpdateIngestionMessage {
     tag = "Chunk",
     request_body(content = UpdateChunkReqPayload, description = "JSON request payload to update a chunk (chunk)", content_type = "application/json"),
     responses(
+        (status = 200, description = "JSON response containing the vectors created for the chunk, only returned when return_embeddings=true is passed", body = InlineChunkEmbedding),
         (status = 204, description = "No content Ok response indicating the chunk was updated as requested",),
         (status = 400, description = "Service error relating to to updating chunk, likely due to conflicting tracking_id", body = ErrorResponseBody),
     ),
     params(
         ("TR-Dataset" = uuid::Uuid, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+        ReturnEmbeddingsQuery,
     ),
     security(
         ("ApiKey" = ["admin"]),
@@ -660,6 +991,7 @@ pub struct UpdateIngestionMessage {
 )]
 pub async fn update_chunk(
     update_chunk_data: web::Json<UpdateChunkReqPayload>,
+    return_embeddings_query: web::Query<ReturnEmbeddingsQuery>,
     pool: web::Data<Pool>,
     redis_pool: web::Data<RedisPool>,
     _user: AdminOnly,
@@ -667,6 +999,13 @@ pub async fn update_chunk(
 ) -> Result<HttpResponse, actix_web::Error> {
     let dataset_id = dataset_org_plan_sub.dataset.id;
     let chunk_id = update_chunk_data.chunk_id;
+    let dataset_config =
+        DatasetConfiguration::from_json(dataset_org_plan_sub.dataset.server_configuration.clone());
+    validate_boost_phrases(
+        update_chunk_data.fulltext_boost.as_ref(),
+        update_chunk_data.semantic_boost.as_ref(),
+        &dataset_config,
+    )?;
 
     let chunk_metadata = if let Some(chunk_id) = chunk_id {
         get_metadata_from_id_query(chunk_id, dataset_id, pool).await?
@@ -758,15 +1097,89 @@ pub async fn update_chunk(
             })
     };
 
-    let message = UpdateIngestionMessage {
+    let content = if update_chunk_data.convert_html_to_text.unwrap_or(true) {
+        convert_html_to_text(&(chunk_metadata.chunk_html.clone().unwrap_or_default()))
+    } else {
+        chunk_metadata.chunk_html.clone().unwrap_or_default()
+    };
+    let embedding_content = get_templated_embedding_content(
+        content.clone(),
+        chunk_metadata.link.as_ref(),
+        chunk_metadata
+            .tag_set
+            .clone()
+            .map(|tag_set| tag_set.into_iter().flatten().collect()),
+        chunk_metadata.metadata.as_ref(),
+        &dataset_config,
+    );
+    let content =
+        get_fulltext_embedding_content(content, chunk_metadata.metadata.as_ref(), &dataset_config);
+
+    let mut message = UpdateIngestionMessage {
         chunk_metadata: chunk_metadata.clone().into(),
         dataset_id,
         group_ids,
         convert_html_to_text: update_chunk_data.convert_html_to_text,
         fulltext_boost: update_chunk_data.fulltext_boost.clone(),
         semantic_boost: update_chunk_data.semantic_boost.clone(),
+        dense_vector: None,
+        sparse_vector: None,
+        vector_update: None,
     };
 
+    let embedding = if return_embeddings_query.return_embeddings.unwrap_or(false) {
+        if content.is_empty() {
+            return Err(
+                ServiceError::BadRequest("Chunk must not have empty chunk_html".into()).into(),
+            );
+        }
+
+        let (dense_vector, sparse_vector) = create_inline_vectors(
+            vec![(
+                content,
+                embedding_content,
+                update_chunk_data.semantic_boost.clone(),
+                update_chunk_data.fulltext_boost.clone(),
+            )],
+            return_embeddings_query
+                .return_sparse_embeddings
+                .unwrap_or(false),
+            dataset_id,
+            dataset_config,
+        )
+        .await?
+        .pop()
+        .ok_or(ServiceError::InternalServerError(
+            "Failed to create vectors for the chunk".to_string(),
+        ))?;
+
+        message.dense_vector.clone_from(&dense_vector);
+        message.sparse_vector.clone_from(&sparse_vector);
+        message.vector_update = Some(ChunkVectorUpdate::FULL);
+
+        Some(InlineChunkEmbedding {
+            chunk_id: chunk_metadata.id,
+            dense_vector,
+            sparse_vector,
+        })
+    } else {
+        let stored_content_hash =
+            get_point_content_hash_query(chunk_metadata.qdrant_point_id, &dataset_config).await?;
+        message.vector_update = Some(plan_chunk_vector_update(
+            stored_content_hash.as_deref(),
+            &get_chunk_content_hash(&content, &embedding_content),
+            update_chunk_data.semantic_boost.as_ref(),
+            update_chunk_data.fulltext_boost.as_ref(),
+            &dataset_config,
+        ));
+
+        None
+    };
+    let vectors_regenerated = message
+        .vector_update
+        .unwrap_or(ChunkVectorUpdate::FULL)
+        .regenerates_vectors();
+
     let mut redis_conn = redis_pool
         .get()
         .await
@@ -779,7 +1192,15 @@ pub async fn update_chunk(
         .await
         .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
 
-    Ok(HttpResponse::NoContent().finish())
+    if let Some(embedding) = embedding {
+        return Ok(HttpResponse::Ok()
+            .insert_header((VECTORS_REGENERATED_HEADER, vectors_regenerated.to_string()))
+            .json(embedding));
+    }
+
+    Ok(HttpResponse::NoContent()
+        .insert_header((VECTORS_REGENERATED_HEADER, vectors_regenerated.to_string()))
+        .finish())
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
@@ -911,6 +1332,9 @@ pub async fn update_chunk_by_tracking_id(
         convert_html_to_text: update_chunk_data.convert_html_to_text,
         fulltext_boost: None,
         semantic_boost: None,
+        dense_vector: None,
+        sparse_vector: None,
+        vector_update: None,
     };
 
     let mut redis_conn = redis_pool
@@ -1023,6 +1447,18 @@ pub struct SearchChunksReqPayload {
     pub user_id: Option<String>,
     /// Typo options lets you specify different methods to handle typos in the search query. If not specified, this defaults to no typo handling.
     pub typo_options: Option<TypoOptions>,
+    /// Precomputed dense vector for the query. If specified, it is used for semantic and hybrid search instead of embedding the query. Its length must match the embedding size of the dataset. Scoring options are not applied to precomputed vectors.
+    pub query_vector: Option<Vec<f32>>,
+    /// Precomputed sparse vector for the query as a list of (index, value) pairs. If specified, it is used for fulltext, bm25 and hybrid search instead of encoding the query. Scoring options are not applied to precomputed vectors.
+    pub query_sparse_vector: Option<Vec<(u32, f32)>>,
+    /// Time budget for the search in milliseconds. Embedding the query fails fast with a 408 once the budget is spent, and cross encoder reranking is skipped if it does not finish within the remaining budget, in which case the response is marked as degraded. If not specified, there is no budget.
+    pub latency_budget_ms: Option<u64>,
+    /// Only applies to hybrid search. Overrides how the semantic and fulltext results are fused into the candidates which are reranked. If not specified, the fusion settings of the dataset are used.
+    pub fusion_options: Option<FusionOptions>,
+    /// Language of the query as an ISO 639-1 code such as `de`. It picks the stemmer used for bm25 query tokens and the entry of the dataset's `EMBEDDING_QUERY_PREFIXES` used as query prefix. If not specified, bm25 stems the query in the dataset's `BM25_LANGUAGE`, and the query prefix is picked by the detected language of the query if the dataset sets `QUERY_LANGUAGE_DETECTION`, else by `BM25_LANGUAGE`.
+    pub query_language: Option<ContentLanguage>,
+    /// Only applies to semantic search without a precomputed query vector. Weights of the content vector and the named field vectors of the dataset's `EMBEDDING_VECTOR_FIELDS`, keyed by `content` or the field name. The score of a chunk is the weighted mean of the similarities of its vectors, chunks without a value for a field are scored with their remaining vectors. Weights default to 1.
+    pub vector_field_weights: Option<HashMap<String, f32>>,
 }
 
 impl Default for SearchChunksReqPayload {
@@ -1044,6 +1480,12 @@ impl Default for SearchChunksReqPayload {
             remove_stop_words: None,
             user_id: None,
             typo_options: None,
+            query_vector: None,
+            query_sparse_vector: None,
+            latency_budget_ms: None,
+            fusion_options: None,
+            query_language: None,
+            vector_field_weights: None,
         }
     }
 }
@@ -1054,6 +1496,9 @@ pub struct SearchChunkQueryResponseBody {
     pub score_chunks: Vec<ScoreChunkDTO>,
     pub corrected_query: Option<String>,
     pub total_chunk_pages: i64,
+    /// Stages which were skipped: "semantic" or "fulltext" for a hybrid search whose encoder failed, and "rerank" if cross encoder reranking did not fit in the latency budget. Not present if the search was not degraded.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub degraded: Option<Vec<String>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
@@ -1063,6 +1508,9 @@ pub struct SearchResponseBody {
     pub chunks: Vec<ScoreChunk>,
     pub corrected_query: Option<String>,
     pub total_pages: i64,
+    /// Stages which were skipped: "semantic" or "fulltext" for a hybrid search whose encoder failed, and "rerank" if cross encoder reranking did not fit in the latency budget. Not present if the search was not degraded.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub degraded: Option<Vec<String>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
@@ -1085,6 +1533,7 @@ impl SearchChunkQueryResponseBody {
                 .collect(),
             corrected_query: self.corrected_query,
             total_pages: self.total_chunk_pages,
+            degraded: self.degraded,
         }
     }
 }
@@ -1195,18 +1644,22 @@ pub fn parse_query(
     request_body(content = SearchChunksReqPayload, description = "JSON request payload to semantically search for chunks (chunks)", content_type = "application/json"),
     responses(
         (status = 200, description = "Chunks with embedding vectors which are similar to those in the request body", body = SearchResponseTypes),
+        (status = 200, description = "With explain=true, the model calls the search would make", body = SearchExplainResponse),
         (status = 400, description = "Service error relating to searching", body = ErrorResponseBody),
     ),
     params(
         ("TR-Dataset" = uuid::Uuid, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
-        ("X-API-Version" = Option<APIVersion>, Header, description = "The API version to use for this request. Defaults to V2 for orgs created after July 12, 2024 and V1 otherwise.")
+        ("X-API-Version" = Option<APIVersion>, Header, description = "The API version to use for this request. Defaults to V2 for orgs created after July 12, 2024 and V1 otherwise."),
+        SearchExplainQuery,
     ),
     security(
         ("ApiKey" = ["readonly"]),
     )
 )]
+#[allow(clippy::too_many_arguments)]
 pub async fn search_chunks(
     data: web::Json<SearchChunksReqPayload>,
+    explain_query: web::Query<SearchExplainQuery>,
     _user: LoggedUser,
     pool: web::Data<Pool>,
     event_queue: web::Data<EventQueue>,
@@ -1214,11 +1667,20 @@ pub async fn search_chunks(
     api_version: APIVersion,
     dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let dataset_config =
+    let mut dataset_config =
         DatasetConfiguration::from_json(dataset_org_plan_sub.dataset.server_configuration.clone());
 
     let mut data = data.into_inner();
 
+    let query_text = match &data.query {
+        QueryTypes::Single(query) => query.clone(),
+        QueryTypes::Multi(query) => query
+            .iter()
+            .map(|multi_query| multi_query.query.clone())
+            .join(" "),
+    };
+    apply_query_language(&mut dataset_config, &query_text, data.query_language);
+
     let parsed_query = match data.query.clone() {
         QueryTypes::Single(query) => ParsedQueryTypes::Single(parse_query(
             query.clone(),
@@ -1242,34 +1704,56 @@ pub async fn search_chunks(
 
     data.score_threshold = data.score_threshold.filter(|threshold| *threshold != 0.0);
 
+    if explain_query.explain.unwrap_or(false) {
+        let explanation = explain_search_query(
+            data,
+            parsed_query.to_parsed_query()?,
+            pool,
+            redis_pool,
+            dataset_org_plan_sub.dataset,
+            &dataset_config,
+        )
+        .await?;
+
+        return Ok(HttpResponse::Ok().json(explanation));
+    }
+
     let mut timer = Timer::new();
 
-    let result_chunks = match data.search_type {
-        SearchMethod::Hybrid => {
-            search_hybrid_chunks(
-                data.clone(),
-                parsed_query.to_parsed_query()?,
-                pool,
-                redis_pool,
-                dataset_org_plan_sub.dataset.clone(),
-                &dataset_config,
-                &mut timer,
-            )
-            .await?
-        }
-        _ => {
-            search_chunks_query(
-                data.clone(),
-                parsed_query,
-                pool,
-                redis_pool,
-                dataset_org_plan_sub.dataset.clone(),
-                &dataset_config,
-                &mut timer,
-            )
-            .await?
-        }
-    };
+    let result_chunks = with_embedding_audit_log(
+        dataset_org_plan_sub.dataset.id,
+        &dataset_config,
+        pool.clone(),
+        async {
+            match data.search_type {
+                SearchMethod::Hybrid => {
+                    search_hybrid_chunks(
+                        data.clone(),
+                        parsed_query.to_parsed_query()?,
+                        pool,
+                        redis_pool,
+                        dataset_org_plan_sub.dataset.clone(),
+                        &dataset_config,
+                        &mut timer,
+                    )
+                    .await
+                }
+                _ => {
+                    search_chunks_query(
+                        data.clone(),
+                        parsed_query,
+                        pool,
+                        redis_pool,
+                        dataset_org_plan_sub.dataset.clone(),
+                        &dataset_config,
+                        &mut timer,
+                    )
+                    .await
+                }
+            }
+        },
+    )
+    .await?;
     timer.add("search_chunks");
 
     let search_id = uuid::Uuid::new_v4();
@@ -1425,6 +1909,12 @@ impl From<AutocompleteReqPayload> for SearchChunksReqPayload {
             remove_stop_words: autocomplete_data.remove_stop_words,
             user_id: autocomplete_data.user_id,
             typo_options: autocomplete_data.typo_options,
+            query_vector: None,
+            query_sparse_vector: None,
+            latency_budget_ms: None,
+            fusion_options: None,
+            query_language: None,
+            vector_field_weights: None,
         }
     }
 }
@@ -1470,14 +1960,19 @@ pub async fn autocomplete(
 
     let mut timer = Timer::new();
 
-    let result_chunks = autocomplete_chunks_query(
-        data.clone(),
-        parsed_query,
-        pool,
-        redis_pool,
-        dataset_org_plan_sub.dataset.clone(),
+    let result_chunks = with_embedding_audit_log(
+        dataset_org_plan_sub.dataset.id,
         &dataset_config,
-        &mut timer,
+        pool.clone(),
+        autocomplete_chunks_query(
+            data.clone(),
+            parsed_query,
+            pool,
+            redis_pool,
+            dataset_org_plan_sub.dataset.clone(),
+            &dataset_config,
+            &mut timer,
+        ),
     )
     .await?;
 
@@ -1645,6 +2140,106 @@ pub async fn scroll_dataset_chunks(
     Ok(HttpResponse::Ok().json(resp))
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct GetEmbeddingDeadLettersReqPayload {
+    /// Page of dead-lettered chunks to fetch, newest first. Defaults to 1.
+    pub page: Option<u64>,
+    /// Number of dead-lettered chunks per page. Defaults to 10.
+    pub page_size: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct EmbeddingDeadLettersResponseBody {
+    pub dead_letters: Vec<EmbeddingDeadLetter>,
+}
+
+/// Get Dead-Lettered Chunks
+///
+/// Get the chunks of the dataset which the embedding model kept rejecting during ingestion. These are no longer retried automatically and stay here with the last error until they are re-driven. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    post,
+    path = "/chunks/dead_letters",
+    context_path = "/api",
+    tag = "Chunk",
+    request_body(content = GetEmbeddingDeadLettersReqPayload, description = "JSON request payload to page through dead-lettered chunks", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Dead-lettered chunks of the dataset", body = EmbeddingDeadLettersResponseBody),
+        (status = 400, description = "Service error relating to getting dead-lettered chunks", body = ErrorResponseBody)
+    ),
+    params(
+        ("TR-Dataset" = uuid::Uuid, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+pub async fn get_embedding_dead_letters(
+    data: web::Json<GetEmbeddingDeadLettersReqPayload>,
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let dead_letters = get_embedding_dead_letters_query(
+        dataset_org_plan_sub.dataset.id,
+        data.page.unwrap_or(1),
+        data.page_size.unwrap_or(10),
+        pool,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(EmbeddingDeadLettersResponseBody { dead_letters }))
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct RedriveEmbeddingDeadLettersReqPayload {
+    /// Ids of the dead letters to re-drive. If not specified, every dead-lettered chunk of the dataset is re-driven.
+    pub ids: Option<Vec<uuid::Uuid>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct RedriveEmbeddingDeadLettersResponseBody {
+    /// Ids of the chunks which were queued for ingestion again
+    pub chunk_ids: Vec<uuid::Uuid>,
+}
+
+/// Re-drive Dead-Lettered Chunks
+///
+/// Queue dead-lettered chunks for ingestion again, for example after fixing their content or switching the embedding model. They start over with a fresh attempt count and are removed from the dead letters. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    post,
+    path = "/chunks/dead_letters/redrive",
+    context_path = "/api",
+    tag = "Chunk",
+    request_body(content = RedriveEmbeddingDeadLettersReqPayload, description = "JSON request payload to re-drive dead-lettered chunks", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Chunks which were queued again", body = RedriveEmbeddingDeadLettersResponseBody),
+        (status = 400, description = "Service error relating to re-driving dead-lettered chunks", body = ErrorResponseBody)
+    ),
+    params(
+        ("TR-Dataset" = uuid::Uuid, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+pub async fn redrive_embedding_dead_letters(
+    data: web::Json<RedriveEmbeddingDeadLettersReqPayload>,
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let chunk_ids = redrive_embedding_dead_letters_query(
+        dataset_org_plan_sub.dataset.id,
+        data.into_inner().ids,
+        pool,
+        redis_pool,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(RedriveEmbeddingDeadLettersResponseBody { chunk_ids }))
+}
+
 /// Get Chunk By Id
 ///
 /// Get a singular chunk by id.
@@ -1735,6 +2330,12 @@ impl From<CountChunksReqPayload> for SearchChunksReqPayload {
             remove_stop_words: None,
             user_id: None,
             typo_options: None,
+            query_vector: None,
+            query_sparse_vector: None,
+            latency_budget_ms: None,
+            fusion_options: None,
+            query_language: None,
+            vector_field_weights: None,
         }
     }
 }
@@ -1,34 +1,44 @@
 use super::auth_handler::{AdminOnly, LoggedUser, OwnerOnly};
 use crate::{
     data::models::{
-        CrawlOptions, Dataset, DatasetAndOrgWithSubAndPlan, DatasetConfiguration,
-        DatasetConfigurationDTO, DatasetDTO, OrganizationWithSubAndPlan, Pool, RedisPool,
-        StripePlan,
+        CrawlOptions, Dataset, DatasetAndOrgWithSubAndPlan, DatasetCloneJob, DatasetConfiguration,
+        DatasetConfigurationDTO, DatasetDTO, EmbeddingAuditEntry, OrganizationWithSubAndPlan, Pool,
+        RedisPool, StripePlan,
     },
     errors::ServiceError,
     middleware::auth_middleware::{verify_admin, verify_owner},
     operators::{
+        chunk_operator::get_chunk_html_sample_query,
         crawl_operator::{
             crawl, get_crawl_request_by_dataset_id_query, update_crawl_settings_for_dataset,
             validate_crawl_options,
         },
         dataset_operator::{
             clear_dataset_by_dataset_id_query, create_dataset_query, create_datasets_query,
-            get_dataset_by_id_query, get_dataset_by_tracking_id_query, get_dataset_usage_query,
-            get_datasets_by_organization_id, get_tags_in_dataset_query,
-            soft_delete_dataset_by_id_query, update_dataset_query,
+            cutover_embedding_model_query, get_dataset_by_id_query,
+            get_dataset_by_tracking_id_query, get_dataset_clone_job_query, get_dataset_usage_query,
+            get_datasets_by_organization_id, get_tags_in_dataset_query, queue_dataset_clone_query,
+            soft_delete_dataset_by_id_query, update_dataset_query, validate_dataset_configuration,
         },
         dittofeed_operator::{
             send_ditto_event, DittoDatasetCreated, DittoTrackProperties, DittoTrackRequest,
         },
+        embedding_audit_operator::get_embedding_audit_entries_query,
+        event_operator::{get_preprocessing_event_counts_query, PreprocessingEventCount},
+        model_operator::{
+            get_bm25_corpus_stats, resolve_embedding_prefixes, Bm25CorpusStats, Bm25Stopwords,
+            ResolvedModelSettings,
+        },
         organization_operator::{get_org_dataset_count, get_org_from_id_query},
+        term_token_operator::get_bm25_term_tokens_query,
     },
 };
 use actix_web::{web, FromRequest, HttpMessage, HttpResponse};
+use dateparser::DateTimeUtc;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::future::{ready, Ready};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 
 impl FromRequest for DatasetAndOrgWithSubAndPlan {
     type Error = ServiceError;
@@ -162,14 +172,19 @@ pub async fn create_dataset(
         validate_crawl_options(&crawl_options)?;
     };
 
+    let mut server_configuration: DatasetConfiguration = data
+        .server_configuration
+        .clone()
+        .map(|c| c.into())
+        .unwrap_or_default();
+    resolve_embedding_prefixes(&mut server_configuration);
+    validate_dataset_configuration(&server_configuration)?;
+
     let dataset = Dataset::from_details(
         data.dataset_name.clone(),
         org_id,
         data.tracking_id.clone(),
-        data.server_configuration
-            .clone()
-            .map(|c| c.into())
-            .unwrap_or_default(),
+        server_configuration,
     );
 
     let d = create_dataset_query(dataset.clone(), pool.clone()).await?;
@@ -303,14 +318,17 @@ pub async fn update_dataset(
     }
 
     let curr_dataset_config = DatasetConfiguration::from_json(curr_dataset.server_configuration);
+    let server_configuration = data
+        .server_configuration
+        .clone()
+        .map(|c| c.from_curr_dataset(curr_dataset_config.clone()))
+        .unwrap_or(curr_dataset_config);
+    validate_dataset_configuration(&server_configuration)?;
 
     let d = update_dataset_query(
         curr_dataset.id,
         data.dataset_name.clone().unwrap_or(curr_dataset.name),
-        data.server_configuration
-            .clone()
-            .map(|c| c.from_curr_dataset(curr_dataset_config.clone()))
-            .unwrap_or(curr_dataset_config),
+        server_configuration,
         data.new_tracking_id.clone(),
         pool.clone(),
     )
@@ -759,6 +777,370 @@ pub async fn get_all_tags(
     }))
 }
 
+#[derive(Serialize, Deserialize, Debug, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetBm25StatsQuery {
+    /// Maximum number of chunks to compute the statistics over. Datasets with more chunks are randomly sampled. Defaults to 10000.
+    pub sample_size: Option<i64>,
+}
+
+/// Get BM25 Corpus Stats
+///
+/// Get statistics about the dataset's corpus which are useful for tuning BM25_AVG_LEN, BM25_B and BM25_K. Chunks are tokenized with the same pipeline used at ingest. Large datasets are computed over a random sample whose size is reported in the response. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    get,
+    path = "/dataset/bm25_stats",
+    context_path = "/api",
+    tag = "Dataset",
+    responses(
+        (status = 200, description = "BM25 statistics for the dataset", body = Bm25CorpusStats),
+        (status = 400, description = "Service error relating to computing the statistics", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = uuid::Uuid, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+        GetBm25StatsQuery,
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+pub async fn get_bm25_stats(
+    query: web::Query<GetBm25StatsQuery>,
+    _user: AdminOnly,
+    pool: web::Data<Pool>,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+) -> Result<HttpResponse, ServiceError> {
+    let sample_size = query.sample_size.unwrap_or(10000);
+    if sample_size < 1 {
+        return Err(ServiceError::BadRequest(
+            "sample_size must be greater than 0".to_string(),
+        ));
+    }
+
+    let dataset_config =
+        DatasetConfiguration::from_json(dataset_org_plan_sub.dataset.server_configuration);
+
+    let (contents, chunk_count) =
+        get_chunk_html_sample_query(dataset_org_plan_sub.dataset.id, sample_size, pool).await?;
+
+    let stats = web::block(move || {
+        get_bm25_corpus_stats(
+            contents,
+            chunk_count,
+            dataset_config.BM25_MIN_TOKEN_LENGTH,
+            dataset_config.BM25_MAX_TOKEN_LENGTH,
+            dataset_config.BM25_LANGUAGE,
+            &Bm25Stopwords::from_dataset_config(&dataset_config),
+        )
+    })
+    .await
+    .map_err(|err| ServiceError::InternalServerError(format!("Thread error {:?}", err)))?;
+
+    Ok(HttpResponse::Ok().json(stats))
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct DatasetModelSettings {
+    /// The settings the embedding, sparse and rerank calls for the dataset use
+    pub settings: ResolvedModelSettings,
+    /// How often preprocessing hit a limit in the last 24 hours, by stage and kind. Null when analytics are unavailable.
+    pub limit_triggers_24h: Option<Vec<PreprocessingEventCount>>,
+}
+
+/// Get Dataset Model Settings
+///
+/// Get the settings the embedding, sparse and rerank calls for the dataset actually use, after environment fallbacks, model presets and provider caps are applied, along with how often their limits truncated or emptied chunk content in the last 24 hours. Model server urls which come from the server's environment rather than the dataset are shown as `server default`. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    get,
+    path = "/dataset/model_settings",
+    context_path = "/api",
+    tag = "Dataset",
+    responses(
+        (status = 200, description = "Resolved model settings of the dataset", body = DatasetModelSettings),
+        (status = 400, description = "Service error relating to resolving the settings", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = uuid::Uuid, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+pub async fn get_dataset_model_settings(
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    clickhouse_client: web::Data<clickhouse::Client>,
+) -> Result<HttpResponse, ServiceError> {
+    let dataset_config =
+        DatasetConfiguration::from_json(dataset_org_plan_sub.dataset.server_configuration);
+
+    let limit_triggers_24h =
+        get_preprocessing_event_counts_query(dataset_org_plan_sub.dataset.id, clickhouse_client)
+            .await
+            .ok();
+
+    Ok(HttpResponse::Ok().json(DatasetModelSettings {
+        settings: ResolvedModelSettings::from_dataset_config(&dataset_config)?,
+        limit_triggers_24h,
+    }))
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct GetEmbeddingAuditLogReqPayload {
+    /// ISO8601 time of the oldest entries to fetch, inclusive. Defaults to the first entry.
+    pub start: Option<String>,
+    /// ISO8601 time up to which entries are fetched, exclusive. Defaults to now.
+    pub end: Option<String>,
+    /// Page of entries to fetch, newest first. Defaults to 1.
+    pub page: Option<u64>,
+    /// Number of entries per page. Defaults to 10.
+    pub page_size: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct EmbeddingAuditLogResponseBody {
+    pub entries: Vec<EmbeddingAuditEntry>,
+}
+
+fn parse_audit_log_time(
+    time: Option<&String>,
+    field: &str,
+) -> Result<Option<chrono::NaiveDateTime>, ServiceError> {
+    time.map(|time| {
+        time.parse::<DateTimeUtc>()
+            .map(|time| time.0.naive_utc())
+            .map_err(|_| ServiceError::BadRequest(format!("Invalid {} timestamp format", field)))
+    })
+    .transpose()
+}
+
+/// Get Embedding Audit Log
+///
+/// Get the calls made to the embedding and sparse servers for the dataset while its EMBEDDING_AUDIT_LOG_ENABLED setting was on. Each entry records the server's origin, the number and total characters of the texts sent with a digest of them, and the outcome. The texts themselves are never stored. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    post,
+    path = "/dataset/embedding_audit_log",
+    context_path = "/api",
+    tag = "Dataset",
+    request_body(content = GetEmbeddingAuditLogReqPayload, description = "JSON request payload to page through the embedding audit log", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Embedding audit log entries of the dataset", body = EmbeddingAuditLogResponseBody),
+        (status = 400, description = "Service error relating to getting the embedding audit log", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = uuid::Uuid, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+pub async fn get_embedding_audit_log(
+    data: web::Json<GetEmbeddingAuditLogReqPayload>,
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    let entries = get_embedding_audit_entries_query(
+        dataset_org_plan_sub.dataset.id,
+        parse_audit_log_time(data.start.as_ref(), "start")?,
+        parse_audit_log_time(data.end.as_ref(), "end")?,
+        data.page.unwrap_or(1),
+        data.page_size.unwrap_or(10),
+        pool,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(EmbeddingAuditLogResponseBody { entries }))
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct GetBm25TermTokensReqPayload {
+    /// BM25 term ids to resolve, at most 1000
+    pub term_ids: Vec<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "term_tokens": {"553238108": ["search"], "867809535": ["term15470", "term98506"]},
+}))]
+pub struct Bm25TermTokensResponseBody {
+    /// The stems each recorded term id was hashed from. Ids can collide, so an id may list several candidates. Ids which were never recorded are left out.
+    pub term_tokens: std::collections::BTreeMap<u32, Vec<String>>,
+}
+
+/// Get BM25 Term Tokens
+///
+/// Resolve BM25 term ids, e.g. the indices of a bm25 sparse vector, back to the stems they were hashed from. Only ids seen while ingesting with the dataset's BM25_TERM_TOKENS_ENABLED setting on can be resolved, and the least recently seen are dropped past BM25_TERM_TOKENS_MAX_ENTRIES. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    post,
+    path = "/dataset/bm25_term_tokens",
+    context_path = "/api",
+    tag = "Dataset",
+    request_body(content = GetBm25TermTokensReqPayload, description = "JSON request payload with the term ids to resolve", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Candidate stems of the term ids", body = Bm25TermTokensResponseBody),
+        (status = 400, description = "Service error relating to resolving the term ids", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = uuid::Uuid, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+pub async fn get_bm25_term_tokens(
+    data: web::Json<GetBm25TermTokensReqPayload>,
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    if data.term_ids.len() > 1000 {
+        return Err(ServiceError::BadRequest(
+            "At most 1000 term_ids can be resolved per request".to_string(),
+        ));
+    }
+
+    let term_tokens =
+        get_bm25_term_tokens_query(dataset_org_plan_sub.dataset.id, data.term_ids.clone(), pool)
+            .await?;
+
+    Ok(HttpResponse::Ok().json(Bm25TermTokensResponseBody {
+        term_tokens: term_tokens
+            .into_map()
+            .into_iter()
+            .map(|(term_id, tokens)| (term_id, tokens.into_iter().collect()))
+            .collect(),
+    }))
+}
+
+/// Cutover Embedding Model
+///
+/// Switch a dataset which is migrating to a new embedding model over to it. While the dataset's EMBEDDING_DUAL_WRITE_TARGET is set, ingestion stores vectors from both models and search keeps reading the current model's vectors. Once every chunk has a vector from the target model, which the dual-write backfill takes care of for chunks stored before the migration started, this makes the target the dataset's embedding model and moves search to its vectors in one update. Fails while chunks are still missing a vector from the target model. Auth'ed user or api key must have an owner role for the specified dataset's organization.
+#[utoipa::path(
+    post,
+    path = "/dataset/embedding_cutover",
+    context_path = "/api",
+    tag = "Dataset",
+    responses(
+        (status = 200, description = "Dataset with the target model as its embedding model", body = Dataset),
+        (status = 400, description = "Service error relating to cutting the dataset over to its target embedding model", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = uuid::Uuid, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+    ),
+    security(
+        ("ApiKey" = ["owner"]),
+    )
+)]
+pub async fn cutover_embedding_model(
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+    user: OwnerOnly,
+) -> Result<HttpResponse, ServiceError> {
+    if !verify_owner(&user, &dataset_org_plan_sub.organization.organization.id) {
+        return Err(ServiceError::Forbidden);
+    }
+
+    let dataset = cutover_embedding_model_query(dataset_org_plan_sub.dataset, pool).await?;
+
+    Ok(HttpResponse::Ok().json(dataset))
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[schema(example = json!({
+    "target_dataset_id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+}))]
+pub struct CloneDatasetReqPayload {
+    /// Id of the dataset the chunks are copied into. It must belong to the same organization as the dataset in the TR-Dataset header.
+    pub target_dataset_id: uuid::Uuid,
+}
+
+/// Clone Dataset
+///
+/// Copy every chunk of the dataset in the TR-Dataset header into another dataset of the same organization. When both datasets embed chunks the same way (embedding model and base url, dimensions, prefixes, dense post-processing and fulltext strategy), the stored dense and sparse vectors are copied as they are and no embedding calls are made. Otherwise the job's reembed_reason lists the settings which differ and the chunks are queued for ingestion into the target dataset to be embedded again. Groups and boosts are not copied. The clone runs in the background, poll the returned job for its progress. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    post,
+    path = "/dataset/clone",
+    context_path = "/api",
+    tag = "Dataset",
+    request_body(content = CloneDatasetReqPayload, description = "JSON request payload to clone the dataset", content_type = "application/json"),
+    responses(
+        (status = 200, description = "The queued clone job", body = DatasetCloneJob),
+        (status = 400, description = "Service error relating to cloning the dataset", body = ErrorResponseBody),
+        (status = 404, description = "Target dataset not found", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = uuid::Uuid, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+pub async fn clone_dataset(
+    data: web::Json<CloneDatasetReqPayload>,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+    user: AdminOnly,
+) -> Result<HttpResponse, ServiceError> {
+    let target_dataset = get_dataset_by_id_query(data.target_dataset_id, pool.clone()).await?;
+
+    if !verify_admin(&user, &dataset_org_plan_sub.organization.organization.id)
+        || target_dataset.organization_id != dataset_org_plan_sub.dataset.organization_id
+    {
+        return Err(ServiceError::Forbidden);
+    }
+
+    let job = queue_dataset_clone_query(
+        dataset_org_plan_sub.dataset,
+        target_dataset,
+        pool,
+        redis_pool,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(job))
+}
+
+/// Get Dataset Clone Job
+///
+/// Get the status and progress of a clone of the dataset in the TR-Dataset header. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    get,
+    path = "/dataset/clone/{job_id}",
+    context_path = "/api",
+    tag = "Dataset",
+    responses(
+        (status = 200, description = "The clone job", body = DatasetCloneJob),
+        (status = 400, description = "Service error relating to getting the clone job", body = ErrorResponseBody),
+        (status = 404, description = "Clone job not found", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = uuid::Uuid, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+        ("job_id" = uuid::Uuid, Path, description = "The id of the clone job returned when the clone was queued."),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+pub async fn get_dataset_clone_job(
+    job_id: web::Path<uuid::Uuid>,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    redis_pool: web::Data<RedisPool>,
+    user: AdminOnly,
+) -> Result<HttpResponse, ServiceError> {
+    if !verify_admin(&user, &dataset_org_plan_sub.organization.organization.id) {
+        return Err(ServiceError::Forbidden);
+    }
+
+    let job = get_dataset_clone_job_query(job_id.into_inner(), redis_pool).await?;
+    if job.source_dataset_id != dataset_org_plan_sub.dataset.id {
+        return Err(ServiceError::NotFound("Clone job not found".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(job))
+}
+
 #[derive(Serialize, Deserialize, Debug, ToSchema)]
 pub struct CreateBatchDataset {
     /// Name of the dataset.
@@ -811,17 +1193,22 @@ pub async fn batch_create_datasets(
         .datasets
         .iter()
         .map(|d| {
-            Dataset::from_details(
+            let mut server_configuration: DatasetConfiguration = d
+                .server_configuration
+                .clone()
+                .map(|c| c.into())
+                .unwrap_or_default();
+            resolve_embedding_prefixes(&mut server_configuration);
+            validate_dataset_configuration(&server_configuration)?;
+
+            Ok(Dataset::from_details(
                 d.dataset_name.clone(),
                 org_with_sub_and_plan.organization.id,
                 d.tracking_id.clone(),
-                d.server_configuration
-                    .clone()
-                    .map(|c| c.into())
-                    .unwrap_or_default(),
-            )
+                server_configuration,
+            ))
         })
-        .collect::<Vec<_>>();
+        .collect::<Result<Vec<_>, ServiceError>>()?;
 
     let created_or_upserted_datasets =
         create_datasets_query(datasets, data.upsert, pool.clone()).await?;
@@ -1,7 +1,8 @@
 use super::{
     auth_handler::{AdminOnly, LoggedUser},
     chunk_handler::{
-        parse_query, ChunkFilter, ParsedQuery, ParsedQueryTypes, SearchChunksReqPayload,
+        parse_query, ChunkFilter, ParsedQuery, ParsedQueryTypes, ScoringOptions,
+        SearchChunksReqPayload,
     },
 };
 use crate::{
@@ -1462,6 +1463,8 @@ pub struct SearchWithinGroupReqPayload {
     pub search_type: SearchMethod,
     /// Sort Options lets you specify different methods to rerank the chunks in the result set. If not specified, this defaults to the score of the chunks.
     pub sort_options: Option<SortOptions>,
+    /// Scoring options provides ways to modify the sparse or dense vector created for the query in order to change how potential matches are scored. If not specified, this defaults to no modifications.
+    pub scoring_options: Option<ScoringOptions>,
     /// Highlight Options lets you specify different methods to highlight the chunks in the result set. If not specified, this defaults to the score of the chunks.
     pub highlight_options: Option<HighlightOptions>,
     /// Set score_threshold to a float to filter out chunks with a score below the threshold. This threshold applies before weight and bias modifications. If not specified, this defaults to 0.0.
@@ -1489,7 +1492,7 @@ impl From<SearchWithinGroupReqPayload> for SearchChunksReqPayload {
             filters: search_within_group_data.filters,
             search_type: search_within_group_data.search_type,
             sort_options: search_within_group_data.sort_options,
-            scoring_options: None,
+            scoring_options: search_within_group_data.scoring_options,
             highlight_options: search_within_group_data.highlight_options,
             score_threshold: search_within_group_data.score_threshold,
             slim_chunks: search_within_group_data.slim_chunks,
@@ -1498,6 +1501,12 @@ impl From<SearchWithinGroupReqPayload> for SearchChunksReqPayload {
             remove_stop_words: search_within_group_data.remove_stop_words,
             user_id: search_within_group_data.user_id,
             typo_options: search_within_group_data.typo_options,
+            query_vector: None,
+            query_sparse_vector: None,
+            latency_budget_ms: None,
+            fusion_options: None,
+            query_language: None,
+            vector_field_weights: None,
         }
     }
 }
@@ -1722,6 +1731,8 @@ pub struct SearchOverGroupsReqPayload {
     pub use_quote_negated_terms: Option<bool>,
     /// Sort Options lets you specify different methods to rerank the chunks in the result set. If not specified, this defaults to the score of the chunks.
     pub sort_options: Option<SortOptions>,
+    /// Scoring options provides ways to modify the sparse or dense vector created for the query in order to change how potential matches are scored. If not specified, this defaults to no modifications.
+    pub scoring_options: Option<ScoringOptions>,
     /// If true, stop words (specified in server/src/stop-words.txt in the git repo) will be removed. Queries that are entirely stop words will be
     /// preserved.
     pub remove_stop_words: Option<bool>,
@@ -1,5 +1,16 @@
-use crate::{data::models::RedisPool, errors::ServiceError};
-use actix_web::{web, HttpResponse};
+use crate::{
+    data::models::RedisPool,
+    errors::ServiceError,
+    operators::model_operator::{
+        get_failure_injection_settings, get_raw_provider_responses, FailureInjectionSettings,
+        RawProviderResponse, EMBEDDING_RATE_LIMIT_REQUESTS_COUNTER,
+        EMBEDDING_RATE_LIMIT_WAIT_HISTOGRAM, EMBEDDING_TOKENS_COUNTER, INJECTED_FAILURES_COUNTER,
+        PROVIDER_RESPONSE_ERRORS_COUNTER, REJECTED_VECTORS_COUNTER, RERANK_QUEUE_FALLBACK_COUNTER,
+        RERANK_QUEUE_WAIT_HISTOGRAM, SPARSE_VECTOR_ACTIVE_TERMS_HISTOGRAM,
+        SPARSE_VECTOR_MAX_WEIGHT_HISTOGRAM, SPARSE_VECTOR_MEAN_WEIGHT_HISTOGRAM,
+    },
+};
+use actix_web::{web, App, HttpResponse, HttpServer};
 use prometheus::{opts, register_counter_vec, CounterVec, Encoder, Error, Gauge, Registry};
 
 #[derive(Clone, Debug)]
@@ -74,6 +85,18 @@ impl Metrics {
         )?;
         registry.register(Box::new(api_error_gauge.clone()))?;
 
+        registry.register(Box::new(RERANK_QUEUE_WAIT_HISTOGRAM.clone()))?;
+        registry.register(Box::new(RERANK_QUEUE_FALLBACK_COUNTER.clone()))?;
+        registry.register(Box::new(EMBEDDING_RATE_LIMIT_REQUESTS_COUNTER.clone()))?;
+        registry.register(Box::new(EMBEDDING_RATE_LIMIT_WAIT_HISTOGRAM.clone()))?;
+        registry.register(Box::new(EMBEDDING_TOKENS_COUNTER.clone()))?;
+        registry.register(Box::new(PROVIDER_RESPONSE_ERRORS_COUNTER.clone()))?;
+        registry.register(Box::new(INJECTED_FAILURES_COUNTER.clone()))?;
+        registry.register(Box::new(REJECTED_VECTORS_COUNTER.clone()))?;
+        registry.register(Box::new(SPARSE_VECTOR_ACTIVE_TERMS_HISTOGRAM.clone()))?;
+        registry.register(Box::new(SPARSE_VECTOR_MAX_WEIGHT_HISTOGRAM.clone()))?;
+        registry.register(Box::new(SPARSE_VECTOR_MEAN_WEIGHT_HISTOGRAM.clone()))?;
+
         Ok(Metrics {
             registry,
             ingest_queue_gauge,
@@ -159,12 +182,84 @@ impl Metrics {
     }
 
     pub fn get_response(&self) -> String {
-        let mut buffer = vec![];
-        let encoder = prometheus::TextEncoder::new();
-        let metric_families = self.registry.gather();
-        encoder.encode(&metric_families, &mut buffer).unwrap();
-        String::from_utf8(buffer).unwrap()
+        encode_registry(&self.registry)
+    }
+}
+
+fn encode_registry(registry: &Registry) -> String {
+    let mut buffer = vec![];
+    let encoder = prometheus::TextEncoder::new();
+    let metric_families = registry.gather();
+    encoder.encode(&metric_families, &mut buffer).unwrap();
+    String::from_utf8(buffer).unwrap()
+}
+
+/// Registry of the model call metrics the ingestion worker records. They are recorded in the
+/// worker's process, so the server's registry never sees them, `serve_worker_metrics` exposes
+/// them for prometheus to scrape from the worker instead.
+pub fn get_ingestion_worker_registry() -> Result<Registry, Error> {
+    let registry = Registry::new();
+
+    registry.register(Box::new(SPARSE_VECTOR_ACTIVE_TERMS_HISTOGRAM.clone()))?;
+    registry.register(Box::new(SPARSE_VECTOR_MAX_WEIGHT_HISTOGRAM.clone()))?;
+    registry.register(Box::new(SPARSE_VECTOR_MEAN_WEIGHT_HISTOGRAM.clone()))?;
+    registry.register(Box::new(REJECTED_VECTORS_COUNTER.clone()))?;
+    registry.register(Box::new(EMBEDDING_TOKENS_COUNTER.clone()))?;
+
+    Ok(registry)
+}
+
+async fn get_worker_metrics(
+    req: actix_web::HttpRequest,
+    registry: web::Data<Registry>,
+) -> HttpResponse {
+    if !check_x_api_access(&req) {
+        return HttpResponse::Unauthorized().finish();
     }
+
+    HttpResponse::Ok()
+        .content_type("text/plain")
+        .body(encode_registry(&registry))
+}
+
+/// Serves `registry` at `/metrics` on `WORKER_METRICS_PORT` from a thread of its own, behind the
+/// same `ADMIN_API_KEY` check as the server's metrics. Workers run no http server otherwise, so
+/// nothing is served when the port is not set.
+pub fn serve_worker_metrics(registry: Registry) {
+    let port = match std::env::var("WORKER_METRICS_PORT")
+        .ok()
+        .filter(|port| !port.is_empty())
+    {
+        Some(port) => match port.parse::<u16>() {
+            Ok(port) => port,
+            Err(_) => {
+                log::error!(
+                    "WORKER_METRICS_PORT {} is not a port, metrics are not served",
+                    port
+                );
+                return;
+            }
+        },
+        None => return,
+    };
+
+    std::thread::spawn(move || {
+        let registry = web::Data::new(registry);
+        let served = actix_web::rt::System::new().block_on(async move {
+            HttpServer::new(move || {
+                App::new()
+                    .app_data(registry.clone())
+                    .route("/metrics", web::get().to(get_worker_metrics))
+            })
+            .workers(1)
+            .bind(("0.0.0.0", port))?
+            .run()
+            .await
+        });
+        if let Err(err) = served {
+            log::error!("Failed to serve worker metrics on port {}: {:?}", port, err);
+        }
+    });
 }
 
 fn check_x_api_access(req: &actix_web::HttpRequest) -> bool {
@@ -215,3 +310,55 @@ pub async fn get_metrics(
     let response = metrics.get_response();
     Ok(HttpResponse::Ok().content_type("text/plain").body(response))
 }
+
+/// Get Raw Provider Responses
+///
+/// This route returns the most recent raw responses received from embedding and rerank servers. Responses are only captured when the server is started with `DEBUG_RAW_PROVIDER_RESPONSES=true`.
+#[utoipa::path(
+    get,
+    path = "/metrics/provider_responses",
+    tag = "Metrics",
+    responses(
+        (status = 200, description = "Raw responses from the embedding and rerank servers", body = Vec<RawProviderResponse>),
+        (status = 401, description = "Unauthorized", body = ErrorResponseBody),
+    ),
+    security(
+        ("X-API-KEY" = []),
+    )
+)]
+pub async fn get_provider_responses(
+    req: actix_web::HttpRequest,
+) -> Result<HttpResponse, actix_web::Error> {
+    let authed = check_x_api_access(&req);
+    if !authed {
+        return Ok(HttpResponse::Unauthorized().finish());
+    }
+
+    Ok(HttpResponse::Ok().json(get_raw_provider_responses()))
+}
+
+/// Get Failure Injection Settings
+///
+/// This route returns the failure injection rules used for chaos testing the model servers and whether they are applied. Rules are only applied when the server is started with `FAILURE_INJECTION_ENABLED=true`, injected failures are counted in the `tr_injected_failures` metric.
+#[utoipa::path(
+    get,
+    path = "/metrics/failure_injection",
+    tag = "Metrics",
+    responses(
+        (status = 200, description = "The failure injection settings of the server", body = FailureInjectionSettings),
+        (status = 401, description = "Unauthorized", body = ErrorResponseBody),
+    ),
+    security(
+        ("X-API-KEY" = []),
+    )
+)]
+pub async fn get_failure_injection(
+    req: actix_web::HttpRequest,
+) -> Result<HttpResponse, actix_web::Error> {
+    let authed = check_x_api_access(&req);
+    if !authed {
+        return Ok(HttpResponse::Unauthorized().finish());
+    }
+
+    Ok(HttpResponse::Ok().json(get_failure_injection_settings()))
+}
@@ -9,8 +9,11 @@ use crate::{
     errors::{custom_json_error_handler, ServiceError},
     handlers::{auth_handler::build_oidc_client, metrics_handler::Metrics},
     operators::{
-        clickhouse_operator::EventQueue, qdrant_operator::create_new_qdrant_collection_query,
-        typo_operator::BKTreeCache, user_operator::create_default_user,
+        clickhouse_operator::EventQueue,
+        model_operator::{check_model_endpoints, validate_model_env},
+        qdrant_operator::create_new_qdrant_collection_query,
+        typo_operator::BKTreeCache,
+        user_operator::create_default_user,
     },
 };
 use actix_cors::Cors;
@@ -116,6 +119,27 @@ macro_rules! get_env {
         ENV_VAR.as_str()
     }};
 }
+
+/// `get_env!` for variables which may be left unset or empty, `None` then.
+#[macro_export]
+#[cfg(not(feature = "runtime-env"))]
+macro_rules! get_optional_env {
+    ($name:expr) => {
+        option_env!($name).filter(|value| !value.is_empty())
+    };
+}
+
+#[macro_export]
+#[cfg(feature = "runtime-env")]
+macro_rules! get_optional_env {
+    ($name:expr) => {{
+        lazy_static::lazy_static! {
+            static ref ENV_VAR: Option<String> =
+                std::env::var($name).ok().filter(|value| !value.is_empty());
+        }
+        ENV_VAR.as_deref()
+    }};
+}
 struct SecurityAddon;
 
 impl Modify for SecurityAddon {
@@ -196,6 +220,8 @@ impl Modify for SecurityAddon {
         handlers::chunk_handler::get_chunks_by_ids,
         handlers::chunk_handler::scroll_dataset_chunks,
         handlers::chunk_handler::bulk_delete_chunk,
+        handlers::chunk_handler::get_embedding_dead_letters,
+        handlers::chunk_handler::redrive_embedding_dead_letters,
         handlers::dataset_handler::get_all_tags,
         handlers::user_handler::update_user,
         handlers::user_handler::get_user_api_keys,
@@ -242,6 +268,13 @@ impl Modify for SecurityAddon {
         handlers::dataset_handler::get_dataset_by_tracking_id,
         handlers::dataset_handler::get_dataset_crawl_options,
         handlers::dataset_handler::get_usage_by_dataset_id,
+        handlers::dataset_handler::get_bm25_stats,
+        handlers::dataset_handler::get_dataset_model_settings,
+        handlers::dataset_handler::get_embedding_audit_log,
+        handlers::dataset_handler::get_bm25_term_tokens,
+        handlers::dataset_handler::cutover_embedding_model,
+        handlers::dataset_handler::clone_dataset,
+        handlers::dataset_handler::get_dataset_clone_job,
         handlers::dataset_handler::get_datasets_from_organization,
         handlers::dataset_handler::clear_dataset,
         handlers::stripe_handler::direct_to_payment_link,
@@ -263,6 +296,8 @@ impl Modify for SecurityAddon {
         handlers::analytics_handler::get_all_events,
         handlers::analytics_handler::get_event_by_id,
         handlers::metrics_handler::get_metrics,
+        handlers::metrics_handler::get_provider_responses,
+        handlers::metrics_handler::get_failure_injection,
         handlers::page_handler::public_page
     ),
     components(
@@ -289,6 +324,9 @@ impl Modify for SecurityAddon {
             handlers::chunk_handler::SplitHtmlResponse,
             handlers::chunk_handler::ChunkedContent,
             handlers::chunk_handler::BatchQueuedChunkResponse,
+            handlers::chunk_handler::InlineChunkEmbedding,
+            handlers::chunk_handler::ReturnEmbeddingsQuery,
+            handlers::chunk_handler::SearchExplainQuery,
             handlers::chunk_handler::ReturnQueuedChunk,
             handlers::chunk_handler::RecommendChunksResponseBody,
             handlers::chunk_handler::RecommendResponseTypes,
@@ -308,14 +346,32 @@ impl Modify for SecurityAddon {
             handlers::chunk_handler::GetTrackingChunksData,
             handlers::chunk_handler::SemanticBoost,
             handlers::chunk_handler::ScoringOptions,
+            handlers::chunk_handler::FusionOptions,
             handlers::chunk_handler::ChunkReturnTypes,
             handlers::chunk_handler::BulkDeleteChunkPayload,
             handlers::chunk_handler::ScrollChunksReqPayload,
             handlers::chunk_handler::ScrollChunksResponseBody,
+            handlers::chunk_handler::GetEmbeddingDeadLettersReqPayload,
+            handlers::chunk_handler::EmbeddingDeadLettersResponseBody,
+            handlers::chunk_handler::RedriveEmbeddingDeadLettersReqPayload,
+            handlers::chunk_handler::RedriveEmbeddingDeadLettersResponseBody,
             handlers::chunk_handler::V1RecommendChunksResponseBody,
             handlers::dataset_handler::TagsWithCount,
             handlers::dataset_handler::GetAllTagsReqPayload,
             handlers::dataset_handler::GetAllTagsResponse,
+            handlers::dataset_handler::GetBm25StatsQuery,
+            operators::model_operator::Bm25CorpusStats,
+            operators::model_operator::ResolvedModelSettings,
+            operators::model_operator::ResolvedEmbeddingSettings,
+            operators::model_operator::ResolvedSparseSettings,
+            operators::model_operator::ResolvedRerankSettings,
+            operators::event_operator::PreprocessingEventCount,
+            handlers::dataset_handler::DatasetModelSettings,
+            handlers::dataset_handler::GetEmbeddingAuditLogReqPayload,
+            handlers::dataset_handler::EmbeddingAuditLogResponseBody,
+            handlers::dataset_handler::GetBm25TermTokensReqPayload,
+            handlers::dataset_handler::Bm25TermTokensResponseBody,
+            handlers::dataset_handler::CloneDatasetReqPayload,
             handlers::dataset_handler::GetCrawlOptionsResponse,
             handlers::dataset_handler::Datasets,
             data::models::UserApiKey,
@@ -364,6 +420,12 @@ impl Modify for SecurityAddon {
             operators::search_operator::SearchOverGroupsResults,
             operators::search_operator::SearchOverGroupsResponseBody,
             operators::search_operator::SearchOverGroupsResponseTypes,
+            operators::search_operator::SearchExplainResponse,
+            operators::search_operator::ExplainedModelCall,
+            operators::model_operator::RawProviderResponse,
+            operators::model_operator::FailureInjectionSettings,
+            operators::model_operator::FailureInjectionRule,
+            operators::model_operator::InjectedFailureKind,
             handlers::dataset_handler::CreateDatasetReqPayload,
             handlers::dataset_handler::CreateBatchDataset,
             handlers::dataset_handler::CreateDatasetBatchReqPayload,
@@ -492,6 +554,10 @@ impl Modify for SecurityAddon {
             data::models::Topic,
             data::models::Message,
             data::models::ChunkMetadata,
+            data::models::EmbeddingDeadLetter,
+            data::models::EmbeddingAuditEntry,
+            data::models::DatasetCloneJob,
+            data::models::DatasetCloneStatus,
             data::models::ChatMessageProxy,
             data::models::WorkerEvent,
             data::models::File,
@@ -523,12 +589,21 @@ impl Modify for SecurityAddon {
             data::models::GeoTypes,
             data::models::ChunkMetadataWithPosition,
             data::models::ScoreChunkDTO,
+            data::models::HighlightRegion,
             data::models::ChunkMetadataTypes,
             data::models::ContentChunkMetadata,
             data::models::ChunkMetadataStringTagSet,
             data::models::ConditionType,
             data::models::HasChunkIDCondition,
             data::models::DistanceMetric,
+            data::models::DenseVectorSlot,
+            data::models::EmbeddingDualWriteTarget,
+            data::models::DenseNormalization,
+            data::models::HybridFusion,
+            data::models::ContentLanguage,
+            data::models::DenseQuantization,
+            data::models::RerankScoreNormalization,
+            data::models::EmbeddingProvider,
             data::models::PublicDatasetOptions,
             data::models::Invitation,
             errors::ErrorResponseBody,
@@ -563,6 +638,11 @@ pub fn main() -> std::io::Result<()> {
         .filter_level(log::LevelFilter::Info)
         .init();
 
+    if let Err(report) = validate_model_env() {
+        log::error!("{}", report);
+        std::process::exit(1);
+    }
+
     let database_url = get_env!("DATABASE_URL", "DATABASE_URL should be set");
     let redis_url = get_env!("REDIS_URL", "REDIS_URL should be set");
 
@@ -570,6 +650,11 @@ pub fn main() -> std::io::Result<()> {
     run_migrations(database_url);
 
     actix_web::rt::System::new().block_on(async move {
+        if let Err(report) = check_model_endpoints().await {
+            log::error!("{}", report);
+            std::process::exit(1);
+        }
+
         // create db connection pool
         let mut config = ManagerConfig::default();
         config.custom_setup = Box::new(establish_connection);
@@ -772,6 +857,14 @@ pub fn main() -> std::io::Result<()> {
                 .service(
                     web::resource("/metrics")
                     .route(web::get().to(handlers::metrics_handler::get_metrics))
+                )
+                .service(
+                    web::resource("/metrics/provider_responses")
+                    .route(web::get().to(handlers::metrics_handler::get_provider_responses))
+                )
+                .service(
+                    web::resource("/metrics/failure_injection")
+                    .route(web::get().to(handlers::metrics_handler::get_failure_injection))
                 ).service(
                     web::resource("/builder-webhook")
                     .route(web::post().to(handlers::webhook_handler::builder_io_webhook))
@@ -801,6 +894,14 @@ pub fn main() -> std::io::Result<()> {
                                     web::resource("/scroll")
                                         .route(web::post().to(handlers::chunk_handler::scroll_dataset_chunks))
                                 )
+                                .service(
+                                    web::resource("/dead_letters")
+                                        .route(web::post().to(handlers::chunk_handler::get_embedding_dead_letters))
+                                )
+                                .service(
+                                    web::resource("/dead_letters/redrive")
+                                        .route(web::post().to(handlers::chunk_handler::redrive_embedding_dead_letters))
+                                )
                         )
                         .service(
                             web::scope("/dataset")
@@ -835,6 +936,34 @@ pub fn main() -> std::io::Result<()> {
                                     web::resource("/get_all_tags")
                                         .route(web::post().to(handlers::dataset_handler::get_all_tags)),
                                 )
+                                .service(
+                                    web::resource("/bm25_stats")
+                                        .route(web::get().to(handlers::dataset_handler::get_bm25_stats)),
+                                )
+                                .service(
+                                    web::resource("/model_settings")
+                                        .route(web::get().to(handlers::dataset_handler::get_dataset_model_settings)),
+                                )
+                                .service(
+                                    web::resource("/embedding_audit_log")
+                                        .route(web::post().to(handlers::dataset_handler::get_embedding_audit_log)),
+                                )
+                                .service(
+                                    web::resource("/bm25_term_tokens")
+                                        .route(web::post().to(handlers::dataset_handler::get_bm25_term_tokens)),
+                                )
+                                .service(
+                                    web::resource("/embedding_cutover")
+                                        .route(web::post().to(handlers::dataset_handler::cutover_embedding_model)),
+                                )
+                                .service(
+                                    web::resource("/clone")
+                                        .route(web::post().to(handlers::dataset_handler::clone_dataset)),
+                                )
+                                .service(
+                                    web::resource("/clone/{job_id}")
+                                        .route(web::get().to(handlers::dataset_handler::get_dataset_clone_job)),
+                                )
                                 .service(
                                     web::resource("/events")
                                         .route(web::post().to(handlers::event_handler::get_events)),
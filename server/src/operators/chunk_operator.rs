@@ -2480,6 +2480,8 @@ pub async fn create_chunk_metadata(
             dataset_id: dataset_uuid,
             chunk: chunk.clone(),
             upsert_by_tracking_id: chunk.upsert_by_tracking_id.unwrap_or(false),
+            dense_vector: None,
+            sparse_vector: None,
         };
 
         ingestion_messages.push(upload_message);
@@ -2488,6 +2490,7 @@ pub async fn create_chunk_metadata(
     Ok((
         BulkUploadIngestionMessage {
             attempt_number: 0,
+            rejected_attempts: 0,
             dataset_id: dataset_uuid,
             ingestion_messages,
         },
@@ -2662,3 +2665,39 @@ pub async fn get_last_processed_from_clickhouse(
 
     Ok(last_processed)
 }
+
+pub async fn get_chunk_html_sample_query(
+    dataset_id: uuid::Uuid,
+    sample_size: i64,
+    pool: web::Data<Pool>,
+) -> Result<(Vec<String>, i64), ServiceError> {
+    use crate::data::schema::chunk_metadata::dsl as chunk_metadata_columns;
+
+    let mut conn = pool.get().await.map_err(|_e| {
+        ServiceError::InternalServerError("Failed to get postgres connection".to_string())
+    })?;
+
+    let chunk_count = chunk_metadata_columns::chunk_metadata
+        .filter(chunk_metadata_columns::dataset_id.eq(dataset_id))
+        .count()
+        .get_result::<i64>(&mut conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(format!("Failed to count chunks {}", err)))?;
+
+    let chunk_htmls: Vec<Option<String>> = chunk_metadata_columns::chunk_metadata
+        .filter(chunk_metadata_columns::dataset_id.eq(dataset_id))
+        .select(chunk_metadata_columns::chunk_html)
+        .order(sql::<sql_types::Double>("RANDOM()"))
+        .limit(sample_size)
+        .load(&mut conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(format!("Failed to sample chunks {}", err)))?;
+
+    Ok((
+        chunk_htmls
+            .into_iter()
+            .map(|chunk_html| convert_html_to_text(&chunk_html.unwrap_or_default()))
+            .collect(),
+        chunk_count,
+    ))
+}
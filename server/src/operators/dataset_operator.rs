@@ -1,14 +1,20 @@
 use crate::data::models::{
-    DatasetAndOrgWithSubAndPlan, DatasetAndUsage, DatasetConfiguration, DatasetUsageCount,
+    ChunkData, ChunkMetadata, ChunkMetadataTypes, ContentLanguage, DatasetAndOrgWithSubAndPlan,
+    DatasetAndUsage, DatasetCloneJob, DatasetCloneStatus, DatasetConfiguration, DatasetUsageCount,
     Organization, OrganizationWithSubAndPlan, RedisPool, StripePlan, StripeSubscription, UnifiedId,
     WordDataset,
 };
-use crate::handlers::chunk_handler::ChunkFilter;
+use crate::handlers::chunk_handler::{BulkUploadIngestionMessage, ChunkFilter, ChunkReqPayload};
 use crate::handlers::dataset_handler::{GetDatasetsPagination, TagsWithCount};
-use crate::operators::chunk_operator::bulk_delete_chunks_query;
+use crate::operators::chunk_operator::{
+    bulk_delete_chunks_query, bulk_insert_chunk_metadata_query, create_chunk_metadata,
+    get_chunk_metadatas_from_point_ids, update_dataset_chunk_count,
+};
 use crate::operators::clickhouse_operator::ClickHouseEvent;
 use crate::operators::qdrant_operator::{
+    bulk_upsert_qdrant_points_query, count_pending_dual_write_points_query,
     delete_points_from_qdrant, get_qdrant_collection_from_dataset_config,
+    scroll_dataset_points_query, MAX_EMBEDDING_VECTOR_FIELDS,
 };
 use crate::{
     data::models::{Dataset, EventType, Pool, WorkerEvent},
@@ -22,10 +28,884 @@ use diesel::result::{DatabaseErrorKind, Error as DBError};
 use diesel::upsert::excluded;
 use diesel_async::RunQueryDsl;
 use itertools::Itertools;
+use qdrant_client::qdrant::{point_id::PointIdOptions, PointId, PointStruct, RetrievedPoint};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use time::{format_description, OffsetDateTime};
 
 use super::clickhouse_operator::EventQueue;
+use super::model_operator::{
+    has_stopword_list, resolve_embedding_base_url, resolve_embedding_prefixes,
+    validate_dense_post_processing, validate_pii_patterns, validate_provider_capabilities,
+    validate_text_template, CONTENT_VECTOR_FIELD, MAX_RERANK_HIGHLIGHT_WINDOWS,
+};
+
+/// Rejects dataset configurations which would only fail later at ingestion or search time.
+pub fn validate_dataset_configuration(
+    dataset_config: &DatasetConfiguration,
+) -> Result<(), ServiceError> {
+    if let Some(patterns) = dataset_config.EMBEDDING_PII_PATTERNS.as_ref() {
+        validate_pii_patterns(patterns)?;
+    }
+    validate_dense_post_processing(dataset_config)?;
+    validate_provider_capabilities(dataset_config)?;
+    validate_text_template(
+        "EMBEDDING_TEMPLATE",
+        dataset_config.EMBEDDING_TEMPLATE.as_ref(),
+    )?;
+    validate_text_template("RERANK_TEMPLATE", dataset_config.RERANK_TEMPLATE.as_ref())?;
+    if !(dataset_config.EMBEDDING_RATE_LIMIT_WEIGHT > 0.0
+        && dataset_config.EMBEDDING_RATE_LIMIT_WEIGHT.is_finite())
+    {
+        return Err(ServiceError::BadRequest(
+            "EMBEDDING_RATE_LIMIT_WEIGHT must be a positive number".to_string(),
+        ));
+    }
+
+    if !(dataset_config.EMBEDDING_MIN_NORM >= 0.0 && dataset_config.EMBEDDING_MIN_NORM.is_finite())
+    {
+        return Err(ServiceError::BadRequest(
+            "EMBEDDING_MIN_NORM must be a non-negative number".to_string(),
+        ));
+    }
+
+    if dataset_config
+        .EMBEDDING_MAX_NORM_DEVIATION
+        .is_some_and(|deviation| !(deviation > 1.0 && deviation.is_finite()))
+    {
+        return Err(ServiceError::BadRequest(
+            "EMBEDDING_MAX_NORM_DEVIATION must be a number greater than 1".to_string(),
+        ));
+    }
+
+    if dataset_config.BM25_MAX_TOKEN_LENGTH == 0 {
+        return Err(ServiceError::BadRequest(
+            "BM25_MAX_TOKEN_LENGTH must be greater than 0".to_string(),
+        ));
+    }
+
+    if dataset_config.BM25_STOPWORDS
+        && dataset_config.BM25_STOPWORD_LIST.is_none()
+        && !has_stopword_list(dataset_config.BM25_LANGUAGE)
+    {
+        return Err(ServiceError::BadRequest(format!(
+            "There is no stopword list for BM25_LANGUAGE {}, set BM25_STOPWORD_LIST to enable BM25_STOPWORDS",
+            dataset_config.BM25_LANGUAGE
+        )));
+    }
+
+    if dataset_config.BOOST_PHRASE_MAX_TOKENS == 0 {
+        return Err(ServiceError::BadRequest(
+            "BOOST_PHRASE_MAX_TOKENS must be greater than 0".to_string(),
+        ));
+    }
+
+    if dataset_config.BM25_TERM_TOKENS_MAX_ENTRIES == 0 {
+        return Err(ServiceError::BadRequest(
+            "BM25_TERM_TOKENS_MAX_ENTRIES must be greater than 0".to_string(),
+        ));
+    }
+
+    if dataset_config.EMBEDDING_CHARS_PER_TOKEN == 0 {
+        return Err(ServiceError::BadRequest(
+            "EMBEDDING_CHARS_PER_TOKEN must be greater than 0".to_string(),
+        ));
+    }
+
+    if dataset_config.RERANKER_MAX_TOKENS == Some(0) {
+        return Err(ServiceError::BadRequest(
+            "RERANKER_MAX_TOKENS must be greater than 0".to_string(),
+        ));
+    }
+
+    if dataset_config.EMBEDDING_BATCH_SIZE == 0 {
+        return Err(ServiceError::BadRequest(
+            "EMBEDDING_BATCH_SIZE must be greater than 0".to_string(),
+        ));
+    }
+
+    if dataset_config.EMBEDDING_TIMEOUT_MS == Some(0) {
+        return Err(ServiceError::BadRequest(
+            "EMBEDDING_TIMEOUT_MS must be greater than 0".to_string(),
+        ));
+    }
+
+    if dataset_config.RERANK_HIGHLIGHT_WINDOWS > MAX_RERANK_HIGHLIGHT_WINDOWS {
+        return Err(ServiceError::BadRequest(format!(
+            "RERANK_HIGHLIGHT_WINDOWS can be at most {}",
+            MAX_RERANK_HIGHLIGHT_WINDOWS
+        )));
+    }
+
+    if dataset_config.RERANK_HIGHLIGHT_WINDOW_SIZE == 0 {
+        return Err(ServiceError::BadRequest(
+            "RERANK_HIGHLIGHT_WINDOW_SIZE must be greater than 0".to_string(),
+        ));
+    }
+
+    if !(0.0..=1.0).contains(&dataset_config.HYBRID_FUSION_SEMANTIC_WEIGHT) {
+        return Err(ServiceError::BadRequest(
+            "HYBRID_FUSION_SEMANTIC_WEIGHT must be between 0 and 1".to_string(),
+        ));
+    }
+
+    if dataset_config
+        .EMBED_METADATA_FIELDS
+        .iter()
+        .any(|field| field.trim().is_empty())
+    {
+        return Err(ServiceError::BadRequest(
+            "EMBED_METADATA_FIELDS must not contain empty field names".to_string(),
+        ));
+    }
+
+    let vector_fields = &dataset_config.EMBEDDING_VECTOR_FIELDS;
+    if vector_fields.len() > MAX_EMBEDDING_VECTOR_FIELDS {
+        return Err(ServiceError::BadRequest(format!(
+            "EMBEDDING_VECTOR_FIELDS can hold at most {} fields",
+            MAX_EMBEDDING_VECTOR_FIELDS
+        )));
+    }
+    if vector_fields
+        .iter()
+        .any(|field| field.trim().is_empty() || field == CONTENT_VECTOR_FIELD)
+        || vector_fields.iter().unique().count() != vector_fields.len()
+    {
+        return Err(ServiceError::BadRequest(format!(
+            "EMBEDDING_VECTOR_FIELDS must be distinct, non-empty field names other than \"{}\"",
+            CONTENT_VECTOR_FIELD
+        )));
+    }
+
+    if let Some(target) = dataset_config.EMBEDDING_DUAL_WRITE_TARGET.as_ref() {
+        if target.base_url.trim().is_empty() || target.model_name.trim().is_empty() {
+            return Err(ServiceError::BadRequest(
+                "EMBEDDING_DUAL_WRITE_TARGET needs a base_url and a model_name".to_string(),
+            ));
+        }
+        // Only the content vector has a second slot to write the target model's vectors into
+        if dataset_config.SEPARATE_DISTANCE_PHRASE_VECTORS || !vector_fields.is_empty() {
+            return Err(ServiceError::BadRequest(
+                "EMBEDDING_DUAL_WRITE_TARGET cannot be combined with SEPARATE_DISTANCE_PHRASE_VECTORS or EMBEDDING_VECTOR_FIELDS".to_string(),
+            ));
+        }
+    }
+
+    if let Some(language) = dataset_config
+        .EMBEDDING_QUERY_PREFIXES
+        .iter()
+        .flat_map(|prefixes| prefixes.keys())
+        .find(|language| {
+            serde_json::from_value::<ContentLanguage>(serde_json::json!(language)).is_err()
+        })
+    {
+        return Err(ServiceError::BadRequest(format!(
+            "EMBEDDING_QUERY_PREFIXES has an entry for {}, which is not a supported language code",
+            language
+        )));
+    }
+
+    Ok(())
+}
+
+/// The configuration a dataset switches to at the cutover of its embedding model migration. The
+/// target model becomes the dataset's model and search moves to the slot its vectors were written
+/// to, in the same update. Fails while `pending_points` still lack a vector from the target model.
+pub fn get_embedding_cutover_configuration(
+    dataset_config: &DatasetConfiguration,
+    pending_points: u64,
+) -> Result<DatasetConfiguration, ServiceError> {
+    let target = dataset_config
+        .EMBEDDING_DUAL_WRITE_TARGET
+        .as_ref()
+        .ok_or(ServiceError::BadRequest(
+            "The dataset is not migrating to a new embedding model, set EMBEDDING_DUAL_WRITE_TARGET first".to_string(),
+        ))?;
+    if pending_points > 0 {
+        return Err(ServiceError::BadRequest(format!(
+            "{} chunks have no vector from {} yet, run the dual-write backfill before the cutover",
+            pending_points, target.model_name
+        )));
+    }
+
+    Ok(DatasetConfiguration {
+        EMBEDDING_BASE_URL: target.base_url.clone(),
+        EMBEDDING_MODEL_NAME: target.model_name.clone(),
+        EMBEDDING_VECTOR_SLOT: dataset_config.EMBEDDING_VECTOR_SLOT.other(),
+        EMBEDDING_DUAL_WRITE_TARGET: None,
+        ..dataset_config.clone()
+    })
+}
+
+/// Switches a dual-writing dataset to its target embedding model once the backfill is done.
+pub async fn cutover_embedding_model_query(
+    dataset: Dataset,
+    pool: web::Data<Pool>,
+) -> Result<Dataset, ServiceError> {
+    let dataset_config = DatasetConfiguration::from_json(dataset.server_configuration.clone());
+    let pending_points = if dataset_config.EMBEDDING_DUAL_WRITE_TARGET.is_some() {
+        count_pending_dual_write_points_query(dataset.id, &dataset_config).await?
+    } else {
+        0
+    };
+    let cutover_config = get_embedding_cutover_configuration(&dataset_config, pending_points)?;
+
+    log::info!(
+        "Cutting dataset {} over to embedding model {}",
+        dataset.id,
+        cutover_config.EMBEDDING_MODEL_NAME
+    );
+    update_dataset_query(dataset.id, dataset.name, cutover_config, None, pool).await
+}
+
+pub const DATASET_CLONE_QUEUE: &str = "dataset_clone_queue";
+pub const DATASET_CLONE_PROCESSING: &str = "dataset_clone_processing";
+const DATASET_CLONE_BATCH_SIZE: u32 = 100;
+
+fn get_dataset_clone_job_key(job_id: uuid::Uuid) -> String {
+    format!("dataset_clone_job:{}", job_id)
+}
+
+/// Settings which shape the dense and sparse vectors stored for a chunk, after the same
+/// resolution ingestion applies.
+fn get_stored_vector_settings(
+    dataset_config: &DatasetConfiguration,
+) -> Vec<(&'static str, String)> {
+    let mut resolved_config = dataset_config.clone();
+    resolve_embedding_prefixes(&mut resolved_config);
+    let c = &resolved_config;
+
+    vec![
+        (
+            "EMBEDDING_BASE_URL",
+            // Unresolvable urls are compared as configured, which only ever re-embeds
+            resolve_embedding_base_url(&c.EMBEDDING_BASE_URL)
+                .unwrap_or(c.EMBEDDING_BASE_URL.clone()),
+        ),
+        ("EMBEDDING_PROVIDER", format!("{:?}", c.EMBEDDING_PROVIDER)),
+        ("EMBEDDING_MODEL_NAME", c.EMBEDDING_MODEL_NAME.clone()),
+        ("EMBEDDING_SIZE", c.EMBEDDING_SIZE.to_string()),
+        ("DISTANCE_METRIC", format!("{:?}", c.DISTANCE_METRIC)),
+        ("EMBEDDING_DOC_PREFIX", c.EMBEDDING_DOC_PREFIX.clone()),
+        ("EMBEDDING_QUERY_PREFIX", c.EMBEDDING_QUERY_PREFIX.clone()),
+        ("EMBEDDING_POOLING", format!("{:?}", c.EMBEDDING_POOLING)),
+        ("EMBEDDING_TEMPLATE", format!("{:?}", c.EMBEDDING_TEMPLATE)),
+        (
+            "EMBED_METADATA_FIELDS",
+            format!("{:?}", c.EMBED_METADATA_FIELDS),
+        ),
+        (
+            "EMBEDDING_PART_SEPARATOR",
+            c.EMBEDDING_PART_SEPARATOR.clone(),
+        ),
+        (
+            "EMBEDDING_PII_REDACTION_ENABLED",
+            c.EMBEDDING_PII_REDACTION_ENABLED.to_string(),
+        ),
+        (
+            "EMBEDDING_PII_PATTERNS",
+            format!("{:?}", c.EMBEDDING_PII_PATTERNS),
+        ),
+        (
+            "EMPTY_CONTENT_POLICY",
+            format!(
+                "{:?} {}",
+                c.EMPTY_CONTENT_POLICY, c.EMPTY_CONTENT_FALLBACK_FIELD
+            ),
+        ),
+        (
+            "DENSE_VECTOR_NORMALIZATION",
+            format!("{:?}", c.DENSE_VECTOR_NORMALIZATION),
+        ),
+        (
+            "DENSE_VECTOR_QUANTIZATION",
+            format!("{:?}", c.DENSE_VECTOR_QUANTIZATION),
+        ),
+        (
+            "EMBEDDING_DIMENSION",
+            format!("{:?}", c.EMBEDDING_DIMENSION),
+        ),
+        (
+            "DENSE_VECTOR_DECIMAL_PLACES",
+            format!("{:?}", c.DENSE_VECTOR_DECIMAL_PLACES),
+        ),
+        (
+            "SEPARATE_DISTANCE_PHRASE_VECTORS",
+            c.SEPARATE_DISTANCE_PHRASE_VECTORS.to_string(),
+        ),
+        (
+            "EMBEDDING_VECTOR_FIELDS",
+            format!("{:?}", c.EMBEDDING_VECTOR_FIELDS),
+        ),
+        (
+            "EMBEDDING_VECTOR_SLOT",
+            format!("{:?}", c.EMBEDDING_VECTOR_SLOT),
+        ),
+        ("SEMANTIC_ENABLED", c.SEMANTIC_ENABLED.to_string()),
+        ("FULLTEXT_ENABLED", c.FULLTEXT_ENABLED.to_string()),
+        ("BM25_ENABLED", c.BM25_ENABLED.to_string()),
+        ("BM25_AVG_LEN", c.BM25_AVG_LEN.to_string()),
+        ("BM25_B", c.BM25_B.to_string()),
+        ("BM25_K", c.BM25_K.to_string()),
+        ("BM25_MIN_TOKEN_LENGTH", c.BM25_MIN_TOKEN_LENGTH.to_string()),
+        ("BM25_MAX_TOKEN_LENGTH", c.BM25_MAX_TOKEN_LENGTH.to_string()),
+        ("BM25_LANGUAGE", format!("{:?}", c.BM25_LANGUAGE)),
+        ("BM25_STOPWORDS", c.BM25_STOPWORDS.to_string()),
+        ("BM25_STOPWORD_LIST", format!("{:?}", c.BM25_STOPWORD_LIST)),
+        (
+            "EMBED_METADATA_FIELDS_IN_FULLTEXT",
+            c.EMBED_METADATA_FIELDS_IN_FULLTEXT.to_string(),
+        ),
+    ]
+}
+
+/// Names of the settings which differ between the two datasets in a way that makes the source
+/// dataset's stored vectors wrong for the target. Empty means the vectors can be copied as they are.
+pub fn get_clone_vector_mismatches(
+    source_config: &DatasetConfiguration,
+    target_config: &DatasetConfiguration,
+) -> Vec<&'static str> {
+    get_stored_vector_settings(source_config)
+        .into_iter()
+        .zip(get_stored_vector_settings(target_config))
+        .filter(|((_, source_value), (_, target_value))| source_value != target_value)
+        .map(|((name, _), _)| name)
+        .collect()
+}
+
+/// Copies of the source chunks for the target dataset, paired with points that carry the
+/// source's dense and sparse vectors unchanged. The ids of a copy are derived from the job and
+/// the source chunk, so a page cloned again after an interruption writes the same rows and
+/// points. Group memberships are not carried over since groups belong to the source dataset.
+pub fn get_cloned_chunks(
+    points: Vec<RetrievedPoint>,
+    chunk_metadatas: Vec<ChunkMetadata>,
+    job_id: uuid::Uuid,
+    target_dataset_id: uuid::Uuid,
+) -> Vec<(ChunkData, PointStruct)> {
+    let mut chunk_metadatas: HashMap<uuid::Uuid, ChunkMetadata> = chunk_metadatas
+        .into_iter()
+        .map(|chunk_metadata| (chunk_metadata.qdrant_point_id, chunk_metadata))
+        .collect();
+
+    points
+        .into_iter()
+        .filter_map(|point| {
+            let point_id = match point.id?.point_id_options? {
+                PointIdOptions::Uuid(id) => uuid::Uuid::parse_str(&id).ok()?,
+                PointIdOptions::Num(_) => return None,
+            };
+            let source_chunk = chunk_metadatas.remove(&point_id)?;
+            let vectors = point.vectors?;
+
+            let chunk_metadata = ChunkMetadata {
+                id: uuid::Uuid::new_v5(&job_id, source_chunk.id.as_bytes()),
+                qdrant_point_id: uuid::Uuid::new_v5(&job_id, point_id.as_bytes()),
+                dataset_id: target_dataset_id,
+                ..source_chunk
+            };
+
+            let mut payload = point.payload;
+            payload.insert(
+                "dataset_id".to_string(),
+                target_dataset_id.to_string().into(),
+            );
+            payload.remove("group_ids");
+            payload.remove("group_tag_set");
+
+            let point = PointStruct {
+                id: Some(chunk_metadata.qdrant_point_id.to_string().into()),
+                payload,
+                vectors: Some(vectors),
+            };
+
+            let chunk_data = ChunkData {
+                content: chunk_metadata.chunk_html.clone().unwrap_or_default(),
+                embedding_content: chunk_metadata.chunk_html.clone().unwrap_or_default(),
+                chunk_metadata,
+                group_ids: None,
+                upsert_by_tracking_id: false,
+                fulltext_boost: None,
+                semantic_boost: None,
+            };
+
+            Some((chunk_data, point))
+        })
+        .collect()
+}
+
+pub async fn set_dataset_clone_job_query(
+    job: &DatasetCloneJob,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let serialized_job = serde_json::to_string(job)
+        .map_err(|_| ServiceError::BadRequest("Failed to serialize clone job".to_string()))?;
+
+    redis::cmd("SET")
+        .arg(get_dataset_clone_job_key(job.id))
+        .arg(serialized_job)
+        .query_async::<_, ()>(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(())
+}
+
+pub async fn get_dataset_clone_job_query(
+    job_id: uuid::Uuid,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<DatasetCloneJob, ServiceError> {
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let serialized_job: Option<String> = redis::cmd("GET")
+        .arg(get_dataset_clone_job_key(job_id))
+        .query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let serialized_job =
+        serialized_job.ok_or_else(|| ServiceError::NotFound("Clone job not found".to_string()))?;
+
+    serde_json::from_str(&serialized_job)
+        .map_err(|_| ServiceError::InternalServerError("Failed to parse clone job".to_string()))
+}
+
+/// Queues a copy of every chunk of `source` into `target`. The stored vectors are reused when
+/// both datasets embed chunks the same way, otherwise the job records why and the chunks go
+/// through ingestion for the target dataset.
+pub async fn queue_dataset_clone_query(
+    source: Dataset,
+    target: Dataset,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<DatasetCloneJob, ServiceError> {
+    if source.id == target.id {
+        return Err(ServiceError::BadRequest(
+            "Cannot clone a dataset into itself".to_string(),
+        ));
+    }
+
+    let source_config = DatasetConfiguration::from_json(source.server_configuration.clone());
+    let target_config = DatasetConfiguration::from_json(target.server_configuration.clone());
+    if source_config.QDRANT_ONLY || target_config.QDRANT_ONLY {
+        return Err(ServiceError::BadRequest(
+            "Cannot clone QDRANT_ONLY datasets, their chunks are not stored in postgres"
+                .to_string(),
+        ));
+    }
+
+    let mismatches = get_clone_vector_mismatches(&source_config, &target_config);
+    let reembed_reason = if mismatches.is_empty() {
+        None
+    } else {
+        Some(format!(
+            "The vectors of the source dataset can't be reused because {} differ, the chunks are embedded again with the target dataset's configuration",
+            mismatches.join(", ")
+        ))
+    };
+
+    let total_chunks = get_dataset_usage_query(source.id, pool).await?.chunk_count;
+
+    let now = chrono::Utc::now().naive_local();
+    let job = DatasetCloneJob {
+        id: uuid::Uuid::new_v4(),
+        source_dataset_id: source.id,
+        target_dataset_id: target.id,
+        status: DatasetCloneStatus::Queued,
+        reuse_vectors: reembed_reason.is_none(),
+        reembed_reason,
+        total_chunks,
+        cloned_chunks: 0,
+        next_offset: None,
+        error: None,
+        created_at: now,
+        updated_at: now,
+    };
+
+    set_dataset_clone_job_query(&job, redis_pool.clone()).await?;
+
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    redis::cmd("lpush")
+        .arg(DATASET_CLONE_QUEUE)
+        .arg(job.id.to_string())
+        .query_async::<_, ()>(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(job)
+}
+
+fn get_reembed_chunk_payload(chunk_metadata: ChunkMetadata) -> ChunkReqPayload {
+    ChunkReqPayload {
+        chunk_html: chunk_metadata.chunk_html,
+        link: chunk_metadata.link,
+        tag_set: chunk_metadata
+            .tag_set
+            .map(|tag_set| tag_set.into_iter().flatten().collect()),
+        num_value: chunk_metadata.num_value,
+        metadata: chunk_metadata.metadata,
+        tracking_id: chunk_metadata.tracking_id,
+        time_stamp: chunk_metadata
+            .time_stamp
+            .map(|time_stamp| time_stamp.to_string()),
+        location: chunk_metadata.location,
+        image_urls: chunk_metadata
+            .image_urls
+            .map(|image_urls| image_urls.into_iter().flatten().collect()),
+        weight: Some(chunk_metadata.weight),
+        ..Default::default()
+    }
+}
+
+/// Clone jobs left in `DATASET_CLONE_PROCESSING` without progress for this long, read from
+/// `CLONE_JOB_STALE_SECS` (default 300), belonged to a worker which stopped and are queued again.
+pub fn get_clone_job_stale_after() -> chrono::Duration {
+    chrono::Duration::seconds(
+        std::env::var("CLONE_JOB_STALE_SECS")
+            .ok()
+            .and_then(|secs| secs.parse::<i64>().ok())
+            .filter(|secs| *secs > 0)
+            .unwrap_or(300),
+    )
+}
+
+/// Moves the jobs of `DATASET_CLONE_PROCESSING` which no worker is running anymore back to
+/// `DATASET_CLONE_QUEUE`, where they resume from their `next_offset`. Finished jobs and jobs
+/// which are gone are dropped from the list. Returns the number of jobs queued again.
+pub async fn requeue_stale_dataset_clone_jobs_query(
+    stale_after: chrono::Duration,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<usize, ServiceError> {
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let processing_job_ids: Vec<String> = redis::cmd("LRANGE")
+        .arg(DATASET_CLONE_PROCESSING)
+        .arg(0)
+        .arg(-1)
+        .query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let now = chrono::Utc::now().naive_local();
+    let mut requeued_jobs = 0;
+    for serialized_job_id in processing_job_ids {
+        let job = match uuid::Uuid::parse_str(&serialized_job_id) {
+            Ok(job_id) => get_dataset_clone_job_query(job_id, redis_pool.clone())
+                .await
+                .ok(),
+            Err(_) => None,
+        };
+        let requeue = match &job {
+            Some(job)
+                if matches!(
+                    job.status,
+                    DatasetCloneStatus::Completed | DatasetCloneStatus::Failed
+                ) =>
+            {
+                false
+            }
+            Some(job) if now - job.updated_at < stale_after => continue,
+            Some(_) => true,
+            None => false,
+        };
+
+        redis::cmd("LREM")
+            .arg(DATASET_CLONE_PROCESSING)
+            .arg(1)
+            .arg(&serialized_job_id)
+            .query_async::<_, ()>(&mut *redis_conn)
+            .await
+            .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+        if requeue {
+            redis::cmd("lpush")
+                .arg(DATASET_CLONE_QUEUE)
+                .arg(&serialized_job_id)
+                .query_async::<_, ()>(&mut *redis_conn)
+                .await
+                .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+            requeued_jobs += 1;
+        }
+    }
+
+    Ok(requeued_jobs)
+}
+
+/// Storage a clone job reads the source dataset from and writes the target dataset to.
+pub(crate) trait DatasetCloneStore {
+    async fn scroll_points(
+        &self,
+        dataset_id: uuid::Uuid,
+        dataset_config: &DatasetConfiguration,
+        offset: Option<uuid::Uuid>,
+        with_vectors: bool,
+    ) -> Result<(Vec<RetrievedPoint>, Option<uuid::Uuid>), ServiceError>;
+
+    async fn get_chunk_metadatas(
+        &self,
+        point_ids: Vec<uuid::Uuid>,
+    ) -> Result<Vec<ChunkMetadata>, ServiceError>;
+
+    /// Inserts the chunks which conflict with no existing chunk and returns them
+    async fn insert_chunks(
+        &self,
+        chunks: Vec<ChunkData>,
+        dataset_id: uuid::Uuid,
+    ) -> Result<Vec<ChunkData>, ServiceError>;
+
+    async fn upsert_points(
+        &self,
+        points: Vec<PointStruct>,
+        dataset_config: &DatasetConfiguration,
+    ) -> Result<(), ServiceError>;
+
+    async fn add_chunk_count(
+        &self,
+        dataset_id: uuid::Uuid,
+        amount: i32,
+    ) -> Result<(), ServiceError>;
+
+    async fn queue_ingestion(
+        &self,
+        message: BulkUploadIngestionMessage,
+    ) -> Result<(), ServiceError>;
+
+    async fn save_job(&self, job: &DatasetCloneJob) -> Result<(), ServiceError>;
+}
+
+/// Postgres, qdrant and the ingestion queue in redis.
+struct ServerDatasetCloneStore {
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+}
+
+impl DatasetCloneStore for ServerDatasetCloneStore {
+    async fn scroll_points(
+        &self,
+        dataset_id: uuid::Uuid,
+        dataset_config: &DatasetConfiguration,
+        offset: Option<uuid::Uuid>,
+        with_vectors: bool,
+    ) -> Result<(Vec<RetrievedPoint>, Option<uuid::Uuid>), ServiceError> {
+        scroll_dataset_points_query(
+            dataset_id,
+            dataset_config,
+            offset,
+            DATASET_CLONE_BATCH_SIZE,
+            with_vectors,
+        )
+        .await
+    }
+
+    async fn get_chunk_metadatas(
+        &self,
+        point_ids: Vec<uuid::Uuid>,
+    ) -> Result<Vec<ChunkMetadata>, ServiceError> {
+        Ok(
+            get_chunk_metadatas_from_point_ids(point_ids, self.pool.clone())
+                .await?
+                .into_iter()
+                .filter_map(|chunk_metadata| match chunk_metadata {
+                    ChunkMetadataTypes::Metadata(chunk_metadata) => Some(chunk_metadata.into()),
+                    _ => None,
+                })
+                .collect(),
+        )
+    }
+
+    async fn insert_chunks(
+        &self,
+        chunks: Vec<ChunkData>,
+        dataset_id: uuid::Uuid,
+    ) -> Result<Vec<ChunkData>, ServiceError> {
+        bulk_insert_chunk_metadata_query(chunks, dataset_id, false, self.pool.clone()).await
+    }
+
+    async fn upsert_points(
+        &self,
+        points: Vec<PointStruct>,
+        dataset_config: &DatasetConfiguration,
+    ) -> Result<(), ServiceError> {
+        bulk_upsert_qdrant_points_query(points, dataset_config.clone()).await
+    }
+
+    async fn add_chunk_count(
+        &self,
+        dataset_id: uuid::Uuid,
+        amount: i32,
+    ) -> Result<(), ServiceError> {
+        update_dataset_chunk_count(dataset_id, amount, self.pool.clone()).await
+    }
+
+    async fn queue_ingestion(
+        &self,
+        message: BulkUploadIngestionMessage,
+    ) -> Result<(), ServiceError> {
+        let serialized_message = serde_json::to_string(&message).map_err(|_| {
+            ServiceError::BadRequest("Failed to Serialize BulkUploadMessage".to_string())
+        })?;
+
+        let mut redis_conn = self
+            .redis_pool
+            .get()
+            .await
+            .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+        redis::cmd("lpush")
+            .arg("ingestion")
+            .arg(&serialized_message)
+            .query_async::<_, ()>(&mut *redis_conn)
+            .await
+            .map_err(|err| ServiceError::BadRequest(err.to_string()))
+    }
+
+    async fn save_job(&self, job: &DatasetCloneJob) -> Result<(), ServiceError> {
+        set_dataset_clone_job_query(job, self.redis_pool.clone()).await
+    }
+}
+
+/// Runs a queued clone job page by page from its `next_offset`, saving its progress after every
+/// page.
+pub async fn clone_dataset_chunks_query(
+    job: &mut DatasetCloneJob,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    let source = get_dataset_by_id_query(job.source_dataset_id, pool.clone()).await?;
+    let target = get_dataset_by_id_query(job.target_dataset_id, pool.clone()).await?;
+    let source_config = DatasetConfiguration::from_json(source.server_configuration);
+    let target_config = DatasetConfiguration::from_json(target.server_configuration);
+
+    clone_dataset_pages(
+        job,
+        &source_config,
+        &target_config,
+        &ServerDatasetCloneStore { pool, redis_pool },
+    )
+    .await
+}
+
+/// Clones the pages of the source dataset left to clone into `store`. The job is marked
+/// completed with its last page, so a job interrupted after that is not started over. A page
+/// cloned again after an interruption only writes the chunks which are missing, except when the
+/// chunks are embedded again, where the interrupted page may be queued for ingestion twice.
+pub(crate) async fn clone_dataset_pages<S: DatasetCloneStore>(
+    job: &mut DatasetCloneJob,
+    source_config: &DatasetConfiguration,
+    target_config: &DatasetConfiguration,
+    store: &S,
+) -> Result<(), ServiceError> {
+    // The configurations may have changed while the job was queued
+    if job.reuse_vectors && !get_clone_vector_mismatches(source_config, target_config).is_empty() {
+        return Err(ServiceError::BadRequest(
+            "The embedding configuration of the datasets changed since the clone was queued, queue it again".to_string(),
+        ));
+    }
+
+    loop {
+        let (points, next_offset) = store
+            .scroll_points(
+                job.source_dataset_id,
+                source_config,
+                job.next_offset,
+                job.reuse_vectors,
+            )
+            .await?;
+
+        let point_ids = points
+            .iter()
+            .filter_map(|point| match point.id.clone()?.point_id_options? {
+                PointIdOptions::Uuid(id) => uuid::Uuid::parse_str(&id).ok(),
+                PointIdOptions::Num(_) => None,
+            })
+            .collect_vec();
+        let chunk_metadatas = store.get_chunk_metadatas(point_ids).await?;
+
+        let cloned_chunks = if job.reuse_vectors {
+            let (chunk_datas, points): (Vec<ChunkData>, Vec<PointStruct>) =
+                get_cloned_chunks(points, chunk_metadatas, job.id, job.target_dataset_id)
+                    .into_iter()
+                    .unzip();
+            let cloned_point_ids = chunk_datas
+                .iter()
+                .map(|chunk_data| chunk_data.chunk_metadata.qdrant_point_id)
+                .collect_vec();
+
+            let inserted_chunks = store
+                .insert_chunks(chunk_datas, job.target_dataset_id)
+                .await?;
+            // Copies inserted by an interrupted run of this page get their points written again,
+            // chunks skipped on a tracking_id conflict in the target keep its existing points
+            let stored_point_ids = store
+                .get_chunk_metadatas(cloned_point_ids)
+                .await?
+                .into_iter()
+                .map(|chunk_metadata| PointId::from(chunk_metadata.qdrant_point_id.to_string()))
+                .collect_vec();
+            let points = points
+                .into_iter()
+                .filter(|point| {
+                    point
+                        .id
+                        .as_ref()
+                        .is_some_and(|id| stored_point_ids.contains(id))
+                })
+                .collect_vec();
+
+            if !points.is_empty() {
+                store.upsert_points(points, target_config).await?;
+            }
+            if !inserted_chunks.is_empty() {
+                store
+                    .add_chunk_count(job.target_dataset_id, inserted_chunks.len() as i32)
+                    .await?;
+            }
+
+            inserted_chunks.len()
+        } else if !chunk_metadatas.is_empty() {
+            let chunks = chunk_metadatas
+                .into_iter()
+                .map(get_reembed_chunk_payload)
+                .collect_vec();
+            let chunk_count = chunks.len();
+            let (ingestion_message, _) =
+                create_chunk_metadata(chunks, job.target_dataset_id).await?;
+            store.queue_ingestion(ingestion_message).await?;
+
+            chunk_count
+        } else {
+            0
+        };
+
+        job.cloned_chunks += cloned_chunks as i32;
+        job.next_offset = next_offset;
+        if next_offset.is_none() {
+            job.status = DatasetCloneStatus::Completed;
+        }
+        job.updated_at = chrono::Utc::now().naive_local();
+        store.save_job(job).await?;
+
+        if job.next_offset.is_none() {
+            break;
+        }
+    }
+
+    Ok(())
+}
 
 pub async fn create_dataset_query(
     new_dataset: Dataset,
@@ -1000,3 +1880,477 @@ pub async fn update_dataset_last_processed_query(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::models::DistanceMetric;
+    use crate::data::models::{DenseVectorSlot, EmbeddingDualWriteTarget};
+    use crate::operators::mock_upstream::MockUpstream;
+    use crate::operators::model_operator::{get_dense_vectors, EmbedContext};
+    use qdrant_client::{qdrant::Vector, Payload};
+    use std::sync::Mutex;
+
+    #[test]
+    pub fn test_embedding_cutover_configuration() {
+        let dataset_config = DatasetConfiguration {
+            EMBEDDING_BASE_URL: "http://old-model".to_string(),
+            EMBEDDING_MODEL_NAME: "old-model".to_string(),
+            EMBEDDING_DUAL_WRITE_TARGET: Some(EmbeddingDualWriteTarget {
+                base_url: "http://new-model".to_string(),
+                model_name: "new-model".to_string(),
+            }),
+            ..Default::default()
+        };
+        assert!(validate_dataset_configuration(&dataset_config).is_ok());
+
+        // The flip waits for the backfill
+        assert!(matches!(
+            get_embedding_cutover_configuration(&dataset_config, 3),
+            Err(ServiceError::BadRequest(_))
+        ));
+
+        let cutover_config =
+            get_embedding_cutover_configuration(&dataset_config, 0).expect("Backfill is done");
+        assert_eq!(cutover_config.EMBEDDING_BASE_URL, "http://new-model");
+        assert_eq!(cutover_config.EMBEDDING_MODEL_NAME, "new-model");
+        assert_eq!(
+            cutover_config.EMBEDDING_VECTOR_SLOT,
+            DenseVectorSlot::Secondary
+        );
+        assert!(cutover_config.EMBEDDING_DUAL_WRITE_TARGET.is_none());
+        // The flip survives being stored
+        let stored_config = DatasetConfiguration::from_json(cutover_config.to_json());
+        assert_eq!(
+            stored_config.EMBEDDING_VECTOR_SLOT,
+            DenseVectorSlot::Secondary
+        );
+        assert!(stored_config.EMBEDDING_DUAL_WRITE_TARGET.is_none());
+
+        // A later migration writes back into the primary slot
+        let next_config = DatasetConfiguration {
+            EMBEDDING_DUAL_WRITE_TARGET: Some(EmbeddingDualWriteTarget {
+                base_url: "http://old-model".to_string(),
+                model_name: "old-model".to_string(),
+            }),
+            ..cutover_config
+        };
+        assert_eq!(
+            get_embedding_cutover_configuration(&next_config, 0)
+                .expect("Backfill is done")
+                .EMBEDDING_VECTOR_SLOT,
+            DenseVectorSlot::Primary
+        );
+
+        assert!(matches!(
+            get_embedding_cutover_configuration(&DatasetConfiguration::default(), 0),
+            Err(ServiceError::BadRequest(_))
+        ));
+        assert!(validate_dataset_configuration(&DatasetConfiguration {
+            SEPARATE_DISTANCE_PHRASE_VECTORS: true,
+            ..dataset_config
+        })
+        .is_err());
+    }
+
+    /// Qdrant and postgres of a clone job in memory, scrolled `page_size` points at a time.
+    /// Chunks queued for ingestion are embedded right away against the target's embedding
+    /// server, as the ingestion worker would.
+    struct MockCloneStore {
+        page_size: usize,
+        target_config: DatasetConfiguration,
+        points: Mutex<Vec<PointStruct>>,
+        chunks: Mutex<Vec<ChunkMetadata>>,
+        chunk_counts: Mutex<HashMap<uuid::Uuid, i32>>,
+        saved_jobs: Mutex<Vec<DatasetCloneJob>>,
+        /// The scroll with this index fails, as if the worker stopped there
+        failing_scroll: Mutex<Option<usize>>,
+        scrolls: Mutex<usize>,
+    }
+
+    impl DatasetCloneStore for MockCloneStore {
+        async fn scroll_points(
+            &self,
+            dataset_id: uuid::Uuid,
+            _dataset_config: &DatasetConfiguration,
+            offset: Option<uuid::Uuid>,
+            with_vectors: bool,
+        ) -> Result<(Vec<RetrievedPoint>, Option<uuid::Uuid>), ServiceError> {
+            let scroll = {
+                let mut scrolls = self.scrolls.lock().unwrap();
+                *scrolls += 1;
+                *scrolls - 1
+            };
+            if *self.failing_scroll.lock().unwrap() == Some(scroll) {
+                return Err(ServiceError::BadRequest("Worker stopped".to_string()));
+            }
+
+            let point_id = |point: &PointStruct| match point.id.clone()?.point_id_options? {
+                PointIdOptions::Uuid(id) => uuid::Uuid::parse_str(&id).ok(),
+                PointIdOptions::Num(_) => None,
+            };
+            let mut points = self
+                .points
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|point| {
+                    point.payload.get("dataset_id") == Some(&dataset_id.to_string().into())
+                })
+                // Every id is at least None, the offset of the first page
+                .filter(|point| point_id(point) >= offset)
+                .cloned()
+                .collect_vec();
+            points.sort_by_key(point_id);
+
+            let next_offset = points.get(self.page_size).and_then(point_id);
+            let page = points
+                .into_iter()
+                .take(self.page_size)
+                .map(|point| RetrievedPoint {
+                    id: point.id,
+                    payload: point.payload,
+                    vectors: point.vectors.filter(|_| with_vectors),
+                    ..Default::default()
+                })
+                .collect_vec();
+            Ok((page, next_offset))
+        }
+
+        async fn get_chunk_metadatas(
+            &self,
+            point_ids: Vec<uuid::Uuid>,
+        ) -> Result<Vec<ChunkMetadata>, ServiceError> {
+            Ok(self
+                .chunks
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|chunk| point_ids.contains(&chunk.qdrant_point_id))
+                .cloned()
+                .collect())
+        }
+
+        async fn insert_chunks(
+            &self,
+            chunks: Vec<ChunkData>,
+            _dataset_id: uuid::Uuid,
+        ) -> Result<Vec<ChunkData>, ServiceError> {
+            let mut stored_chunks = self.chunks.lock().unwrap();
+            let mut inserted_chunks = vec![];
+            for chunk_data in chunks {
+                let new_chunk = &chunk_data.chunk_metadata;
+                let conflicts = stored_chunks.iter().any(|chunk| {
+                    chunk.id == new_chunk.id
+                        || (chunk.tracking_id.is_some()
+                            && chunk.tracking_id == new_chunk.tracking_id
+                            && chunk.dataset_id == new_chunk.dataset_id)
+                });
+                if !conflicts {
+                    stored_chunks.push(new_chunk.clone());
+                    inserted_chunks.push(chunk_data);
+                }
+            }
+            Ok(inserted_chunks)
+        }
+
+        async fn upsert_points(
+            &self,
+            points: Vec<PointStruct>,
+            _dataset_config: &DatasetConfiguration,
+        ) -> Result<(), ServiceError> {
+            let mut stored_points = self.points.lock().unwrap();
+            for point in points {
+                stored_points.retain(|stored_point| stored_point.id != point.id);
+                stored_points.push(point);
+            }
+            Ok(())
+        }
+
+        async fn add_chunk_count(
+            &self,
+            dataset_id: uuid::Uuid,
+            amount: i32,
+        ) -> Result<(), ServiceError> {
+            *self
+                .chunk_counts
+                .lock()
+                .unwrap()
+                .entry(dataset_id)
+                .or_default() += amount;
+            Ok(())
+        }
+
+        async fn queue_ingestion(
+            &self,
+            message: BulkUploadIngestionMessage,
+        ) -> Result<(), ServiceError> {
+            let vectors = get_dense_vectors(
+                message
+                    .ingestion_messages
+                    .iter()
+                    .map(|message| (message.chunk.chunk_html.clone().unwrap_or_default(), None))
+                    .collect(),
+                "doc",
+                &EmbedContext::from_dataset_config(&self.target_config),
+                reqwest::Client::new(),
+            )
+            .await?;
+
+            let points = message
+                .ingestion_messages
+                .iter()
+                .zip(vectors)
+                .map(|(message, vector)| {
+                    let payload = Payload::try_from(serde_json::json!({
+                        "dataset_id": message.dataset_id.to_string(),
+                    }))
+                    .expect("Payload is an object");
+                    PointStruct::new(
+                        message
+                            .ingest_specific_chunk_metadata
+                            .qdrant_point_id
+                            .to_string(),
+                        HashMap::from([("3_vectors".to_string(), Vector::from(vector))]),
+                        payload,
+                    )
+                })
+                .collect_vec();
+            self.upsert_points(points, &self.target_config).await
+        }
+
+        async fn save_job(&self, job: &DatasetCloneJob) -> Result<(), ServiceError> {
+            self.saved_jobs.lock().unwrap().push(job.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    pub fn test_clone_dataset_reuses_vectors() {
+        let upstream = MockUpstream::start();
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .expect("Runtime builds");
+        let source_dataset_id = uuid::Uuid::new_v4();
+        let target_dataset_id = uuid::Uuid::new_v4();
+        let source_config = DatasetConfiguration {
+            EMBEDDING_BASE_URL: upstream.origin.clone(),
+            EMBEDDING_API_KEY: "mock-key".to_string(),
+            EMBEDDING_SIZE: 3,
+            DISTANCE_METRIC: DistanceMetric::Dot,
+            ..Default::default()
+        };
+        assert!(get_clone_vector_mismatches(&source_config, &source_config.clone()).is_empty());
+
+        let source_chunks = (0..5)
+            .map(|i| ChunkMetadata {
+                chunk_html: Some(format!("chunk {}", i)),
+                tracking_id: Some(format!("tracking-{}", i)),
+                tag_set: Some(vec![Some("tag".to_string())]),
+                dataset_id: source_dataset_id,
+                ..Default::default()
+            })
+            .collect_vec();
+        let source_points = source_chunks
+            .iter()
+            .enumerate()
+            .map(|(i, chunk)| {
+                let vectors = HashMap::from([
+                    (
+                        "3_vectors".to_string(),
+                        Vector::from(vec![i as f32, 0.5, -1.0]),
+                    ),
+                    (
+                        "sparse_vectors".to_string(),
+                        Vector::from(vec![(i as u32, 0.5), (7, 1.5)]),
+                    ),
+                ]);
+                let payload = Payload::try_from(serde_json::json!({
+                    "dataset_id": source_dataset_id.to_string(),
+                    "content": chunk.chunk_html,
+                    "group_ids": [uuid::Uuid::new_v4().to_string()],
+                    "group_tag_set": ["group"],
+                }))
+                .expect("Payload is an object");
+                PointStruct::new(chunk.qdrant_point_id.to_string(), vectors, payload)
+            })
+            .collect_vec();
+
+        let store = |target_config: &DatasetConfiguration| MockCloneStore {
+            page_size: 2,
+            target_config: target_config.clone(),
+            points: Mutex::new(source_points.clone()),
+            chunks: Mutex::new(source_chunks.clone()),
+            chunk_counts: Mutex::new(HashMap::new()),
+            saved_jobs: Mutex::new(vec![]),
+            failing_scroll: Mutex::new(None),
+            scrolls: Mutex::new(0),
+        };
+        let new_job = |reuse_vectors: bool| DatasetCloneJob {
+            id: uuid::Uuid::new_v4(),
+            source_dataset_id,
+            target_dataset_id,
+            status: DatasetCloneStatus::Running,
+            reuse_vectors,
+            reembed_reason: None,
+            total_chunks: 5,
+            cloned_chunks: 0,
+            next_offset: None,
+            error: None,
+            created_at: chrono::Utc::now().naive_local(),
+            updated_at: chrono::Utc::now().naive_local(),
+        };
+        let target_points = |store: &MockCloneStore| {
+            store
+                .points
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|point| {
+                    point.payload.get("dataset_id") == Some(&target_dataset_id.to_string().into())
+                })
+                .cloned()
+                .collect_vec()
+        };
+
+        // The worker stops after the first of three pages and the job resumes from its offset
+        let target_config = source_config.clone();
+        let reuse_store = store(&target_config);
+        *reuse_store.failing_scroll.lock().unwrap() = Some(1);
+        let mut job = new_job(true);
+        assert!(runtime
+            .block_on(clone_dataset_pages(
+                &mut job,
+                &source_config,
+                &target_config,
+                &reuse_store
+            ))
+            .is_err());
+        let mut saved_job = reuse_store
+            .saved_jobs
+            .lock()
+            .unwrap()
+            .last()
+            .cloned()
+            .expect("First page is saved");
+        assert_eq!(saved_job.cloned_chunks, 2);
+        assert!(saved_job.next_offset.is_some());
+        assert_eq!(saved_job.status, DatasetCloneStatus::Running);
+
+        runtime
+            .block_on(clone_dataset_pages(
+                &mut saved_job,
+                &source_config,
+                &target_config,
+                &reuse_store,
+            ))
+            .expect("Clone resumes");
+        assert_eq!(saved_job.status, DatasetCloneStatus::Completed);
+        assert_eq!(saved_job.cloned_chunks, 5);
+        assert_eq!(saved_job.next_offset, None);
+        assert_eq!(
+            reuse_store.chunk_counts.lock().unwrap()[&target_dataset_id],
+            5
+        );
+
+        // The target holds the source's vectors as they were, nothing was embedded
+        let cloned_points = target_points(&reuse_store);
+        assert_eq!(cloned_points.len(), 5);
+        let cloned_chunks = reuse_store.chunks.lock().unwrap().clone();
+        for source_point in source_points.iter() {
+            let cloned_point = cloned_points
+                .iter()
+                .find(|point| point.payload.get("content") == source_point.payload.get("content"))
+                .expect("Every chunk is cloned");
+            assert_ne!(cloned_point.id, source_point.id);
+            assert_eq!(cloned_point.vectors, source_point.vectors);
+            assert!(cloned_point.payload.get("group_ids").is_none());
+            assert!(cloned_chunks.iter().any(|chunk| {
+                chunk.dataset_id == target_dataset_id
+                    && Some(PointId::from(chunk.qdrant_point_id.to_string())) == cloned_point.id
+            }));
+        }
+        assert!(upstream.take_requests().is_empty());
+
+        // Cloning a page again writes no duplicates
+        saved_job.next_offset = None;
+        runtime
+            .block_on(clone_dataset_pages(
+                &mut saved_job,
+                &source_config,
+                &target_config,
+                &reuse_store,
+            ))
+            .expect("Clone runs again");
+        assert_eq!(target_points(&reuse_store).len(), 5);
+        assert_eq!(reuse_store.chunks.lock().unwrap().len(), 10);
+        assert_eq!(
+            reuse_store.chunk_counts.lock().unwrap()[&target_dataset_id],
+            5
+        );
+
+        // Another model embeds the chunks again with the target's configuration
+        let reembed_config = DatasetConfiguration {
+            EMBEDDING_MODEL_NAME: "other-model".to_string(),
+            ..source_config.clone()
+        };
+        let reembed_store = store(&reembed_config);
+        let mut job = new_job(false);
+        runtime
+            .block_on(clone_dataset_pages(
+                &mut job,
+                &source_config,
+                &reembed_config,
+                &reembed_store,
+            ))
+            .expect("Clone queues the chunks");
+        assert_eq!(job.cloned_chunks, 5);
+        let reembedded_points = target_points(&reembed_store);
+        assert_eq!(reembedded_points.len(), 5);
+        assert!(reembedded_points.iter().all(|point| source_points
+            .iter()
+            .all(|source_point| point.vectors != source_point.vectors)));
+        assert!(!upstream.take_requests().is_empty());
+        assert!(matches!(
+            runtime.block_on(clone_dataset_pages(
+                &mut new_job(true),
+                &source_config,
+                &reembed_config,
+                &reembed_store,
+            )),
+            Err(ServiceError::BadRequest(_))
+        ));
+
+        assert_eq!(
+            get_clone_vector_mismatches(
+                &source_config,
+                &DatasetConfiguration {
+                    EMBEDDING_MODEL_NAME: "other-model".to_string(),
+                    EMBEDDING_DOC_PREFIX: "passage: ".to_string(),
+                    ..source_config.clone()
+                }
+            ),
+            vec!["EMBEDDING_MODEL_NAME", "EMBEDDING_DOC_PREFIX"]
+        );
+        assert_eq!(
+            get_clone_vector_mismatches(
+                &source_config,
+                &DatasetConfiguration {
+                    BM25_K: 1.5,
+                    ..source_config.clone()
+                }
+            ),
+            vec!["BM25_K"]
+        );
+        // Settings which only apply at search time don't affect the stored vectors
+        assert!(get_clone_vector_mismatches(
+            &source_config,
+            &DatasetConfiguration {
+                RERANKER_MODEL_NAME: "other-reranker".to_string(),
+                ..source_config.clone()
+            }
+        )
+        .is_empty());
+    }
+}
@@ -0,0 +1,670 @@
+use crate::data::models::{EmbeddingDeadLetter, Pool, RedisPool};
+use crate::errors::ServiceError;
+use crate::handlers::chunk_handler::{BulkUploadIngestionMessage, UploadIngestionMessage};
+use crate::operators::model_operator::RetryJitter;
+use actix_web::web;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+/// Attempts after which a failing bulk upload is given up on and pushed to the `dead_letters`
+/// redis list, whatever the error.
+pub const MAX_INGESTION_ATTEMPTS: usize = 10;
+
+/// Number of times the model server may reject a chunk before it is dead-lettered.
+pub fn get_embedding_dead_letter_attempts() -> usize {
+    std::env::var("EMBEDDING_DEAD_LETTER_ATTEMPTS")
+        .ok()
+        .and_then(|attempts| attempts.parse::<usize>().ok())
+        .filter(|attempts| *attempts > 0)
+        .unwrap_or(3)
+}
+
+#[derive(Debug, Clone)]
+pub enum IngestionRetry {
+    /// Requeue the message after the retry delay
+    Retry(BulkUploadIngestionMessage),
+    /// The model server rejected a batch, requeue each chunk on its own so only the chunks it
+    /// rejects end up dead-lettered
+    Split(Vec<BulkUploadIngestionMessage>),
+    /// Stop retrying the chunk and move it to the dead-letter table
+    DeadLetter(BulkUploadIngestionMessage),
+    /// Out of attempts for an error which may not be the chunk's fault
+    GiveUp(BulkUploadIngestionMessage),
+}
+
+/// Decides what to do with a bulk upload which failed with `error`. Only `ModelRejected` errors
+/// count towards dead-lettering, every other error is retried until `MAX_INGESTION_ATTEMPTS`.
+pub fn plan_ingestion_retry(
+    mut payload: BulkUploadIngestionMessage,
+    error: &ServiceError,
+    max_rejected_attempts: usize,
+) -> IngestionRetry {
+    payload.attempt_number += 1;
+
+    if let ServiceError::ModelRejected(_) = error {
+        if payload.ingestion_messages.len() > 1 {
+            // There is no telling which chunk was rejected, so splitting does not count as one
+            return IngestionRetry::Split(
+                payload
+                    .ingestion_messages
+                    .into_iter()
+                    .map(|ingestion_message| BulkUploadIngestionMessage {
+                        attempt_number: payload.attempt_number,
+                        rejected_attempts: 0,
+                        dataset_id: payload.dataset_id,
+                        ingestion_messages: vec![ingestion_message],
+                    })
+                    .collect(),
+            );
+        }
+
+        payload.rejected_attempts += 1;
+        if payload.rejected_attempts >= max_rejected_attempts {
+            return IngestionRetry::DeadLetter(payload);
+        }
+    }
+
+    if payload.attempt_number >= MAX_INGESTION_ATTEMPTS {
+        IngestionRetry::GiveUp(payload)
+    } else {
+        IngestionRetry::Retry(payload)
+    }
+}
+
+/// How bulk uploads which failed are spaced out when they go back on the queue. Retries back off
+/// exponentially from a base which depends on the error, and at most `redeliveries_per_window`
+/// retries of one dataset go back on the queue per `redelivery_window`. Without that cap every
+/// message which failed during a provider outage would hit the provider the moment it recovers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RequeuePolicy {
+    pub base_delay_ms: u64,
+    /// Base delay after `UpstreamUnavailable` and timeouts, which mean the provider is down or
+    /// overloaded rather than that the message is at fault
+    pub unavailable_base_delay_ms: u64,
+    pub max_delay_ms: u64,
+    pub jitter: RetryJitter,
+    pub redeliveries_per_window: usize,
+    pub redelivery_window_ms: u64,
+}
+
+impl Default for RequeuePolicy {
+    fn default() -> Self {
+        RequeuePolicy {
+            base_delay_ms: 500,
+            unavailable_base_delay_ms: 5000,
+            max_delay_ms: 30000,
+            jitter: RetryJitter::Full,
+            redeliveries_per_window: 50,
+            redelivery_window_ms: 1000,
+        }
+    }
+}
+
+impl RequeuePolicy {
+    /// Read from `RETRY_BASE_DELAY_MS`, `RETRY_UNAVAILABLE_BASE_DELAY_MS`, `RETRY_MAX_DELAY_MS`,
+    /// `RETRY_JITTER_STRATEGY`, `REQUEUE_REDELIVERIES_PER_WINDOW` and
+    /// `REQUEUE_REDELIVERY_WINDOW_MS`.
+    pub fn from_env() -> Self {
+        fn parse_env(name: &str) -> Option<u64> {
+            std::env::var(name)
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+        }
+
+        let default = RequeuePolicy::default();
+        RequeuePolicy {
+            base_delay_ms: parse_env("RETRY_BASE_DELAY_MS").unwrap_or(default.base_delay_ms),
+            unavailable_base_delay_ms: parse_env("RETRY_UNAVAILABLE_BASE_DELAY_MS")
+                .unwrap_or(default.unavailable_base_delay_ms),
+            max_delay_ms: parse_env("RETRY_MAX_DELAY_MS").unwrap_or(default.max_delay_ms),
+            jitter: RetryJitter::from_env(),
+            redeliveries_per_window: parse_env("REQUEUE_REDELIVERIES_PER_WINDOW")
+                .filter(|redeliveries| *redeliveries > 0)
+                .map(|redeliveries| redeliveries as usize)
+                .unwrap_or(default.redeliveries_per_window),
+            redelivery_window_ms: parse_env("REQUEUE_REDELIVERY_WINDOW_MS")
+                .filter(|window_ms| *window_ms > 0)
+                .unwrap_or(default.redelivery_window_ms),
+        }
+    }
+
+    fn get_base_delay_ms(&self, error: &ServiceError) -> u64 {
+        match error {
+            ServiceError::UpstreamUnavailable(_) | ServiceError::RequestTimeout(_) => {
+                self.unavailable_base_delay_ms
+            }
+            _ => self.base_delay_ms,
+        }
+    }
+}
+
+/// Delay before a bulk upload which failed with `error` goes back on the queue for its 1-indexed
+/// `attempt`. `sample` is a random number in [0, 1] which places the delay within the policy's
+/// jitter range, so the schedule itself is deterministic.
+pub fn get_requeue_delay(
+    error: &ServiceError,
+    attempt: usize,
+    policy: &RequeuePolicy,
+    sample: f64,
+) -> std::time::Duration {
+    let base_ms = policy.get_base_delay_ms(error);
+    let max_ms = policy.max_delay_ms.max(base_ms);
+    let exponential_ms = |attempt: usize| {
+        base_ms
+            .saturating_mul(1u64 << attempt.saturating_sub(1).min(20))
+            .min(max_ms)
+    };
+    let sample = if sample.is_finite() {
+        sample.clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    let delay_ms = match policy.jitter {
+        RetryJitter::None => exponential_ms(attempt),
+        RetryJitter::Full => (exponential_ms(attempt) as f64 * sample) as u64,
+        RetryJitter::Decorrelated => {
+            let upper_ms = exponential_ms(attempt.saturating_sub(1).max(1))
+                .saturating_mul(3)
+                .max(base_ms);
+            (base_ms + ((upper_ms - base_ms) as f64 * sample) as u64).min(max_ms)
+        }
+    };
+
+    std::time::Duration::from_millis(delay_ms)
+}
+
+/// Whether a retry may go back on the queue now. `position` is its 1-indexed position among the
+/// retries of its dataset in the current redelivery window and `since_window_start` how far into
+/// the window it is. Returns `None` within the cap, otherwise how long to wait before asking again:
+/// until the window its overflow position falls into, placed within it by `sample` in [0, 1].
+pub fn get_redelivery_wait(
+    position: usize,
+    since_window_start: std::time::Duration,
+    policy: &RequeuePolicy,
+    sample: f64,
+) -> Option<std::time::Duration> {
+    let per_window = policy.redeliveries_per_window.max(1);
+    if position <= per_window {
+        return None;
+    }
+
+    let window = std::time::Duration::from_millis(policy.redelivery_window_ms.max(1));
+    let windows_ahead = ((position - per_window - 1) / per_window) as u32;
+    let sample = if sample.is_finite() {
+        sample.clamp(0.0, 0.99)
+    } else {
+        0.0
+    };
+
+    Some(
+        window.saturating_sub(since_window_start) + window * windows_ahead + window.mul_f64(sample),
+    )
+}
+
+/// Waits until `count` retries of `dataset_id` may go back on the queue under the policy's
+/// redelivery cap. Retries are counted per window in redis so the cap holds across workers. When
+/// redis can't be reached the retries are let through rather than held back.
+pub async fn wait_for_redelivery_slot(
+    dataset_id: uuid::Uuid,
+    count: usize,
+    policy: &RequeuePolicy,
+    redis_pool: web::Data<RedisPool>,
+) {
+    let window_ms = policy.redelivery_window_ms.max(1);
+    loop {
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let key = format!("requeue_redeliveries:{}:{}", dataset_id, now_ms / window_ms);
+
+        let mut redis_conn = match redis_pool.get().await {
+            Ok(redis_conn) => redis_conn,
+            Err(err) => {
+                log::warn!("Requeueing without a redelivery slot {:?}", err);
+                return;
+            }
+        };
+        let position = match redis::pipe()
+            .cmd("INCRBY")
+            .arg(&key)
+            .arg(count)
+            .cmd("PEXPIRE")
+            .arg(&key)
+            .arg(window_ms * 2)
+            .ignore()
+            .query_async::<redis::aio::MultiplexedConnection, (usize,)>(&mut *redis_conn)
+            .await
+        {
+            Ok((position,)) => position,
+            Err(err) => {
+                log::warn!("Requeueing without a redelivery slot {:?}", err);
+                return;
+            }
+        };
+        // Don't hold onto the connection while waiting for a slot
+        drop(redis_conn);
+
+        let since_window_start = std::time::Duration::from_millis(now_ms % window_ms);
+        match get_redelivery_wait(position, since_window_start, policy, rand::random::<f64>()) {
+            Some(wait) => tokio::time::sleep(wait).await,
+            None => return,
+        }
+    }
+}
+
+pub fn get_dead_letters_from_payload(
+    payload: &BulkUploadIngestionMessage,
+    error: &ServiceError,
+) -> Result<Vec<EmbeddingDeadLetter>, ServiceError> {
+    let error_message = match error {
+        ServiceError::ModelRejected(message) => message.clone(),
+        error => error.to_string(),
+    };
+
+    payload
+        .ingestion_messages
+        .iter()
+        .map(|ingestion_message| {
+            let serialized_message = serde_json::to_value(ingestion_message).map_err(|_| {
+                ServiceError::InternalServerError(
+                    "Failed to serialize chunk for the dead-letter table".to_string(),
+                )
+            })?;
+
+            Ok(EmbeddingDeadLetter::from_details(
+                payload.dataset_id,
+                ingestion_message.ingest_specific_chunk_metadata.id,
+                ingestion_message.chunk.tracking_id.clone(),
+                payload.rejected_attempts as i32,
+                error_message.clone(),
+                serialized_message,
+            ))
+        })
+        .collect()
+}
+
+/// Returns the message which queues a dead-lettered chunk again with fresh attempt counts.
+pub fn get_redrive_message(
+    dead_letter: &EmbeddingDeadLetter,
+) -> Result<BulkUploadIngestionMessage, ServiceError> {
+    let ingestion_message: UploadIngestionMessage =
+        serde_json::from_value(dead_letter.ingestion_message.clone()).map_err(|_| {
+            ServiceError::InternalServerError(format!(
+                "Dead-lettered chunk {} could not be deserialized",
+                dead_letter.chunk_id
+            ))
+        })?;
+
+    Ok(BulkUploadIngestionMessage {
+        attempt_number: 0,
+        rejected_attempts: 0,
+        dataset_id: dead_letter.dataset_id,
+        ingestion_messages: vec![ingestion_message],
+    })
+}
+
+pub async fn insert_embedding_dead_letters_query(
+    dead_letters: Vec<EmbeddingDeadLetter>,
+    pool: web::Data<Pool>,
+) -> Result<(), ServiceError> {
+    use crate::data::schema::embedding_dead_letters::dsl as embedding_dead_letters_table;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|e| ServiceError::InternalServerError(e.to_string()))?;
+
+    diesel::insert_into(embedding_dead_letters_table::embedding_dead_letters)
+        .values(&dead_letters)
+        .execute(&mut conn)
+        .await
+        .map_err(|e| ServiceError::InternalServerError(e.to_string()))?;
+
+    Ok(())
+}
+
+pub async fn get_embedding_dead_letters_query(
+    dataset_id: uuid::Uuid,
+    page: u64,
+    page_size: u64,
+    pool: web::Data<Pool>,
+) -> Result<Vec<EmbeddingDeadLetter>, ServiceError> {
+    use crate::data::schema::embedding_dead_letters::dsl as embedding_dead_letters_table;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|e| ServiceError::InternalServerError(e.to_string()))?;
+
+    embedding_dead_letters_table::embedding_dead_letters
+        .filter(embedding_dead_letters_table::dataset_id.eq(dataset_id))
+        .order(embedding_dead_letters_table::created_at.desc())
+        .offset(((page.max(1) - 1) * page_size) as i64)
+        .limit(page_size as i64)
+        .select(EmbeddingDeadLetter::as_select())
+        .load::<EmbeddingDeadLetter>(&mut conn)
+        .await
+        .map_err(|e| ServiceError::InternalServerError(e.to_string()))
+}
+
+/// Queues dead-lettered chunks of the dataset for ingestion again and removes them from the
+/// dead-letter table. Re-drives every dead-lettered chunk of the dataset if `ids` is `None`.
+pub async fn redrive_embedding_dead_letters_query(
+    dataset_id: uuid::Uuid,
+    ids: Option<Vec<uuid::Uuid>>,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<Vec<uuid::Uuid>, ServiceError> {
+    use crate::data::schema::embedding_dead_letters::dsl as embedding_dead_letters_table;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|e| ServiceError::InternalServerError(e.to_string()))?;
+
+    let mut query = embedding_dead_letters_table::embedding_dead_letters
+        .filter(embedding_dead_letters_table::dataset_id.eq(dataset_id))
+        .into_boxed();
+    if let Some(ids) = ids {
+        query = query.filter(embedding_dead_letters_table::id.eq_any(ids));
+    }
+
+    let dead_letters = query
+        .select(EmbeddingDeadLetter::as_select())
+        .load::<EmbeddingDeadLetter>(&mut conn)
+        .await
+        .map_err(|e| ServiceError::InternalServerError(e.to_string()))?;
+
+    if dead_letters.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let serialized_messages = dead_letters
+        .iter()
+        .map(|dead_letter| {
+            let message = get_redrive_message(dead_letter)?;
+            serde_json::to_string(&message).map_err(|_| {
+                ServiceError::InternalServerError("Failed to serialize chunk".to_string())
+            })
+        })
+        .collect::<Result<Vec<String>, ServiceError>>()?;
+
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    redis::cmd("lpush")
+        .arg("ingestion")
+        .arg(&serialized_messages)
+        .query_async::<redis::aio::MultiplexedConnection, usize>(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    diesel::delete(
+        embedding_dead_letters_table::embedding_dead_letters.filter(
+            embedding_dead_letters_table::id.eq_any(
+                dead_letters
+                    .iter()
+                    .map(|dead_letter| dead_letter.id)
+                    .collect::<Vec<uuid::Uuid>>(),
+            ),
+        ),
+    )
+    .execute(&mut conn)
+    .await
+    .map_err(|e| ServiceError::InternalServerError(e.to_string()))?;
+
+    Ok(dead_letters
+        .into_iter()
+        .map(|dead_letter| dead_letter.chunk_id)
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::models::IngestSpecificChunkMetadata;
+    use crate::handlers::chunk_handler::ChunkReqPayload;
+    use crate::operators::model_operator::parse_provider_response;
+    use std::collections::HashMap;
+
+    fn upload_message(dataset_id: uuid::Uuid, tracking_id: &str) -> UploadIngestionMessage {
+        UploadIngestionMessage {
+            ingest_specific_chunk_metadata: IngestSpecificChunkMetadata {
+                id: uuid::Uuid::new_v4(),
+                dataset_id,
+                qdrant_point_id: uuid::Uuid::new_v4(),
+            },
+            chunk: ChunkReqPayload {
+                chunk_html: Some(format!("<p>{}</p>", tracking_id)),
+                tracking_id: Some(tracking_id.to_string()),
+                ..Default::default()
+            },
+            dataset_id,
+            upsert_by_tracking_id: false,
+            dense_vector: None,
+            sparse_vector: None,
+        }
+    }
+
+    #[test]
+    pub fn test_rejected_chunk_is_dead_lettered_and_redriven() {
+        // Mock model server which answers every request for the bad chunk with a 400 until it
+        // is switched to a model which accepts it
+        let rejecting = std::cell::Cell::new(true);
+        let embed = |payload: &BulkUploadIngestionMessage| -> Result<(), ServiceError> {
+            let rejects = rejecting.get()
+                && payload
+                    .ingestion_messages
+                    .iter()
+                    .any(|message| message.chunk.tracking_id.as_deref() == Some("bad"));
+            if !rejects {
+                return Ok(());
+            }
+
+            let body = r#"{"error": {"message": "Input contains unsupported content", "type": "invalid_request_error", "code": 400}}"#;
+            parse_provider_response::<Vec<Vec<f32>>>("embedding", body, None)
+                .map(|_| ())
+                .map_err(|err| err.into_service_error(ServiceError::InternalServerError))
+        };
+
+        let dataset_id = uuid::Uuid::new_v4();
+        let payload = BulkUploadIngestionMessage {
+            attempt_number: 0,
+            rejected_attempts: 0,
+            dataset_id,
+            ingestion_messages: vec![
+                upload_message(dataset_id, "good"),
+                upload_message(dataset_id, "bad"),
+            ],
+        };
+
+        // The batch is split so the good chunk is not held back by the bad one
+        let error = embed(&payload).expect_err("Batch contains the bad chunk");
+        assert!(matches!(error, ServiceError::ModelRejected(_)));
+        let mut payloads = match plan_ingestion_retry(payload, &error, 3) {
+            IngestionRetry::Split(payloads) => payloads,
+            retry => panic!("Expected the batch to be split, got {:?}", retry),
+        };
+        assert_eq!(payloads.len(), 2);
+        let bad_payload = payloads.pop().expect("Two payloads");
+        assert!(embed(&payloads[0]).is_ok());
+
+        // The bad chunk is retried until it has been rejected 3 times
+        let mut retry = IngestionRetry::Retry(bad_payload);
+        let mut embed_calls = 0;
+        let dead_letter_payload = loop {
+            let payload = match retry {
+                IngestionRetry::Retry(payload) => payload,
+                IngestionRetry::DeadLetter(payload) => break payload,
+                retry => panic!("Expected a retry or dead letter, got {:?}", retry),
+            };
+            embed_calls += 1;
+            let error = embed(&payload).expect_err("The mock always rejects the bad chunk");
+            retry = plan_ingestion_retry(payload, &error, 3);
+        };
+        assert_eq!(embed_calls, 3);
+        assert_eq!(dead_letter_payload.rejected_attempts, 3);
+
+        let dead_letters = get_dead_letters_from_payload(
+            &dead_letter_payload,
+            &ServiceError::ModelRejected("Input contains unsupported content".to_string()),
+        )
+        .expect("Dead letters serialize");
+        assert_eq!(dead_letters.len(), 1);
+        let dead_letter = &dead_letters[0];
+        assert_eq!(dead_letter.tracking_id.as_deref(), Some("bad"));
+        assert_eq!(dead_letter.attempts, 3);
+        assert_eq!(
+            dead_letter.error_message,
+            "Input contains unsupported content"
+        );
+
+        // Re-driving starts the chunk over and it is ingested once the model accepts it
+        let redriven = get_redrive_message(dead_letter).expect("Dead letter deserializes");
+        assert_eq!(redriven.attempt_number, 0);
+        assert_eq!(redriven.rejected_attempts, 0);
+        assert_eq!(
+            redriven.ingestion_messages[0]
+                .ingest_specific_chunk_metadata
+                .id,
+            dead_letter.chunk_id
+        );
+        assert!(embed(&redriven).is_err());
+        rejecting.set(false);
+        assert!(embed(&redriven).is_ok());
+
+        // Other errors are never dead-lettered, only given up on after the usual attempts
+        let mut payload = get_redrive_message(dead_letter).expect("Dead letter deserializes");
+        let error = ServiceError::InternalServerError("Model server unreachable".to_string());
+        for _ in 1..MAX_INGESTION_ATTEMPTS {
+            payload = match plan_ingestion_retry(payload, &error, 3) {
+                IngestionRetry::Retry(payload) => payload,
+                retry => panic!("Expected a retry, got {:?}", retry),
+            };
+        }
+        assert!(matches!(
+            plan_ingestion_retry(payload, &error, 3),
+            IngestionRetry::GiveUp(_)
+        ));
+    }
+
+    #[test]
+    pub fn test_requeue_delay_schedule() {
+        let policy = RequeuePolicy {
+            jitter: RetryJitter::None,
+            ..Default::default()
+        };
+        let unavailable = ServiceError::UpstreamUnavailable("Model server is down".to_string());
+        let rejected = ServiceError::ModelRejected("Input too long".to_string());
+        let delay_ms =
+            |error: &ServiceError, attempt: usize, policy: &RequeuePolicy, sample: f64| {
+                get_requeue_delay(error, attempt, policy, sample).as_millis() as u64
+            };
+
+        // An unavailable provider is backed off from a longer base than errors of the message
+        let schedule = (1..=5)
+            .map(|attempt| delay_ms(&unavailable, attempt, &policy, 0.5))
+            .collect::<Vec<u64>>();
+        assert_eq!(schedule, vec![5000, 10000, 20000, 30000, 30000]);
+        let schedule = (1..=5)
+            .map(|attempt| delay_ms(&rejected, attempt, &policy, 0.5))
+            .collect::<Vec<u64>>();
+        assert_eq!(schedule, vec![500, 1000, 2000, 4000, 8000]);
+        assert_eq!(
+            delay_ms(
+                &ServiceError::RequestTimeout("Timed out".to_string()),
+                1,
+                &policy,
+                0.5
+            ),
+            5000
+        );
+        assert_eq!(delay_ms(&rejected, 0, &policy, 0.5), 500);
+        assert_eq!(delay_ms(&rejected, 100, &policy, 0.5), 30000);
+
+        // Full jitter spreads retries over the whole exponential delay
+        let full = RequeuePolicy {
+            jitter: RetryJitter::Full,
+            ..Default::default()
+        };
+        assert_eq!(delay_ms(&unavailable, 2, &full, 0.0), 0);
+        assert_eq!(delay_ms(&unavailable, 2, &full, 0.25), 2500);
+        assert_eq!(delay_ms(&unavailable, 2, &full, 1.0), 10000);
+        assert_eq!(delay_ms(&unavailable, 2, &full, f64::NAN), 0);
+        assert_eq!(delay_ms(&unavailable, 2, &full, 7.0), 10000);
+
+        // Decorrelated jitter stays between the base and 3x the previous delay
+        let decorrelated = RequeuePolicy {
+            jitter: RetryJitter::Decorrelated,
+            ..Default::default()
+        };
+        assert_eq!(delay_ms(&unavailable, 3, &decorrelated, 0.0), 5000);
+        assert_eq!(delay_ms(&unavailable, 3, &decorrelated, 0.5), 17500);
+        assert_eq!(delay_ms(&unavailable, 3, &decorrelated, 1.0), 30000);
+    }
+
+    #[test]
+    pub fn test_redelivery_ramp() {
+        let policy = RequeuePolicy {
+            redeliveries_per_window: 20,
+            redelivery_window_ms: 1000,
+            ..Default::default()
+        };
+        let window_ms = policy.redelivery_window_ms;
+        // Deterministic stand-in for the random samples
+        let sample = |index: usize| (index * 37 % 100) as f64 / 100.0;
+
+        // 200 messages of one dataset and 10 of another failed during an outage and are all due
+        // in the first second after the provider recovered. Each asks for a slot when it is due
+        // and asks again after the wait it is given, as the worker does.
+        let mut pending = (0..210)
+            .map(|index| ((sample(index) * window_ms as f64) as u64, index))
+            .collect::<std::collections::BTreeSet<(u64, usize)>>();
+        let mut counters: HashMap<(&str, u64), usize> = HashMap::new();
+        let mut delivered: HashMap<(&str, u64), usize> = HashMap::new();
+        let mut attempts = 0;
+        while let Some((now_ms, index)) = pending.pop_first() {
+            let dataset = if index < 200 { "busy" } else { "quiet" };
+            let window = now_ms / window_ms;
+            let position = counters.entry((dataset, window)).or_insert(0);
+            *position += 1;
+
+            attempts += 1;
+            match get_redelivery_wait(
+                *position,
+                std::time::Duration::from_millis(now_ms % window_ms),
+                &policy,
+                sample(index + attempts),
+            ) {
+                None => *delivered.entry((dataset, window)).or_insert(0) += 1,
+                Some(wait) => {
+                    pending.insert((now_ms + wait.as_millis() as u64, index));
+                }
+            }
+        }
+
+        // No window sends more than the cap of a dataset to the provider
+        assert!(delivered.values().all(|count| *count <= 20));
+        assert_eq!(delivered.values().sum::<usize>(), 210);
+        // The busy dataset ramps back at the cap, over about 200 / 20 windows
+        let busy_windows = delivered
+            .keys()
+            .filter(|(dataset, _)| *dataset == "busy")
+            .map(|(_, window)| *window)
+            .collect::<Vec<u64>>();
+        assert!(busy_windows.iter().max().copied().unwrap_or_default() <= 11);
+        assert!(
+            busy_windows
+                .iter()
+                .filter(|window| delivered[&("busy", **window)] == 20)
+                .count()
+                >= 8
+        );
+        // while the quiet dataset is not held back behind it
+        assert_eq!(delivered.get(&("quiet", 0)), Some(&10));
+    }
+}
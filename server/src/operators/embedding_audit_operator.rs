@@ -0,0 +1,85 @@
+use crate::data::models::{DatasetConfiguration, EmbeddingAuditEntry, Pool};
+use crate::errors::ServiceError;
+use crate::operators::model_operator::collect_embedding_audit_entries;
+use actix_web::web;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+/// Runs `future` and, if the dataset has `EMBEDDING_AUDIT_LOG_ENABLED`, stores an audit log entry
+/// for every model server call it made. Failing to store the entries only logs an error, the
+/// output of `future` is returned either way.
+pub async fn with_embedding_audit_log<F: std::future::Future>(
+    dataset_id: uuid::Uuid,
+    dataset_config: &DatasetConfiguration,
+    pool: web::Data<Pool>,
+    future: F,
+) -> F::Output {
+    if !dataset_config.EMBEDDING_AUDIT_LOG_ENABLED {
+        return future.await;
+    }
+
+    let (output, entries) = collect_embedding_audit_entries(dataset_id, future).await;
+    if !entries.is_empty() {
+        if let Err(err) = insert_embedding_audit_entries_query(entries, pool).await {
+            log::error!("Failed to store embedding audit log entries {:?}", err);
+        }
+    }
+
+    output
+}
+
+pub async fn insert_embedding_audit_entries_query(
+    entries: Vec<EmbeddingAuditEntry>,
+    pool: web::Data<Pool>,
+) -> Result<(), ServiceError> {
+    use crate::data::schema::embedding_audit_log::dsl as embedding_audit_log_table;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|e| ServiceError::InternalServerError(e.to_string()))?;
+
+    diesel::insert_into(embedding_audit_log_table::embedding_audit_log)
+        .values(&entries)
+        .execute(&mut conn)
+        .await
+        .map_err(|e| ServiceError::InternalServerError(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Audit log entries of the dataset created between `start` and `end`, newest first.
+pub async fn get_embedding_audit_entries_query(
+    dataset_id: uuid::Uuid,
+    start: Option<chrono::NaiveDateTime>,
+    end: Option<chrono::NaiveDateTime>,
+    page: u64,
+    page_size: u64,
+    pool: web::Data<Pool>,
+) -> Result<Vec<EmbeddingAuditEntry>, ServiceError> {
+    use crate::data::schema::embedding_audit_log::dsl as embedding_audit_log_table;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|e| ServiceError::InternalServerError(e.to_string()))?;
+
+    let mut query = embedding_audit_log_table::embedding_audit_log
+        .filter(embedding_audit_log_table::dataset_id.eq(dataset_id))
+        .into_boxed();
+    if let Some(start) = start {
+        query = query.filter(embedding_audit_log_table::created_at.ge(start));
+    }
+    if let Some(end) = end {
+        query = query.filter(embedding_audit_log_table::created_at.lt(end));
+    }
+
+    query
+        .order(embedding_audit_log_table::created_at.desc())
+        .offset(((page.max(1) - 1) * page_size) as i64)
+        .limit(page_size as i64)
+        .select(EmbeddingAuditEntry::as_select())
+        .load::<EmbeddingAuditEntry>(&mut conn)
+        .await
+        .map_err(|e| ServiceError::InternalServerError(e.to_string()))
+}
@@ -3,6 +3,7 @@ use crate::{
     errors::ServiceError,
 };
 use actix_web::web;
+use clickhouse::Row;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
@@ -113,3 +114,46 @@ pub async fn get_events_query(
         page_count: count,
     })
 }
+
+/// Number of times preprocessing hit one of the embedding pipeline's limits.
+#[derive(Debug, Deserialize, Serialize, Clone, Row, ToSchema)]
+pub struct PreprocessingEventCount {
+    /// The step of the embedding pipeline whose limit was hit, html_removal, dense, sparse or
+    /// boost_phrase
+    pub stage: String,
+    /// What happened to the content, truncated or emptied
+    pub kind: String,
+    pub count: u64,
+}
+
+/// Counts the chunk_preprocessed events of the dataset from the last 24 hours by stage and kind.
+pub async fn get_preprocessing_event_counts_query(
+    dataset_id: uuid::Uuid,
+    clickhouse_client: web::Data<clickhouse::Client>,
+) -> Result<Vec<PreprocessingEventCount>, ServiceError> {
+    let query = format!(
+        "
+        SELECT
+            JSONExtractString(event_data, 'stage') AS stage,
+            JSONExtractString(event_data, 'kind') AS kind,
+            count(*) AS count
+        FROM dataset_events
+        WHERE dataset_id = '{dataset_id}'
+            AND event_type = '{event_type}'
+            AND created_at >= now() - INTERVAL 1 DAY
+        GROUP BY stage, kind
+        ORDER BY stage, kind
+        ",
+        dataset_id = dataset_id,
+        event_type = EventTypeRequest::ChunkPreprocessed,
+    );
+
+    clickhouse_client
+        .query(&query)
+        .fetch_all()
+        .await
+        .map_err(|err| {
+            log::error!("Failed to get preprocessing event counts {:?}", err);
+            ServiceError::BadRequest("Failed to get preprocessing event counts".to_string())
+        })
+}
@@ -0,0 +1,390 @@
+//! A local stand-in for the model servers which records every request it receives, so tests can
+//! compare the exact requests sent to providers against the snapshots in `snapshots/upstream`.
+//!
+//! It answers the OpenAI or Cohere embeddings, TEI `embed_sparse` and TEI or Cohere `rerank`
+//! schemas with deterministic responses, embeddings in base64 when the request asks for that
+//! `encoding_format`, and conditional requests carrying `If-None-Match` with an empty 304 Not
+//! Modified. Requests under `/status/<code>/` are answered with that failing status, the first
+//! `<n>` requests under `/flaky/<n>/` with a 503, and requests under `/delay/<ms>/` only after
+//! waiting that long. Run the tests with `UPDATE_UPSTREAM_SNAPSHOTS=true` to rewrite the
+//! snapshots after an intended change to a request format.
+
+use super::model_operator::encode_base64_embedding;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+
+/// Headers kept in the snapshots, everything else depends on the http client.
+const RECORDED_HEADERS: [&str; 4] = ["api-key", "authorization", "content-type", "if-none-match"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// Path and query string, the origin changes with every run
+    pub path: String,
+    /// The headers of `RECORDED_HEADERS` that were sent, with credentials redacted
+    pub headers: BTreeMap<String, String>,
+    pub body: serde_json::Value,
+}
+
+pub struct MockUpstream {
+    pub origin: String,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+/// Requests answered so far to each path under `/flaky/<n>/`
+type FlakyRequestCounts = Mutex<BTreeMap<String, usize>>;
+
+impl MockUpstream {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Mock upstream binds");
+        let origin = format!(
+            "http://{}",
+            listener.local_addr().expect("Mock upstream has an address")
+        );
+        let requests = Arc::new(Mutex::new(vec![]));
+        let flaky_request_counts = Arc::new(FlakyRequestCounts::default());
+
+        let recorded_requests = requests.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let recorded_requests = recorded_requests.clone();
+                let flaky_request_counts = flaky_request_counts.clone();
+                std::thread::spawn(move || {
+                    handle_connection(stream, &recorded_requests, &flaky_request_counts)
+                });
+            }
+        });
+
+        MockUpstream { origin, requests }
+    }
+
+    /// Returns the requests received since the last call, sorted so concurrent requests of one
+    /// call always come back in the same order.
+    pub fn take_requests(&self) -> Vec<RecordedRequest> {
+        let mut requests = std::mem::take(
+            &mut *self
+                .requests
+                .lock()
+                .expect("Mock upstream lock is poisoned"),
+        );
+        requests.sort_by_key(|request| (request.path.clone(), request.body.to_string()));
+        requests
+    }
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    recorded_requests: &Mutex<Vec<RecordedRequest>>,
+    flaky_request_counts: &FlakyRequestCounts,
+) {
+    let mut reader = BufReader::new(match stream.try_clone() {
+        Ok(stream) => stream,
+        Err(_) => return,
+    });
+
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    let mut request_line = request_line.split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let path = request_line.next().unwrap_or_default().to_string();
+
+    let mut headers = BTreeMap::new();
+    let mut content_length = 0;
+    let mut chunked = false;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).is_err() || line.trim().is_empty() {
+            break;
+        }
+        let (name, value) = match line.split_once(':') {
+            Some((name, value)) => (name.trim().to_lowercase(), value.trim().to_string()),
+            None => continue,
+        };
+
+        match name.as_str() {
+            "content-length" => content_length = value.parse().unwrap_or(0),
+            "transfer-encoding" => chunked = value.eq_ignore_ascii_case("chunked"),
+            _ => {}
+        }
+        if RECORDED_HEADERS.contains(&name.as_str()) {
+            let value = match name.as_str() {
+                "authorization" => format!(
+                    "{}[REDACTED]",
+                    if value.starts_with("Bearer ") {
+                        "Bearer "
+                    } else {
+                        ""
+                    }
+                ),
+                "api-key" => "[REDACTED]".to_string(),
+                _ => value,
+            };
+            headers.insert(name, value);
+        }
+    }
+
+    let body = if chunked {
+        read_chunked_body(&mut reader)
+    } else {
+        let mut body = vec![0; content_length];
+        reader
+            .read_exact(&mut body)
+            .map(|_| body)
+            .unwrap_or_default()
+    };
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap_or_default();
+
+    let not_modified = headers.contains_key("if-none-match");
+    let forced_status = get_forced_status(&path, flaky_request_counts);
+    let delay_ms = get_delay_ms(&path);
+    let response = get_mock_response(&path, &body);
+    recorded_requests
+        .lock()
+        .expect("Mock upstream lock is poisoned")
+        .push(RecordedRequest {
+            method,
+            path,
+            headers,
+            body,
+        });
+
+    let (status, response_body) = match (forced_status, response) {
+        (Some(status), _) => (status, r#"{"error": "mock failure"}"#.to_string()),
+        (None, Some(_)) if not_modified => ("304 Not Modified".to_string(), "".to_string()),
+        (None, Some(response)) => ("200 OK".to_string(), response.to_string()),
+        (None, None) => (
+            "404 Not Found".to_string(),
+            r#"{"error": "not found"}"#.to_string(),
+        ),
+    };
+    if let Some(delay_ms) = delay_ms {
+        std::thread::sleep(std::time::Duration::from_millis(delay_ms));
+    }
+    let mut stream = stream;
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        response_body.len(),
+        response_body
+    );
+    let _ = stream.flush();
+}
+
+/// The status line of a request to a `/status/<code>/` path, or to a `/flaky/<n>/` path that
+/// has not failed `<n>` times yet.
+fn get_forced_status(path: &str, flaky_request_counts: &FlakyRequestCounts) -> Option<String> {
+    if let Some(failures) = path.strip_prefix("/flaky/") {
+        let failures = failures.split('/').next()?;
+        let mut counts = flaky_request_counts
+            .lock()
+            .expect("Mock upstream lock is poisoned");
+        let count = counts.entry(path.to_string()).or_insert(0);
+        *count += 1;
+        return (*count <= failures.parse().ok()?).then(|| "503 Mock Failure".to_string());
+    }
+
+    let code = path.strip_prefix("/status/")?.split('/').next()?;
+    code.parse::<u16>().ok()?;
+    Some(format!("{} Mock Failure", code))
+}
+
+/// How long to wait before answering a request to a `/delay/<ms>/` path.
+fn get_delay_ms(path: &str) -> Option<u64> {
+    path.strip_prefix("/delay/")?
+        .split('/')
+        .next()?
+        .parse()
+        .ok()
+}
+
+fn read_chunked_body(reader: &mut BufReader<TcpStream>) -> Vec<u8> {
+    let mut body = vec![];
+    loop {
+        let mut size_line = String::new();
+        if reader.read_line(&mut size_line).is_err() {
+            break;
+        }
+        let size = usize::from_str_radix(size_line.trim(), 16).unwrap_or(0);
+        let mut chunk = vec![0; size + 2];
+        if size == 0 || reader.read_exact(&mut chunk).is_err() {
+            break;
+        }
+        chunk.truncate(size);
+        body.extend(chunk);
+    }
+    body
+}
+
+/// Rerankers score texts containing this above all others.
+pub const MOCK_RELEVANT_MARKER: &str = "MOCK_RELEVANT";
+
+fn get_mock_rerank_score(index: usize, text: &serde_json::Value, count: usize) -> f32 {
+    let marker_bonus = match text.as_str() {
+        Some(text) if text.contains(MOCK_RELEVANT_MARKER) => 1.0,
+        _ => 0.0,
+    };
+    (index + 1) as f32 / count as f32 + marker_bonus
+}
+
+/// Deterministic responses in the schema of the endpoint. Dense vectors are
+/// `[input index + 1, input chars, 1]`, sparse vectors hold the single token `input index + 1`
+/// and rerankers score the last text best, except for texts containing `MOCK_RELEVANT_MARKER`.
+fn get_mock_response(path: &str, body: &serde_json::Value) -> Option<serde_json::Value> {
+    let path = path.split('?').next().unwrap_or_default();
+
+    if path.ends_with("/embeddings") {
+        let inputs = match &body["input"] {
+            serde_json::Value::Array(inputs) => inputs.clone(),
+            input => vec![input.clone()],
+        };
+        let data = inputs
+            .iter()
+            .enumerate()
+            .map(|(index, input)| {
+                let chars = input.as_str().unwrap_or_default().chars().count();
+                let embedding = [index as f32 + 1.0, chars as f32, 1.0];
+                if body["encoding_format"] == "base64" {
+                    serde_json::json!({ "embedding": encode_base64_embedding(&embedding) })
+                } else {
+                    serde_json::json!({ "embedding": embedding })
+                }
+            })
+            .collect::<Vec<serde_json::Value>>();
+        return Some(serde_json::json!({ "data": data }));
+    }
+
+    if path.ends_with("/embed") {
+        let texts = body["texts"].as_array().cloned().unwrap_or_default();
+        let embeddings = texts
+            .iter()
+            .enumerate()
+            .map(|(index, text)| {
+                let chars = text.as_str().unwrap_or_default().chars().count();
+                serde_json::json!([index as f32 + 1.0, chars as f32, 1.0])
+            })
+            .collect::<Vec<serde_json::Value>>();
+        return Some(serde_json::json!({
+            "id": "mock",
+            "texts": texts,
+            "embeddings": embeddings,
+            "response_type": "embeddings_floats",
+        }));
+    }
+
+    if path.ends_with(":predict") {
+        let instances = body["instances"].as_array().cloned().unwrap_or_default();
+        let predictions = instances
+            .iter()
+            .enumerate()
+            .map(|(index, instance)| {
+                let chars = instance["content"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .chars()
+                    .count();
+                serde_json::json!({
+                    "embeddings": {
+                        "values": [index as f32 + 1.0, chars as f32, 1.0],
+                        "statistics": { "truncated": false, "token_count": chars },
+                    }
+                })
+            })
+            .collect::<Vec<serde_json::Value>>();
+        return Some(serde_json::json!({ "predictions": predictions }));
+    }
+
+    // OAuth token endpoints, of a metadata server or for service account assertions
+    if path.ends_with("/token") {
+        return Some(serde_json::json!({
+            "access_token": "mock-access-token",
+            "expires_in": 3599,
+            "token_type": "Bearer",
+        }));
+    }
+
+    if path.ends_with("/embed_sparse") {
+        let inputs = body["inputs"].as_array().cloned().unwrap_or_default();
+        return Some(serde_json::Value::Array(
+            (0..inputs.len())
+                .map(|index| serde_json::json!([{ "index": index + 1, "value": 1.0 }]))
+                .collect(),
+        ));
+    }
+
+    if path.ends_with("/rerank") {
+        // Cohere compatible rerankers get documents, the self hosted cross encoder texts
+        if let Some(documents) = body["documents"].as_array() {
+            let top_n = body["top_n"].as_u64().unwrap_or(documents.len() as u64) as usize;
+            let mut results = documents
+                .iter()
+                .enumerate()
+                .map(|(index, document)| {
+                    (
+                        index,
+                        get_mock_rerank_score(index, document, documents.len()),
+                    )
+                })
+                .collect::<Vec<(usize, f32)>>();
+            results.sort_by(|a, b| b.1.total_cmp(&a.1));
+            let results = results
+                .into_iter()
+                .take(top_n)
+                .map(|(index, relevance_score)| {
+                    serde_json::json!({
+                        "index": index,
+                        "relevance_score": relevance_score,
+                    })
+                })
+                .collect::<Vec<serde_json::Value>>();
+            return Some(serde_json::json!({ "results": results }));
+        }
+
+        let texts = body["texts"].as_array().cloned().unwrap_or_default();
+        return Some(serde_json::Value::Array(
+            texts
+                .iter()
+                .enumerate()
+                .map(|(index, text)| {
+                    serde_json::json!({
+                        "index": index,
+                        "score": get_mock_rerank_score(index, text, texts.len()),
+                    })
+                })
+                .collect(),
+        ));
+    }
+
+    None
+}
+
+/// Compares `request` with the snapshot `snapshots/upstream/<name>.json`, or rewrites the
+/// snapshot when `UPDATE_UPSTREAM_SNAPSHOTS` is true.
+pub fn assert_request_snapshot(name: &str, request: &RecordedRequest) {
+    let snapshot_path = format!(
+        "{}/src/operators/snapshots/upstream/{}.json",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    );
+
+    if std::env::var("UPDATE_UPSTREAM_SNAPSHOTS").unwrap_or("false".to_string()) == "true" {
+        let snapshot = serde_json::to_string_pretty(request).expect("Request serializes");
+        std::fs::write(&snapshot_path, snapshot + "\n").expect("Snapshot is writable");
+        return;
+    }
+
+    let snapshot = std::fs::read_to_string(&snapshot_path)
+        .unwrap_or_else(|_| panic!("Missing upstream snapshot {}", snapshot_path));
+    let expected: RecordedRequest =
+        serde_json::from_str(&snapshot).expect("Upstream snapshot is a recorded request");
+    assert_eq!(
+        request, &expected,
+        "Request differs from the {} snapshot, rerun with UPDATE_UPSTREAM_SNAPSHOTS=true if the change is intended",
+        name
+    );
+}
@@ -3,20 +3,28 @@ pub mod chunk_operator;
 pub mod clickhouse_operator;
 pub mod crawl_operator;
 pub mod dataset_operator;
+pub mod dead_letter_operator;
 pub mod dittofeed_operator;
 pub mod email_operator;
+pub mod embedding_audit_operator;
 pub mod event_operator;
 pub mod file_operator;
+pub mod gcp_auth_operator;
 pub mod group_operator;
 pub mod invitation_operator;
 pub mod message_operator;
+#[cfg(test)]
+pub mod mock_upstream;
 pub mod model_operator;
 pub mod organization_operator;
 pub mod parse_operator;
 pub mod qdrant_operator;
 pub mod search_operator;
 pub mod stripe_operator;
+pub mod term_token_operator;
+pub mod token_operator;
 pub mod topic_operator;
 pub mod typo_operator;
 pub mod user_operator;
+pub mod vector_operator;
 pub mod webhook_operator;
@@ -1,10 +1,9 @@
-use ndarray::Array2;
 use regex::Regex;
 use regex_split::RegexSplit;
 use scraper::{Html, Selector};
 use std::cmp;
 
-use crate::errors::ServiceError;
+use crate::{errors::ServiceError, operators::vector_operator::mean};
 
 pub fn convert_html_to_text(html: &str) -> String {
     let dom = Html::parse_fragment(html);
@@ -12,6 +11,28 @@ pub fn convert_html_to_text(html: &str) -> String {
     text
 }
 
+/// Text describing the elements of `html` which have no text of their own, the alt and title
+/// attributes of images, iframes and the like joined with spaces. `None` if there are none.
+pub fn get_html_fallback_text(html: &str) -> Option<String> {
+    let dom = Html::parse_fragment(html);
+    let selector = Selector::parse("[alt], [title]").expect("Selector is always valid");
+
+    let text = dom
+        .select(&selector)
+        .flat_map(|element| [element.value().attr("alt"), element.value().attr("title")])
+        .flatten()
+        .map(|text| text.trim())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ");
+
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
 pub fn extract_text_from_html(html: &str) -> String {
     let document = Html::parse_document(html);
     let selector = Selector::parse("body").unwrap();
@@ -133,25 +154,18 @@ pub fn coarse_doc_chunker(
 }
 
 pub fn average_embeddings(embeddings: Vec<Vec<f32>>) -> Result<Vec<f32>, ServiceError> {
-    let first_embedding_len = match embeddings.first() {
-        Some(embedding) => embedding.len(),
-        None => {
-            return Err(ServiceError::BadRequest(
-                "No embeddings provided".to_string(),
-            ));
-        }
-    };
+    if embeddings.is_empty() {
+        return Err(ServiceError::BadRequest(
+            "No embeddings provided".to_string(),
+        ));
+    }
 
-    let shape = (embeddings.len(), first_embedding_len);
-    let flat: Vec<f32> = embeddings.iter().flatten().cloned().collect();
-    let arr: Array2<f32> = Array2::from_shape_vec(shape, flat).map_err(|e| {
-        log::error!("Error creating ndarray from embeddings: {}", e);
+    mean(&embeddings).ok_or_else(|| {
+        log::error!("Embeddings to average have differing dimensions");
         ServiceError::InternalServerError(
-            "Error creating ndarray from embeddings to average".to_string(),
+            "Embeddings to average have differing dimensions".to_string(),
         )
-    })?;
-
-    Ok((arr.sum_axis(ndarray::Axis(0)) / (embeddings.len() as f32)).to_vec())
+    })
 }
 
 #[cfg(test)]
@@ -170,4 +184,23 @@ mod test {
         let result = average_embeddings(embeddings).unwrap();
         assert!(result == vec![2.0, 2.5, 1.0]);
     }
+
+    #[test]
+    pub fn test_html_fallback_text() {
+        let image_only = r#"<div>
+            <img src="https://example.com/cat.png" alt="A cat sleeping on a keyboard">
+        </div>"#;
+        assert!(convert_html_to_text(image_only).trim().is_empty());
+        assert_eq!(
+            get_html_fallback_text(image_only),
+            Some("A cat sleeping on a keyboard".to_string())
+        );
+        assert_eq!(
+            get_html_fallback_text(
+                r#"<iframe src="https://example.com/embed" title="Pricing video"></iframe><img src="a.png" alt=" ">"#
+            ),
+            Some("Pricing video".to_string())
+        );
+        assert_eq!(get_html_fallback_text(r#"<img src="a.png">"#), None);
+    }
 }
@@ -1,37 +1,45 @@
 use super::{
     group_operator::get_groups_from_group_ids_query,
+    model_operator::{
+        check_created_vector_count, get_dense_vectors, get_dual_write_config, get_sparse_vectors,
+        get_templated_embedding_content, get_vector_field_weights, score_with_distance_phrase,
+        score_with_vector_fields, EmbedContext,
+    },
     search_operator::{assemble_qdrant_filter, SearchResult, SearchResultTrait},
 };
 use crate::{
     data::models::{
-        ChunkMetadata, DatasetConfiguration, DistanceMetric, Pool, QdrantPayload, RecommendType,
-        RecommendationStrategy, SortByField, SortOrder,
+        ChunkMetadata, DatasetConfiguration, DenseVectorSlot, DistanceMetric, Pool, QdrantPayload,
+        RecommendType, RecommendationStrategy, SortByField, SortOrder,
     },
     errors::ServiceError,
     get_env,
-    handlers::chunk_handler::ChunkFilter,
+    handlers::chunk_handler::{ChunkFilter, FullTextBoost, SemanticBoost},
 };
 use actix_web::web;
 use futures::future::try_join_all;
-use itertools::Itertools;
+use itertools::{izip, Itertools};
 use qdrant_client::{
     qdrant::{
-        group_id::Kind, point_id::PointIdOptions, quantization_config::Quantization, query,
-        vectors::VectorsOptions, BinaryQuantization, CreateCollectionBuilder,
-        CreateFieldIndexCollectionBuilder, DeleteFieldIndexCollectionBuilder, DeletePointsBuilder,
-        Distance, FieldType, Filter, GetPointsBuilder, HnswConfigDiff, OrderBy, PointId,
-        PointStruct, PrefetchQuery, QuantizationConfig, Query, QueryBatchPoints, QueryPointGroups,
-        QueryPoints, RecommendPointGroups, RecommendPoints, RecommendStrategy, RetrievedPoint,
+        condition::ConditionOneOf::HasId, group_id::Kind, point_id::PointIdOptions,
+        quantization_config::Quantization, query, vectors::VectorsOptions,
+        with_payload_selector::SelectorOptions, with_vectors_selector, BinaryQuantization,
+        Condition, CountPointsBuilder, CreateCollectionBuilder, CreateFieldIndexCollectionBuilder,
+        DeleteFieldIndexCollectionBuilder, DeletePointVectorsBuilder, DeletePointsBuilder,
+        Distance, FieldType, Filter, GetPointsBuilder, HasIdCondition, HnswConfigDiff, OrderBy,
+        PayloadIncludeSelector, PointId, PointStruct, PointVectors, PrefetchQuery,
+        QuantizationConfig, Query, QueryBatchPoints, QueryPointGroups, QueryPoints,
+        RecommendPointGroups, RecommendPoints, RecommendStrategy, RetrievedPoint, ScoredPoint,
         ScrollPointsBuilder, SearchBatchPoints, SearchParams, SearchPointGroups, SearchPoints,
         SetPayloadPointsBuilder, SparseIndexConfig, SparseVectorConfig, SparseVectorParams,
-        TextIndexParamsBuilder, TokenizerType, UpsertPointsBuilder, UuidIndexParamsBuilder, Value,
-        Vector, VectorInput, VectorParams, VectorParamsMap, VectorsConfig, WithPayloadSelector,
-        WithVectorsSelector,
+        TextIndexParamsBuilder, TokenizerType, UpdatePointVectorsBuilder, UpsertPointsBuilder,
+        UuidIndexParamsBuilder, Value, Vector, VectorInput, VectorParams, VectorParamsMap,
+        VectorsConfig, VectorsSelector, WithPayloadSelector, WithVectorsSelector,
     },
     Payload, Qdrant,
 };
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, str::FromStr};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
 
 pub async fn get_qdrant_connection(
     qdrant_url: Option<&str>,
@@ -70,6 +78,346 @@ pub fn get_qdrant_collection_from_dataset_config(dataset_config: &DatasetConfigu
     }
 }
 
+/// Sizes of the dense vectors collections are created with.
+const DENSE_VECTOR_SIZES: [usize; 6] = [384, 512, 768, 1024, 1536, 3072];
+
+fn get_dense_slot_vector_name(size: usize, slot: DenseVectorSlot) -> String {
+    match slot {
+        DenseVectorSlot::Primary => format!("{}_vectors", size),
+        DenseVectorSlot::Secondary => format!("{}_migration_vectors", size),
+    }
+}
+
+/// Named vector holding the dense content vectors of `size` in `slot`, `None` for sizes
+/// collections are not created with. Search reads the dataset's `EMBEDDING_VECTOR_SLOT`, the
+/// other slot holds the vectors of its `EMBEDDING_DUAL_WRITE_TARGET`.
+pub fn get_dense_vector_name(size: usize, slot: DenseVectorSlot) -> Option<String> {
+    DENSE_VECTOR_SIZES
+        .contains(&size)
+        .then(|| get_dense_slot_vector_name(size, slot))
+}
+
+/// Payload key recording which model's vector of the current content a point holds in its
+/// inactive dense slot, see `get_dual_write_marker`.
+pub const DUAL_WRITE_VECTOR_PAYLOAD_KEY: &str = "dual_write_vector";
+
+/// Marker of the points which already hold the vector of the dataset's
+/// `EMBEDDING_DUAL_WRITE_TARGET`. It names the slot too, so markers left from an earlier
+/// migration into the other slot don't count.
+pub fn get_dual_write_marker(dataset_config: &DatasetConfiguration) -> Option<String> {
+    dataset_config
+        .EMBEDDING_DUAL_WRITE_TARGET
+        .as_ref()
+        .map(|target| {
+            format!(
+                "{}:{}",
+                dataset_config.EMBEDDING_VECTOR_SLOT.other(),
+                target.model_name
+            )
+        })
+}
+
+/// Inserts a chunk's content vector under the dataset's active dense vector. While the dataset
+/// dual-writes, the vector of the target model goes under the other slot and the point is marked
+/// so the backfill skips it.
+pub fn insert_dense_vectors(
+    vector_payload: &mut HashMap<String, Vector>,
+    payload: &mut Payload,
+    vector: Vec<f32>,
+    dual_write_vector: Option<Vec<f32>>,
+    dataset_config: &DatasetConfiguration,
+) -> Result<(), ServiceError> {
+    let slot = dataset_config.EMBEDDING_VECTOR_SLOT;
+    let invalid_size = || ServiceError::BadRequest("Invalid embedding vector size".into());
+
+    vector_payload.insert(
+        get_dense_vector_name(vector.len(), slot).ok_or_else(invalid_size)?,
+        Vector::from(vector),
+    );
+    if let (Some(dual_write_vector), Some(marker)) =
+        (dual_write_vector, get_dual_write_marker(dataset_config))
+    {
+        vector_payload.insert(
+            get_dense_vector_name(dual_write_vector.len(), slot.other())
+                .ok_or_else(invalid_size)?,
+            Vector::from(dual_write_vector),
+        );
+        payload.insert(DUAL_WRITE_VECTOR_PAYLOAD_KEY, marker);
+    }
+
+    Ok(())
+}
+
+/// Named vector holding the semantic_boost phrase vectors of datasets with
+/// `SEPARATE_DISTANCE_PHRASE_VECTORS` set.
+pub fn get_distance_phrase_vector_name(size: usize) -> String {
+    format!("{}_distance_phrase_vectors", size)
+}
+
+/// Collections are created with a fixed set of named vectors, so the vectors of a dataset's
+/// `EMBEDDING_VECTOR_FIELDS` are stored by the index of their field rather than its name.
+pub const MAX_EMBEDDING_VECTOR_FIELDS: usize = 4;
+
+/// Named vector holding the vectors of the `field_index`th entry of `EMBEDDING_VECTOR_FIELDS`.
+pub fn get_vector_field_vector_name(size: usize, field_index: usize) -> String {
+    format!("{}_field_{}_vectors", size, field_index)
+}
+
+/// Payload key of the distance_factor applied to the phrase vector at search time.
+pub const DISTANCE_FACTOR_PAYLOAD_KEY: &str = "distance_factor";
+
+/// Payload key set to `false` on points whose sparse vector was deferred by
+/// `LAZY_SPARSE_ENCODING` and to `true` once it is computed. Points without it were encoded at
+/// ingest.
+pub const SPARSE_ENCODED_PAYLOAD_KEY: &str = "sparse_encoded";
+
+/// Whether a new chunk is stored without its sparse vector, leaving it to
+/// `encode_pending_sparse_vectors`. Chunks with a fulltext boost are always encoded at ingest
+/// because the boost phrase is not kept on the point.
+pub fn should_defer_sparse_encoding(
+    dataset_config: &DatasetConfiguration,
+    fulltext_boost: Option<&FullTextBoost>,
+) -> bool {
+    dataset_config.FULLTEXT_ENABLED
+        && dataset_config.LAZY_SPARSE_ENCODING
+        && fulltext_boost.is_none()
+}
+
+/// Payload key of the hash of the text a point's vectors were created from, see
+/// `get_chunk_content_hash`.
+pub const CONTENT_HASH_PAYLOAD_KEY: &str = "content_hash";
+
+/// Payload keys describing how a point's vectors are stored, which are not part of
+/// `QdrantPayload` and have to survive payload overwrites.
+const VECTOR_STATE_PAYLOAD_KEYS: [&str; 4] = [
+    DISTANCE_FACTOR_PAYLOAD_KEY,
+    SPARSE_ENCODED_PAYLOAD_KEY,
+    CONTENT_HASH_PAYLOAD_KEY,
+    DUAL_WRITE_VECTOR_PAYLOAD_KEY,
+];
+
+/// Hashes the text given to the sparse model (`content`) and the dense model
+/// (`embedding_content`, the content after templating). Two chunks with the same hash get the
+/// same vectors as long as the dataset's model settings don't change.
+pub fn get_chunk_content_hash(content: &str, embedding_content: &str) -> String {
+    let mut hasher = blake3::Hasher::new();
+    for text in [content, embedding_content] {
+        hasher.update(&(text.len() as u64).to_le_bytes());
+        hasher.update(text.as_bytes());
+    }
+
+    hasher.finalize().to_hex().to_string()
+}
+
+/// Which vectors of an updated chunk have to be created again. Vectors which are not
+/// regenerated are left as they are on the point.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkVectorUpdate {
+    pub regenerate_dense: bool,
+    /// Only embed the semantic_boost phrase and replace the point's phrase vector, for datasets
+    /// with `SEPARATE_DISTANCE_PHRASE_VECTORS` set.
+    pub recompute_phrase: bool,
+    pub regenerate_sparse: bool,
+}
+
+impl ChunkVectorUpdate {
+    pub const FULL: ChunkVectorUpdate = ChunkVectorUpdate {
+        regenerate_dense: true,
+        recompute_phrase: false,
+        regenerate_sparse: true,
+    };
+
+    pub fn regenerates_vectors(&self) -> bool {
+        self.regenerate_dense || self.recompute_phrase || self.regenerate_sparse
+    }
+}
+
+/// Decides which vectors a chunk update needs from the content hash stored on its point. Points
+/// stored before content hashes were kept always get new vectors. When the content is unchanged
+/// only the vectors a boost in the update applies to are created again.
+pub fn plan_chunk_vector_update(
+    stored_content_hash: Option<&str>,
+    content_hash: &str,
+    semantic_boost: Option<&SemanticBoost>,
+    fulltext_boost: Option<&FullTextBoost>,
+    dataset_config: &DatasetConfiguration,
+) -> ChunkVectorUpdate {
+    if stored_content_hash != Some(content_hash) {
+        return ChunkVectorUpdate::FULL;
+    }
+
+    let separate_phrase = dataset_config.SEPARATE_DISTANCE_PHRASE_VECTORS;
+    ChunkVectorUpdate {
+        regenerate_dense: semantic_boost.is_some() && !separate_phrase,
+        recompute_phrase: semantic_boost.is_some() && separate_phrase,
+        regenerate_sparse: fulltext_boost.is_some(),
+    }
+}
+
+pub async fn get_point_content_hash_query(
+    point_id: uuid::Uuid,
+    dataset_config: &DatasetConfiguration,
+) -> Result<Option<String>, ServiceError> {
+    let qdrant_collection = get_qdrant_collection_from_dataset_config(dataset_config);
+
+    let qdrant_client = get_qdrant_connection(
+        Some(get_env!("QDRANT_URL", "QDRANT_URL should be set")),
+        Some(get_env!("QDRANT_API_KEY", "QDRANT_API_KEY should be set")),
+    )
+    .await?;
+
+    let points = qdrant_client
+        .get_points(
+            GetPointsBuilder::new(qdrant_collection, vec![point_id.to_string().into()])
+                .with_payload(WithPayloadSelector {
+                    selector_options: Some(SelectorOptions::Include(PayloadIncludeSelector {
+                        fields: vec![CONTENT_HASH_PAYLOAD_KEY.to_string()],
+                    })),
+                })
+                .with_vectors(false)
+                .build(),
+        )
+        .await
+        .map_err(|_err| ServiceError::BadRequest("Failed to get point from qdrant".into()))?
+        .result;
+
+    Ok(points.first().and_then(|point| {
+        point
+            .payload
+            .get(CONTENT_HASH_PAYLOAD_KEY)
+            .and_then(|hash| hash.as_str())
+            .cloned()
+    }))
+}
+
+/// Replaces the semantic_boost phrase vector and distance_factor of a point in a dataset with
+/// `SEPARATE_DISTANCE_PHRASE_VECTORS` set, keeping its content vector.
+pub async fn update_distance_phrase_vector_query(
+    point_id: uuid::Uuid,
+    phrase_vector: Vec<f32>,
+    distance_factor: f32,
+    dataset_config: &DatasetConfiguration,
+) -> Result<(), ServiceError> {
+    let qdrant_collection = get_qdrant_collection_from_dataset_config(dataset_config);
+
+    let qdrant_client = get_qdrant_connection(
+        Some(get_env!("QDRANT_URL", "QDRANT_URL should be set")),
+        Some(get_env!("QDRANT_API_KEY", "QDRANT_API_KEY should be set")),
+    )
+    .await?;
+
+    let point_id: PointId = point_id.to_string().into();
+
+    qdrant_client
+        .update_vectors(UpdatePointVectorsBuilder::new(
+            qdrant_collection.clone(),
+            vec![PointVectors {
+                id: Some(point_id.clone()),
+                vectors: Some(
+                    HashMap::from([(
+                        get_distance_phrase_vector_name(phrase_vector.len()),
+                        Vector::from(phrase_vector),
+                    )])
+                    .into(),
+                ),
+            }],
+        ))
+        .await
+        .map_err(|_err| {
+            ServiceError::BadRequest("Failed updating distance phrase vector in qdrant".into())
+        })?;
+
+    let mut payload = Payload::new();
+    payload.insert(DISTANCE_FACTOR_PAYLOAD_KEY, distance_factor as f64);
+    qdrant_client
+        .set_payload(
+            SetPayloadPointsBuilder::new(qdrant_collection, payload)
+                .points_selector(vec![point_id]),
+        )
+        .await
+        .map_err(|_err| {
+            ServiceError::BadRequest("Failed updating distance factor in qdrant".into())
+        })?;
+
+    Ok(())
+}
+
+/// Replaces the `EMBEDDING_VECTOR_FIELDS` vectors of a point with `field_vectors`, removing the
+/// vectors of fields the chunk no longer has.
+pub async fn update_vector_field_vectors_query(
+    point_id: uuid::Uuid,
+    field_vectors: Vec<(usize, Vec<f32>)>,
+    dataset_config: &DatasetConfiguration,
+) -> Result<(), ServiceError> {
+    let qdrant_collection = get_qdrant_collection_from_dataset_config(dataset_config);
+
+    let qdrant_client = get_qdrant_connection(
+        Some(get_env!("QDRANT_URL", "QDRANT_URL should be set")),
+        Some(get_env!("QDRANT_API_KEY", "QDRANT_API_KEY should be set")),
+    )
+    .await?;
+
+    let point_id: PointId = point_id.to_string().into();
+    let removed_fields: Vec<String> = (0..dataset_config.EMBEDDING_VECTOR_FIELDS.len())
+        .filter(|field_index| !field_vectors.iter().any(|(index, _)| index == field_index))
+        .map(|field_index| get_vector_field_vector_name(dataset_config.EMBEDDING_SIZE, field_index))
+        .collect();
+
+    if !field_vectors.is_empty() {
+        qdrant_client
+            .update_vectors(UpdatePointVectorsBuilder::new(
+                qdrant_collection.clone(),
+                vec![PointVectors {
+                    id: Some(point_id.clone()),
+                    vectors: Some(
+                        field_vectors
+                            .into_iter()
+                            .map(|(field_index, vector)| {
+                                (
+                                    get_vector_field_vector_name(vector.len(), field_index),
+                                    Vector::from(vector),
+                                )
+                            })
+                            .collect::<HashMap<String, Vector>>()
+                            .into(),
+                    ),
+                }],
+            ))
+            .await
+            .map_err(|_err| {
+                ServiceError::BadRequest("Failed updating field vectors in qdrant".into())
+            })?;
+    }
+
+    if !removed_fields.is_empty() {
+        qdrant_client
+            .delete_vectors(
+                DeletePointVectorsBuilder::new(qdrant_collection)
+                    .points_selector(vec![point_id])
+                    .vectors(VectorsSelector {
+                        names: removed_fields,
+                    }),
+            )
+            .await
+            .map_err(|_err| {
+                ServiceError::BadRequest("Failed removing field vectors in qdrant".into())
+            })?;
+    }
+
+    Ok(())
+}
+
+fn with_vector_state_payload(payload: QdrantPayload, current_point: &RetrievedPoint) -> Payload {
+    let mut payload: Payload = payload.into();
+    for key in VECTOR_STATE_PAYLOAD_KEYS {
+        if let Some(value) = current_point.payload.get(key) {
+            payload.insert(key, value.clone());
+        }
+    }
+
+    payload
+}
+
 /// Create Qdrant collection and indexes needed
 
 pub async fn create_new_qdrant_collection_query(
@@ -156,18 +504,35 @@ pub async fn create_new_qdrant_collection_query(
                     None
                 };
 
+                let vector_params = VectorParams {
+                    size,
+                    distance: distance.into(),
+                    quantization_config,
+                    on_disk,
+                    ..Default::default()
+                };
                 let vectors_hash_map = HashMap::from_iter(
-                    vec![(
-                        format!("{}_vectors", size).to_string(),
-                        VectorParams {
-                            size,
-                            distance: distance.into(),
-                            quantization_config,
-                            on_disk,
-                            ..Default::default()
-                        },
-                    )]
-                    .into_iter(),
+                    vec![
+                        (
+                            format!("{}_vectors", size).to_string(),
+                            vector_params.clone(),
+                        ),
+                        (
+                            get_dense_slot_vector_name(size as usize, DenseVectorSlot::Secondary),
+                            vector_params.clone(),
+                        ),
+                        (
+                            get_distance_phrase_vector_name(size as usize),
+                            vector_params.clone(),
+                        ),
+                    ]
+                    .into_iter()
+                    .chain((0..MAX_EMBEDDING_VECTOR_FIELDS).map(|field_index| {
+                        (
+                            get_vector_field_vector_name(size as usize, field_index),
+                            vector_params.clone(),
+                        )
+                    })),
                 );
 
                 qdrant_client
@@ -385,6 +750,15 @@ pub async fn create_new_qdrant_collection_query(
             ))
             .await
             .map_err(|_| ServiceError::BadRequest("Failed to create index".into()))?;
+
+        qdrant_client
+            .create_field_index(CreateFieldIndexCollectionBuilder::new(
+                collection_name.clone(),
+                SPARSE_ENCODED_PAYLOAD_KEY,
+                FieldType::Bool,
+            ))
+            .await
+            .map_err(|_| ServiceError::BadRequest("Failed to create index".into()))?;
     }
 
     Ok(())
@@ -446,22 +820,12 @@ pub async fn create_new_qdrant_point_query(
     let payload = QdrantPayload::new(chunk_metadata, group_ids, None, chunk_tags);
     let qdrant_collection = get_qdrant_collection_from_dataset_config(&dataset_config);
 
-    let vector_name = match embedding_vector.len() {
-        384 => "384_vectors",
-        512 => "512_vectors",
-        768 => "768_vectors",
-        1024 => "1024_vectors",
-        3072 => "3072_vectors",
-        1536 => "1536_vectors",
-        _ => {
-            return Err(ServiceError::BadRequest(
-                "Invalid embedding vector size".into(),
-            ))
-        }
-    };
+    let vector_name =
+        get_dense_vector_name(embedding_vector.len(), dataset_config.EMBEDDING_VECTOR_SLOT)
+            .ok_or_else(|| ServiceError::BadRequest("Invalid embedding vector size".into()))?;
 
     let vector_payload = HashMap::from([
-        (vector_name.to_string(), Vector::from(embedding_vector)),
+        (vector_name, Vector::from(embedding_vector)),
         ("sparse_vectors".to_string(), Vector::from(splade_vector)),
     ]);
 
@@ -491,8 +855,9 @@ pub async fn update_qdrant_point_query(
     updated_vector: Option<Vec<f32>>,
     group_ids: Option<Vec<uuid::Uuid>>,
     dataset_id: uuid::Uuid,
-    splade_vector: Vec<(u32, f32)>,
+    splade_vector: Option<Vec<(u32, f32)>>,
     bm25_vector: Option<Vec<(u32, f32)>>,
+    content_hash: Option<String>,
     dataset_config: DatasetConfiguration,
     web_pool: web::Data<Pool>,
 ) -> Result<(), actix_web::Error> {
@@ -556,28 +921,26 @@ pub async fn update_qdrant_point_query(
         )
     };
 
+    let mut vector_payload = HashMap::new();
+    let replaces_dense_vector = updated_vector.is_some();
     if let Some(updated_vector) = updated_vector {
-        let vector_name = match updated_vector.len() {
-            384 => "384_vectors",
-            512 => "512_vectors",
-            768 => "768_vectors",
-            1024 => "1024_vectors",
-            3072 => "3072_vectors",
-            1536 => "1536_vectors",
-            _ => {
-                return Err(ServiceError::BadRequest("Invalid embedding vector size".into()).into())
-            }
-        };
-        let mut vector_payload = HashMap::from([
-            (vector_name.to_string(), Vector::from(updated_vector)),
-            ("sparse_vectors".to_string(), Vector::from(splade_vector)),
-        ]);
+        let vector_name =
+            get_dense_vector_name(updated_vector.len(), dataset_config.EMBEDDING_VECTOR_SLOT)
+                .ok_or_else(|| ServiceError::BadRequest("Invalid embedding vector size".into()))?;
+        vector_payload.insert(vector_name, Vector::from(updated_vector));
+    }
+    let replaces_vectors = !vector_payload.is_empty() && splade_vector.is_some();
+    if let Some(splade_vector) = splade_vector {
+        vector_payload.insert("sparse_vectors".to_string(), Vector::from(splade_vector));
+    }
+    if let Some(bm25_vector) = bm25_vector {
+        vector_payload.insert("bm25_vectors".to_string(), Vector::from(bm25_vector));
+    }
 
-        if let Some(bm25_vector) = bm25_vector.clone() {
-            vector_payload.insert(
-                "bm25_vectors".to_string(),
-                Vector::from(bm25_vector.clone()),
-            );
+    if replaces_vectors {
+        let mut payload: Payload = payload.into();
+        if let Some(content_hash) = content_hash {
+            payload.insert(CONTENT_HASH_PAYLOAD_KEY, content_hash);
         }
 
         let point = PointStruct::new(
@@ -594,19 +957,45 @@ pub async fn update_qdrant_point_query(
         return Ok(());
     }
 
+    let mut payload = match current_point {
+        Some(current_point) => with_vector_state_payload(payload, current_point),
+        None => payload.into(),
+    };
+    if let Some(content_hash) = content_hash {
+        payload.insert(CONTENT_HASH_PAYLOAD_KEY, content_hash);
+    }
+    // The inactive slot still holds the target model's vector of the old content, so the
+    // backfill has to embed the point again
+    if replaces_dense_vector {
+        payload.insert(DUAL_WRITE_VECTOR_PAYLOAD_KEY, "");
+    }
+
     qdrant_client
         .overwrite_payload(
-            SetPayloadPointsBuilder::new(
-                qdrant_collection,
-                <QdrantPayload as std::convert::Into<Payload>>::into(payload),
-            )
-            .points_selector(qdrant_point_id),
+            SetPayloadPointsBuilder::new(qdrant_collection.clone(), payload)
+                .points_selector(qdrant_point_id.clone()),
         )
         .await
         .map_err(|_err| {
             ServiceError::BadRequest("Failed updating chunk payload in qdrant".into())
         })?;
 
+    // Vectors which were not regenerated are kept as they are on the point
+    if !vector_payload.is_empty() {
+        qdrant_client
+            .update_vectors(UpdatePointVectorsBuilder::new(
+                qdrant_collection,
+                vec![PointVectors {
+                    id: qdrant_point_id.first().cloned(),
+                    vectors: Some(vector_payload.into()),
+                }],
+            ))
+            .await
+            .map_err(|_err| {
+                ServiceError::BadRequest("Failed updating chunk vectors in qdrant".into())
+            })?;
+    }
+
     Ok(())
 }
 
@@ -673,7 +1062,7 @@ pub async fn add_bookmark_to_qdrant_query(
         .overwrite_payload(
             SetPayloadPointsBuilder::new(
                 qdrant_collection,
-                <QdrantPayload as std::convert::Into<Payload>>::into(payload),
+                with_vector_state_payload(payload, current_point),
             )
             .points_selector(qdrant_point_id),
         )
@@ -748,7 +1137,7 @@ pub async fn remove_bookmark_from_qdrant_query(
         .overwrite_payload(
             SetPayloadPointsBuilder::new(
                 qdrant_collection,
-                <QdrantPayload as std::convert::Into<Payload>>::into(payload),
+                with_vector_state_payload(payload, current_point),
             )
             .points_selector(qdrant_point_id),
         )
@@ -810,6 +1199,9 @@ pub struct QdrantSearchQuery {
     pub sort_by: Option<SortByField>,
     pub vector: VectorType,
     pub group_size: Option<u64>,
+    /// Weights of the content vector and the `EMBEDDING_VECTOR_FIELDS` vectors, see
+    /// `get_vector_field_weights`
+    pub vector_field_weights: Option<HashMap<String, f32>>,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -824,6 +1216,8 @@ pub async fn search_over_groups_qdrant_query(
         return Ok((vec![], 0));
     }
 
+    encode_pending_sparse_vectors_for_queries(&queries, &dataset_config).await?;
+
     let group_size = queries
         .iter()
         .map(|query| query.group_size.unwrap_or(1))
@@ -883,7 +1277,7 @@ pub async fn search_over_groups_qdrant_query(
                 query: Some(qdrant_query),
                 score_threshold,
                 with_payload: Some(WithPayloadSelector::from(get_payload)),
-                with_vectors: Some(WithVectorsSelector::from(use_mmr)),
+                with_vectors: Some(get_mmr_vectors_selector(use_mmr, &dataset_config)),
                 timeout: Some(60),
                 filter: Some(query.filter.clone()),
                 params: Some(SearchParams {
@@ -970,7 +1364,7 @@ pub async fn search_over_groups_qdrant_query(
     Ok((point_ids, count?))
 }
 
-fn get_qdrant_vector(query: QdrantSearchQuery) -> (String, VectorInput) {
+fn get_qdrant_vector(query: QdrantSearchQuery, slot: DenseVectorSlot) -> (String, VectorInput) {
     match query.vector {
         VectorType::SpladeSparse(vector) => {
             let indices = vector.iter().map(|(index, _)| *index).collect::<Vec<u32>>();
@@ -989,30 +1383,40 @@ fn get_qdrant_vector(query: QdrantSearchQuery) -> (String, VectorInput) {
             )
         }
         VectorType::Dense(embedding_vector) => {
-            let vector_name = match embedding_vector.len() {
-                384 => "384_vectors",
-                512 => "512_vectors",
-                768 => "768_vectors",
-                1024 => "1024_vectors",
-                3072 => "3072_vectors",
-                1536 => "1536_vectors",
-                _ => "invalid",
-            };
-            (
-                vector_name.to_string(),
-                VectorInput::new_dense(embedding_vector),
-            )
+            let vector_name = get_dense_vector_name(embedding_vector.len(), slot)
+                .unwrap_or("invalid".to_string());
+            (vector_name, VectorInput::new_dense(embedding_vector))
         }
     }
 }
 
+/// Vectors returned with search results for MMR. Only the active dense vector is asked for, the
+/// other dense slot of a migrating dataset holds vectors of a different model.
+fn get_mmr_vectors_selector(
+    use_mmr: bool,
+    dataset_config: &DatasetConfiguration,
+) -> WithVectorsSelector {
+    match get_dense_vector_name(
+        dataset_config.EMBEDDING_SIZE,
+        dataset_config.EMBEDDING_VECTOR_SLOT,
+    ) {
+        Some(name) if use_mmr => WithVectorsSelector {
+            selector_options: Some(with_vectors_selector::SelectorOptions::Include(
+                VectorsSelector { names: vec![name] },
+            )),
+        },
+        _ => WithVectorsSelector::from(use_mmr),
+    }
+}
+
 fn get_prefetch_query(
     query: QdrantSearchQuery,
     dataset_config: DatasetConfiguration,
 ) -> (Vec<PrefetchQuery>, (Option<String>, Query)) {
+    let slot = dataset_config.EMBEDDING_VECTOR_SLOT;
     if let Some(ref rerank_query) = *query.rerank_by {
-        let (rerank_vector_name, rerank_vector) = get_qdrant_vector(rerank_query.clone());
-        let (name, vector) = get_qdrant_vector(query.clone());
+        let (rerank_vector_name, rerank_vector) = get_qdrant_vector(rerank_query.clone(), slot);
+        let (name, vector) = get_qdrant_vector(query.clone(), slot);
         (
             vec![PrefetchQuery {
                 query: Some(Query::new_nearest(vector)),
@@ -1024,7 +1428,7 @@ fn get_prefetch_query(
             (Some(rerank_vector_name), Query::new_nearest(rerank_vector)),
         )
     } else if let Some(ref sort_by) = query.sort_by {
-        let (name, vector) = get_qdrant_vector(query.clone());
+        let (name, vector) = get_qdrant_vector(query.clone(), slot);
         let prefetch_amount = sort_by.prefetch_amount.unwrap_or(1000);
         let prefetch_amount = if prefetch_amount > dataset_config.MAX_LIMIT {
             dataset_config.MAX_LIMIT
@@ -1051,22 +1455,26 @@ fn get_prefetch_query(
             ),
         )
     } else {
-        let (name, vector) = get_qdrant_vector(query.clone());
+        let (name, vector) = get_qdrant_vector(query.clone(), slot);
         (vec![], (Some(name), Query::new_nearest(vector)))
     }
 }
 
+/// Runs `queries` as one batch. Returns the results of all queries deduplicated by point id, the
+/// total count and the results of each query with its own scores, in the order of `queries`.
 pub async fn search_qdrant_query(
     page: u64,
     queries: Vec<QdrantSearchQuery>,
     dataset_config: DatasetConfiguration,
     get_total_pages: bool,
     use_mmr: bool,
-) -> Result<(Vec<SearchResult>, u64, Vec<usize>), ServiceError> {
+) -> Result<(Vec<SearchResult>, u64, Vec<Vec<SearchResult>>), ServiceError> {
     if queries.is_empty() || queries.iter().all(|query| query.limit == 0) {
         return Ok((vec![], 0, vec![]));
     }
 
+    encode_pending_sparse_vectors_for_queries(&queries, &dataset_config).await?;
+
     let get_payload = dataset_config.QDRANT_ONLY;
 
     let qdrant_collection = get_qdrant_collection_from_dataset_config(&dataset_config);
@@ -1081,6 +1489,41 @@ pub async fn search_qdrant_query(
 
     let count_future = count_qdrant_query(count_limit, queries.clone(), dataset_config.clone());
 
+    // Plain dense queries are rescored with the phrase vectors when they are stored separately
+    let distance_phrase_query_vectors: Vec<Option<Vec<f32>>> = queries
+        .iter()
+        .map(|query| match &query.vector {
+            VectorType::Dense(vector)
+                if dataset_config.SEPARATE_DISTANCE_PHRASE_VECTORS
+                    && query.rerank_by.is_none()
+                    && query.sort_by.is_none() =>
+            {
+                Some(vector.clone())
+            }
+            _ => None,
+        })
+        .collect();
+    // and with the field vectors when the dataset has EMBEDDING_VECTOR_FIELDS
+    let vector_field_queries: Vec<Option<(Vec<f32>, (f32, Vec<f32>))>> = queries
+        .iter()
+        .map(|query| match &query.vector {
+            VectorType::Dense(vector)
+                if !dataset_config.EMBEDDING_VECTOR_FIELDS.is_empty()
+                    && query.rerank_by.is_none()
+                    && query.sort_by.is_none() =>
+            {
+                Some((
+                    vector.clone(),
+                    get_vector_field_weights(
+                        &dataset_config.EMBEDDING_VECTOR_FIELDS,
+                        query.vector_field_weights.as_ref(),
+                    ),
+                ))
+            }
+            _ => None,
+        })
+        .collect();
+
     let search_point_req_payloads: Vec<QueryPoints> = queries
         .into_iter()
         .map(|query| {
@@ -1116,7 +1559,7 @@ pub async fn search_qdrant_query(
                 query: Some(qdrant_query),
                 score_threshold,
                 with_payload: Some(WithPayloadSelector::from(get_payload)),
-                with_vectors: Some(WithVectorsSelector::from(use_mmr)),
+                with_vectors: Some(get_mmr_vectors_selector(use_mmr, &dataset_config)),
                 timeout: Some(60),
                 filter: Some(query.filter.clone()),
                 params: Some(SearchParams {
@@ -1141,21 +1584,41 @@ pub async fn search_qdrant_query(
     let (count, search_batch_response) =
         futures::future::join(count_future, search_batch_future).await;
 
-    let search_batch_response = search_batch_response.map_err(|e| {
+    let mut search_batch_response = search_batch_response.map_err(|e| {
         log::error!("Failed to search points on Qdrant {:?}", e);
         ServiceError::BadRequest(format!("Failed to search points on Qdrant {:?}", e))
     })?;
 
-    let batch_lengths = search_batch_response
-        .result
-        .iter()
-        .map(|batch_result| batch_result.result.len())
-        .collect();
+    for (batch_result, query_vector, vector_field_query) in izip!(
+        search_batch_response.result.iter_mut(),
+        distance_phrase_query_vectors,
+        vector_field_queries
+    ) {
+        if let Some(query_vector) = query_vector {
+            rescore_with_distance_phrases(
+                &qdrant_client,
+                &qdrant_collection,
+                query_vector,
+                &mut batch_result.result,
+            )
+            .await?;
+        }
+        if let Some((query_vector, weights)) = vector_field_query {
+            rescore_with_vector_fields(
+                &qdrant_client,
+                &qdrant_collection,
+                query_vector,
+                weights,
+                &mut batch_result.result,
+            )
+            .await?;
+        }
+    }
 
-    let search_results: Vec<SearchResult> = search_batch_response
+    let stage_results: Vec<Vec<SearchResult>> = search_batch_response
         .result
         .iter()
-        .flat_map(|batch_result| {
+        .map(|batch_result| {
             batch_result
                 .result
                 .iter()
@@ -1185,10 +1648,180 @@ pub async fn search_qdrant_query(
                 )
                 .collect::<Vec<SearchResult>>()
         })
+        .collect();
+
+    let search_results: Vec<SearchResult> = stage_results
+        .iter()
+        .flatten()
+        .cloned()
         .unique_by(|point| point.point_id)
         .collect();
 
-    Ok((search_results, count?, batch_lengths))
+    Ok((search_results, count?, stage_results))
+}
+
+/// Adds the similarity to the separately stored phrase vector, times the point's
+/// distance_factor, to the scores of `scored_points` and sorts them by the new score. Only the
+/// points already retrieved by their content vector are rescored.
+async fn rescore_with_distance_phrases(
+    qdrant_client: &Qdrant,
+    qdrant_collection: &str,
+    query_vector: Vec<f32>,
+    scored_points: &mut [ScoredPoint],
+) -> Result<(), ServiceError> {
+    if scored_points.is_empty() {
+        return Ok(());
+    }
+
+    let point_ids: Vec<PointId> = scored_points
+        .iter()
+        .filter_map(|point| point.id.clone())
+        .collect();
+    let vector_name = get_distance_phrase_vector_name(query_vector.len());
+
+    let phrase_points = qdrant_client
+        .query(QueryPoints {
+            collection_name: qdrant_collection.to_string(),
+            query: Some(Query::new_nearest(VectorInput::new_dense(query_vector))),
+            using: Some(vector_name),
+            filter: Some(Filter::must([Condition {
+                condition_one_of: Some(HasId(HasIdCondition {
+                    has_id: point_ids.clone(),
+                })),
+            }])),
+            limit: Some(point_ids.len() as u64),
+            with_payload: Some(WithPayloadSelector {
+                selector_options: Some(SelectorOptions::Include(PayloadIncludeSelector {
+                    fields: vec![DISTANCE_FACTOR_PAYLOAD_KEY.to_string()],
+                })),
+            }),
+            timeout: Some(60),
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| {
+            log::error!("Failed to search distance phrase vectors on Qdrant {:?}", e);
+            ServiceError::BadRequest(format!(
+                "Failed to search distance phrase vectors on Qdrant {:?}",
+                e
+            ))
+        })?;
+
+    let point_key = |id: &PointId| match id.point_id_options.as_ref()? {
+        PointIdOptions::Uuid(id) => Some(id.clone()),
+        PointIdOptions::Num(id) => Some(id.to_string()),
+    };
+
+    let phrase_scores: HashMap<String, (f32, f32)> = phrase_points
+        .result
+        .into_iter()
+        .filter_map(|point| {
+            let distance_factor = point
+                .payload
+                .get(DISTANCE_FACTOR_PAYLOAD_KEY)?
+                .as_double()? as f32;
+            Some((
+                point_key(point.id.as_ref()?)?,
+                (point.score, distance_factor),
+            ))
+        })
+        .collect();
+
+    for point in scored_points.iter_mut() {
+        let distance_phrase = point
+            .id
+            .as_ref()
+            .and_then(point_key)
+            .and_then(|id| phrase_scores.get(&id))
+            .cloned();
+        point.score = score_with_distance_phrase(point.score, distance_phrase);
+    }
+    scored_points.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    Ok(())
+}
+
+/// Combines the scores of `scored_points` with the similarity to their `EMBEDDING_VECTOR_FIELDS`
+/// vectors using `score_with_vector_fields` and sorts them by the new score. Points without a
+/// vector for a field skip it.
+async fn rescore_with_vector_fields(
+    qdrant_client: &Qdrant,
+    qdrant_collection: &str,
+    query_vector: Vec<f32>,
+    (content_weight, field_weights): (f32, Vec<f32>),
+    scored_points: &mut [ScoredPoint],
+) -> Result<(), ServiceError> {
+    if scored_points.is_empty() {
+        return Ok(());
+    }
+
+    let point_ids: Vec<PointId> = scored_points
+        .iter()
+        .filter_map(|point| point.id.clone())
+        .collect();
+
+    let field_points = qdrant_client
+        .query_batch(QueryBatchPoints {
+            collection_name: qdrant_collection.to_string(),
+            query_points: (0..field_weights.len())
+                .map(|field_index| QueryPoints {
+                    collection_name: qdrant_collection.to_string(),
+                    query: Some(Query::new_nearest(VectorInput::new_dense(
+                        query_vector.clone(),
+                    ))),
+                    using: Some(get_vector_field_vector_name(
+                        query_vector.len(),
+                        field_index,
+                    )),
+                    filter: Some(Filter::must([Condition {
+                        condition_one_of: Some(HasId(HasIdCondition {
+                            has_id: point_ids.clone(),
+                        })),
+                    }])),
+                    limit: Some(point_ids.len() as u64),
+                    with_payload: Some(WithPayloadSelector::from(false)),
+                    timeout: Some(60),
+                    ..Default::default()
+                })
+                .collect(),
+            timeout: Some(60),
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| {
+            log::error!("Failed to search field vectors on Qdrant {:?}", e);
+            ServiceError::BadRequest(format!("Failed to search field vectors on Qdrant {:?}", e))
+        })?;
+
+    let point_key = |id: &PointId| match id.point_id_options.as_ref()? {
+        PointIdOptions::Uuid(id) => Some(id.clone()),
+        PointIdOptions::Num(id) => Some(id.to_string()),
+    };
+
+    let field_scores: Vec<HashMap<String, f32>> = field_points
+        .result
+        .into_iter()
+        .map(|batch_result| {
+            batch_result
+                .result
+                .into_iter()
+                .filter_map(|point| Some((point_key(point.id.as_ref()?)?, point.score)))
+                .collect()
+        })
+        .collect();
+
+    for point in scored_points.iter_mut() {
+        let id = point.id.as_ref().and_then(point_key);
+        let point_field_scores = field_weights
+            .iter()
+            .zip(field_scores.iter())
+            .map(|(weight, scores)| (*weight, id.as_ref().and_then(|id| scores.get(id)).copied()))
+            .collect::<Vec<(f32, Option<f32>)>>();
+        point.score = score_with_vector_fields(point.score, content_weight, &point_field_scores);
+    }
+    scored_points.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    Ok(())
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -1234,21 +1867,13 @@ pub async fn recommend_qdrant_query(
     let recommend_type = recommend_type.unwrap_or(RecommendType::Semantic);
 
     let vector_name = match recommend_type {
-        RecommendType::Semantic => match dataset_config.EMBEDDING_SIZE {
-            384 => "384_vectors",
-            512 => "512_vectors",
-            768 => "768_vectors",
-            1024 => "1024_vectors",
-            3072 => "3072_vectors",
-            1536 => "1536_vectors",
-            _ => {
-                return Err(ServiceError::BadRequest(
-                    "Invalid embedding vector size".to_string(),
-                ))
-            }
-        },
-        RecommendType::FullText => "sparse_vectors",
-        RecommendType::BM25 => "bm25_vectors",
+        RecommendType::Semantic => get_dense_vector_name(
+            dataset_config.EMBEDDING_SIZE,
+            dataset_config.EMBEDDING_VECTOR_SLOT,
+        )
+        .ok_or_else(|| ServiceError::BadRequest("Invalid embedding vector size".to_string()))?,
+        RecommendType::FullText => "sparse_vectors".to_string(),
+        RecommendType::BM25 => "bm25_vectors".to_string(),
     };
 
     let recommend_points = RecommendPoints {
@@ -1266,7 +1891,7 @@ pub async fn recommend_qdrant_query(
         }),
         score_threshold: None,
         offset: None,
-        using: Some(vector_name.to_string()),
+        using: Some(vector_name),
         lookup_from: None,
         read_consistency: None,
         positive_vectors: vec![],
@@ -1345,21 +1970,13 @@ pub async fn recommend_qdrant_groups_query(
     let recommend_type = recommend_type.unwrap_or(RecommendType::Semantic);
 
     let vector_name = match recommend_type {
-        RecommendType::Semantic => match dataset_config.EMBEDDING_SIZE {
-            384 => "384_vectors",
-            512 => "512_vectors",
-            768 => "768_vectors",
-            1024 => "1024_vectors",
-            3072 => "3072_vectors",
-            1536 => "1536_vectors",
-            _ => {
-                return Err(ServiceError::BadRequest(
-                    "Invalid embedding vector size".to_string(),
-                ))
-            }
-        },
-        RecommendType::FullText => "sparse_vectors",
-        RecommendType::BM25 => "bm25_vectors",
+        RecommendType::Semantic => get_dense_vector_name(
+            dataset_config.EMBEDDING_SIZE,
+            dataset_config.EMBEDDING_VECTOR_SLOT,
+        )
+        .ok_or_else(|| ServiceError::BadRequest("Invalid embedding vector size".to_string()))?,
+        RecommendType::FullText => "sparse_vectors".to_string(),
+        RecommendType::BM25 => "bm25_vectors".to_string(),
     };
 
     let recommend_points = RecommendPointGroups {
@@ -1376,7 +1993,7 @@ pub async fn recommend_qdrant_groups_query(
             ..Default::default()
         }),
         score_threshold: None,
-        using: Some(vector_name.to_string()),
+        using: Some(vector_name),
         lookup_from: None,
         read_consistency: None,
         positive_vectors: vec![],
@@ -1581,6 +2198,55 @@ pub async fn scroll_qdrant_collection_ids_custom_url(
     Ok((points, offset))
 }
 
+/// Pages through the points of one dataset, optionally with their stored vectors, so they can be copied without embedding the chunks again.
+pub async fn scroll_dataset_points_query(
+    dataset_id: uuid::Uuid,
+    dataset_config: &DatasetConfiguration,
+    offset_id: Option<uuid::Uuid>,
+    limit: u32,
+    with_vectors: bool,
+) -> Result<(Vec<RetrievedPoint>, Option<uuid::Uuid>), ServiceError> {
+    let qdrant_collection = get_qdrant_collection_from_dataset_config(dataset_config);
+
+    let qdrant_client = get_qdrant_connection(
+        Some(get_env!("QDRANT_URL", "QDRANT_URL should be set")),
+        Some(get_env!("QDRANT_API_KEY", "QDRANT_API_KEY should be set")),
+    )
+    .await?;
+
+    let mut filter = Filter::default();
+    filter
+        .must
+        .push(Condition::matches("dataset_id", dataset_id.to_string()));
+
+    let mut scroll_points_params = ScrollPointsBuilder::new(qdrant_collection)
+        .filter(filter)
+        .limit(limit)
+        .with_payload(true)
+        .with_vectors(with_vectors);
+
+    if let Some(offset_id) = offset_id {
+        scroll_points_params = scroll_points_params.offset(offset_id.to_string());
+    };
+
+    let scroll_response = qdrant_client
+        .scroll(scroll_points_params)
+        .await
+        .map_err(|err| {
+            log::info!("Failed to scroll points from qdrant {:?}", err);
+            ServiceError::BadRequest("Failed to scroll points from qdrant".to_string())
+        })?;
+
+    let offset = scroll_response
+        .next_page_offset
+        .and_then(|id| match id.point_id_options {
+            Some(PointIdOptions::Uuid(id)) => uuid::Uuid::parse_str(&id).ok(),
+            _ => None,
+        });
+
+    Ok((scroll_response.result, offset))
+}
+
 pub async fn scroll_qdrant_collection_ids(
     collection_name: String,
     offset_id: Option<String>,
@@ -1699,24 +2365,18 @@ pub async fn count_qdrant_group_query(
                 })
             }
             VectorType::Dense(embedding_vector) => {
-                let vector_name = match embedding_vector.len() {
-                    384 => "384_vectors",
-                    512 => "512_vectors",
-                    768 => "768_vectors",
-                    1024 => "1024_vectors",
-                    3072 => "3072_vectors",
-                    1536 => "1536_vectors",
-                    _ => {
-                        return Err(ServiceError::BadRequest(
-                            "Invalid embedding vector size".to_string(),
-                        ))
-                    }
-                };
+                let vector_name = get_dense_vector_name(
+                    embedding_vector.len(),
+                    dataset_config.EMBEDDING_VECTOR_SLOT,
+                )
+                .ok_or_else(|| {
+                    ServiceError::BadRequest("Invalid embedding vector size".to_string())
+                })?;
 
                 Ok(SearchPointGroups {
                     collection_name: qdrant_collection.to_string(),
                     vector: embedding_vector,
-                    vector_name: Some(vector_name.to_string()),
+                    vector_name: Some(vector_name),
                     limit: limit as u32,
                     score_threshold: query.score_threshold,
                     with_payload: Some(WithPayloadSelector::from(false)),
@@ -1824,24 +2484,18 @@ pub async fn count_qdrant_query(
                 })
             }
             VectorType::Dense(embedding_vector) => {
-                let vector_name = match embedding_vector.len() {
-                    384 => "384_vectors",
-                    512 => "512_vectors",
-                    768 => "768_vectors",
-                    1024 => "1024_vectors",
-                    3072 => "3072_vectors",
-                    1536 => "1536_vectors",
-                    _ => {
-                        return Err(ServiceError::BadRequest(
-                            "Invalid embedding vector size".to_string(),
-                        ))
-                    }
-                };
+                let vector_name = get_dense_vector_name(
+                    embedding_vector.len(),
+                    dataset_config.EMBEDDING_VECTOR_SLOT,
+                )
+                .ok_or_else(|| {
+                    ServiceError::BadRequest("Invalid embedding vector size".to_string())
+                })?;
 
                 Ok(SearchPoints {
                     collection_name: qdrant_collection.to_string(),
                     vector: embedding_vector,
-                    vector_name: Some(vector_name.to_string()),
+                    vector_name: Some(vector_name),
                     limit,
                     score_threshold: query.score_threshold,
                     with_payload: Some(WithPayloadSelector::from(false)),
@@ -2031,3 +2685,682 @@ pub async fn scroll_dataset_points(
             }),
     ))
 }
+
+const SPARSE_ENCODING_BATCH_SIZE: u32 = 100;
+
+lazy_static::lazy_static! {
+    static ref SPARSE_ENCODING_LOCKS: std::sync::Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>> =
+        std::sync::Mutex::new(HashMap::new());
+}
+
+/// Calls `encode_batch` until it encodes nothing and reports the running total to `on_progress`
+/// after every batch. Holds a lock per `lock_key`, so concurrent searches over the same
+/// collection wait for one encoder instead of encoding the same points twice.
+pub async fn run_sparse_encoding_batches<F, Fut>(
+    lock_key: &str,
+    mut encode_batch: F,
+    mut on_progress: impl FnMut(usize),
+) -> Result<usize, ServiceError>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<usize, ServiceError>>,
+{
+    let lock = SPARSE_ENCODING_LOCKS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .entry(lock_key.to_string())
+        .or_default()
+        .clone();
+    let _guard = lock.lock().await;
+
+    let mut encoded = 0;
+    loop {
+        let batch_encoded = encode_batch().await?;
+        if batch_encoded == 0 {
+            return Ok(encoded);
+        }
+
+        encoded += batch_encoded;
+        on_progress(encoded);
+    }
+}
+
+async fn encode_sparse_vector_batch(
+    qdrant_client: &Qdrant,
+    qdrant_collection: &str,
+    pending_filter: &Filter,
+    context: &EmbedContext,
+    reqwest_client: &reqwest::Client,
+) -> Result<usize, ServiceError> {
+    let pending_points = qdrant_client
+        .scroll(
+            ScrollPointsBuilder::new(qdrant_collection)
+                .filter(pending_filter.clone())
+                .limit(SPARSE_ENCODING_BATCH_SIZE)
+                .with_payload(WithPayloadSelector {
+                    selector_options: Some(SelectorOptions::Include(PayloadIncludeSelector {
+                        fields: vec!["content".to_string()],
+                    })),
+                })
+                .with_vectors(false),
+        )
+        .await
+        .map_err(|err| {
+            log::error!(
+                "Failed to scroll pending sparse points from qdrant {:?}",
+                err
+            );
+            ServiceError::BadRequest("Failed to scroll points from qdrant".to_string())
+        })?
+        .result;
+
+    let (point_ids, content_and_boosts): (Vec<PointId>, Vec<(String, Option<FullTextBoost>)>) =
+        pending_points
+            .into_iter()
+            .filter_map(|point| {
+                let content = point
+                    .payload
+                    .get("content")
+                    .and_then(|content| content.as_str())
+                    .cloned()
+                    .unwrap_or_default();
+
+                Some((point.id?, (content, None)))
+            })
+            .unzip();
+
+    if point_ids.is_empty() {
+        return Ok(0);
+    }
+
+    let sparse_vectors =
+        get_sparse_vectors(content_and_boosts, "doc", context, reqwest_client.clone()).await?;
+
+    let point_vectors: Vec<PointVectors> = point_ids
+        .iter()
+        .cloned()
+        .zip(sparse_vectors)
+        .map(|(point_id, sparse_vector)| PointVectors {
+            id: Some(point_id),
+            vectors: Some(
+                HashMap::from([("sparse_vectors".to_string(), Vector::from(sparse_vector))]).into(),
+            ),
+        })
+        .collect();
+
+    qdrant_client
+        .update_vectors(UpdatePointVectorsBuilder::new(
+            qdrant_collection,
+            point_vectors,
+        ))
+        .await
+        .map_err(|err| {
+            log::error!("Failed to store lazily encoded sparse vectors {:?}", err);
+            ServiceError::BadRequest("Failed to store sparse vectors in qdrant".to_string())
+        })?;
+
+    let mut encoded_payload = Payload::new();
+    encoded_payload.insert(SPARSE_ENCODED_PAYLOAD_KEY, true);
+    qdrant_client
+        .set_payload(
+            SetPayloadPointsBuilder::new(qdrant_collection, encoded_payload)
+                .points_selector(point_ids.clone()),
+        )
+        .await
+        .map_err(|err| {
+            log::error!("Failed to mark sparse vectors as encoded {:?}", err);
+            ServiceError::BadRequest("Failed updating chunk payload in qdrant".to_string())
+        })?;
+
+    Ok(point_ids.len())
+}
+
+/// Computes and stores the sparse vectors of the points matching `filter` which were ingested
+/// with `LAZY_SPARSE_ENCODING`. Returns how many were encoded.
+pub async fn encode_pending_sparse_vectors(
+    filter: Filter,
+    dataset_config: &DatasetConfiguration,
+    on_progress: impl FnMut(usize),
+) -> Result<usize, ServiceError> {
+    let qdrant_collection = get_qdrant_collection_from_dataset_config(dataset_config);
+
+    let qdrant_client = get_qdrant_connection(
+        Some(get_env!("QDRANT_URL", "QDRANT_URL should be set")),
+        Some(get_env!("QDRANT_API_KEY", "QDRANT_API_KEY should be set")),
+    )
+    .await?;
+    let reqwest_client = reqwest::Client::new();
+    let context = EmbedContext::from_dataset_config(dataset_config);
+
+    let mut pending_filter = filter;
+    pending_filter
+        .must
+        .push(Condition::matches(SPARSE_ENCODED_PAYLOAD_KEY, false));
+
+    run_sparse_encoding_batches(
+        &qdrant_collection,
+        || {
+            encode_sparse_vector_batch(
+                &qdrant_client,
+                &qdrant_collection,
+                &pending_filter,
+                &context,
+                &reqwest_client,
+            )
+        },
+        on_progress,
+    )
+    .await
+}
+
+/// Encodes the pending sparse vectors of every point a fulltext query can reach before it runs,
+/// so chunks ingested with `LAZY_SPARSE_ENCODING` show up in its results.
+async fn encode_pending_sparse_vectors_for_queries(
+    queries: &[QdrantSearchQuery],
+    dataset_config: &DatasetConfiguration,
+) -> Result<(), ServiceError> {
+    if !dataset_config.LAZY_SPARSE_ENCODING {
+        return Ok(());
+    }
+
+    let fulltext_filters = queries
+        .iter()
+        .filter(|query| {
+            matches!(query.vector, VectorType::SpladeSparse(_))
+                || matches!(
+                    query.rerank_by.as_ref(),
+                    Some(QdrantSearchQuery {
+                        vector: VectorType::SpladeSparse(_),
+                        ..
+                    })
+                )
+        })
+        .map(|query| query.filter.clone())
+        .dedup();
+
+    for filter in fulltext_filters {
+        let encoded = encode_pending_sparse_vectors(filter, dataset_config, |_| {}).await?;
+        if encoded > 0 {
+            log::info!(
+                "Encoded {} lazily ingested sparse vectors before searching",
+                encoded
+            );
+        }
+    }
+
+    Ok(())
+}
+
+const DUAL_WRITE_BACKFILL_BATCH_SIZE: u32 = 100;
+
+/// Filter of a dataset's points which don't hold the vector of its `EMBEDDING_DUAL_WRITE_TARGET`
+/// yet.
+fn get_pending_dual_write_filter(
+    dataset_id: uuid::Uuid,
+    dataset_config: &DatasetConfiguration,
+) -> Result<Filter, ServiceError> {
+    let marker = get_dual_write_marker(dataset_config).ok_or(ServiceError::BadRequest(
+        "The dataset has no EMBEDDING_DUAL_WRITE_TARGET to write vectors for".to_string(),
+    ))?;
+
+    let mut filter = Filter::default();
+    filter
+        .must
+        .push(Condition::matches("dataset_id", dataset_id.to_string()));
+    filter
+        .must_not
+        .push(Condition::matches(DUAL_WRITE_VECTOR_PAYLOAD_KEY, marker));
+
+    Ok(filter)
+}
+
+async fn encode_dual_write_vector_batch(
+    qdrant_client: &Qdrant,
+    qdrant_collection: &str,
+    pending_filter: &Filter,
+    dataset_config: &DatasetConfiguration,
+    reqwest_client: &reqwest::Client,
+) -> Result<usize, ServiceError> {
+    let (target_config, marker) = match (
+        get_dual_write_config(dataset_config),
+        get_dual_write_marker(dataset_config),
+    ) {
+        (Some(target_config), Some(marker)) => (target_config, marker),
+        _ => return Ok(0),
+    };
+
+    let pending_points = qdrant_client
+        .scroll(
+            ScrollPointsBuilder::new(qdrant_collection)
+                .filter(pending_filter.clone())
+                .limit(DUAL_WRITE_BACKFILL_BATCH_SIZE)
+                .with_payload(WithPayloadSelector {
+                    selector_options: Some(SelectorOptions::Include(PayloadIncludeSelector {
+                        fields: vec![
+                            "content".to_string(),
+                            "link".to_string(),
+                            "tag_set".to_string(),
+                            "metadata".to_string(),
+                        ],
+                    })),
+                })
+                .with_vectors(false),
+        )
+        .await
+        .map_err(|err| {
+            log::error!(
+                "Failed to scroll pending dual-write points from qdrant {:?}",
+                err
+            );
+            ServiceError::BadRequest("Failed to scroll points from qdrant".to_string())
+        })?
+        .result;
+
+    // The semantic_boost and semantic_content of a chunk are not kept on its point, so the
+    // backfill embeds the templated content only
+    let (point_ids, embedding_contents): (Vec<PointId>, Vec<String>) = pending_points
+        .into_iter()
+        .filter_map(|point| {
+            let payload_str = |key: &str| {
+                point
+                    .payload
+                    .get(key)
+                    .and_then(|value| value.as_str())
+                    .cloned()
+            };
+            let tag_set = point.payload.get("tag_set").and_then(|tag_set| {
+                tag_set.as_list().map(|tags| {
+                    tags.iter()
+                        .filter_map(|tag| tag.as_str().cloned())
+                        .collect::<Vec<String>>()
+                })
+            });
+            let metadata: Option<serde_json::Value> = point
+                .payload
+                .get("metadata")
+                .cloned()
+                .map(|value| value.into());
+            let embedding_content = get_templated_embedding_content(
+                payload_str("content").unwrap_or_default(),
+                payload_str("link").as_ref(),
+                tag_set,
+                metadata.as_ref(),
+                dataset_config,
+            );
+
+            Some((point.id?, embedding_content))
+        })
+        .unzip();
+
+    if point_ids.is_empty() {
+        return Ok(0);
+    }
+
+    // Metadata only chunks have no dense vector to replace, they are only marked as done
+    let to_embed: Vec<(PointId, String)> = izip!(point_ids.iter(), embedding_contents)
+        .filter(|(_, content)| !content.trim().is_empty())
+        .map(|(point_id, content)| (point_id.clone(), content))
+        .collect();
+    if !to_embed.is_empty() {
+        let requested_vectors = to_embed.len();
+        let vectors = check_created_vector_count(
+            get_dense_vectors(
+                to_embed
+                    .iter()
+                    .map(|(_, content)| (content.clone(), None))
+                    .collect(),
+                "doc",
+                &EmbedContext::from_dataset_config(&target_config),
+                reqwest_client.clone(),
+            )
+            .await?,
+            requested_vectors,
+            "dense",
+        )?;
+
+        let point_vectors = izip!(to_embed, vectors)
+            .map(|((point_id, _), vector)| {
+                let vector_name = get_dense_vector_name(
+                    vector.len(),
+                    dataset_config.EMBEDDING_VECTOR_SLOT.other(),
+                )
+                .ok_or_else(|| ServiceError::BadRequest("Invalid embedding vector size".into()))?;
+                Ok(PointVectors {
+                    id: Some(point_id),
+                    vectors: Some(HashMap::from([(vector_name, Vector::from(vector))]).into()),
+                })
+            })
+            .collect::<Result<Vec<PointVectors>, ServiceError>>()?;
+
+        qdrant_client
+            .update_vectors(UpdatePointVectorsBuilder::new(
+                qdrant_collection,
+                point_vectors,
+            ))
+            .await
+            .map_err(|err| {
+                log::error!("Failed to store dual-write vectors {:?}", err);
+                ServiceError::BadRequest("Failed to store dual-write vectors in qdrant".to_string())
+            })?;
+    }
+
+    let mut marker_payload = Payload::new();
+    marker_payload.insert(DUAL_WRITE_VECTOR_PAYLOAD_KEY, marker);
+    qdrant_client
+        .set_payload(
+            SetPayloadPointsBuilder::new(qdrant_collection, marker_payload)
+                .points_selector(point_ids.clone()),
+        )
+        .await
+        .map_err(|err| {
+            log::error!("Failed to mark dual-write vectors as written {:?}", err);
+            ServiceError::BadRequest("Failed updating chunk payload in qdrant".to_string())
+        })?;
+
+    Ok(point_ids.len())
+}
+
+/// Embeds the points of a dataset which were stored before its `EMBEDDING_DUAL_WRITE_TARGET` was
+/// set, or whose content changed since, with the target model. Returns how many were written.
+pub async fn backfill_dual_write_vectors(
+    dataset_id: uuid::Uuid,
+    dataset_config: &DatasetConfiguration,
+    on_progress: impl FnMut(usize),
+) -> Result<usize, ServiceError> {
+    let pending_filter = get_pending_dual_write_filter(dataset_id, dataset_config)?;
+    let qdrant_collection = get_qdrant_collection_from_dataset_config(dataset_config);
+
+    let qdrant_client = get_qdrant_connection(
+        Some(get_env!("QDRANT_URL", "QDRANT_URL should be set")),
+        Some(get_env!("QDRANT_API_KEY", "QDRANT_API_KEY should be set")),
+    )
+    .await?;
+    let reqwest_client = reqwest::Client::new();
+
+    run_sparse_encoding_batches(
+        &format!("{}_dual_write_{}", qdrant_collection, dataset_id),
+        || {
+            encode_dual_write_vector_batch(
+                &qdrant_client,
+                &qdrant_collection,
+                &pending_filter,
+                dataset_config,
+                &reqwest_client,
+            )
+        },
+        on_progress,
+    )
+    .await
+}
+
+/// Counts the points of a dataset the dual-write backfill still has to embed with the target
+/// model.
+pub async fn count_pending_dual_write_points_query(
+    dataset_id: uuid::Uuid,
+    dataset_config: &DatasetConfiguration,
+) -> Result<u64, ServiceError> {
+    let pending_filter = get_pending_dual_write_filter(dataset_id, dataset_config)?;
+    let qdrant_collection = get_qdrant_collection_from_dataset_config(dataset_config);
+
+    let qdrant_client = get_qdrant_connection(
+        Some(get_env!("QDRANT_URL", "QDRANT_URL should be set")),
+        Some(get_env!("QDRANT_API_KEY", "QDRANT_API_KEY should be set")),
+    )
+    .await?;
+
+    qdrant_client
+        .count(
+            CountPointsBuilder::new(qdrant_collection)
+                .filter(pending_filter)
+                .exact(true),
+        )
+        .await
+        .map_err(|err| {
+            log::error!("Failed to count pending dual-write points {:?}", err);
+            ServiceError::BadRequest("Failed to count points in qdrant".to_string())
+        })?
+        .result
+        .map(|result| result.count)
+        .ok_or(ServiceError::BadRequest(
+            "Failed to count points in qdrant".to_string(),
+        ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    pub fn test_lazy_sparse_encoding_runs_once() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_time()
+            .build()
+            .expect("Runtime builds");
+
+        // Stands in for the points of a lazily ingested dataset, each counting how often its
+        // sparse vector was computed
+        let encode_counts: Arc<Vec<AtomicUsize>> =
+            Arc::new((0..250).map(|_| AtomicUsize::new(0)).collect());
+
+        let searches = (0..4).map(|_| {
+            let encode_counts = encode_counts.clone();
+            runtime.spawn(async move {
+                let encode_counts = encode_counts.as_ref();
+                run_sparse_encoding_batches(
+                    "lazy_sparse_test_vectors",
+                    || async move {
+                        let pending: Vec<&AtomicUsize> = encode_counts
+                            .iter()
+                            .filter(|count| count.load(Ordering::SeqCst) == 0)
+                            .take(SPARSE_ENCODING_BATCH_SIZE as usize)
+                            .collect();
+                        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+                        pending.iter().for_each(|count| {
+                            count.fetch_add(1, Ordering::SeqCst);
+                        });
+                        Ok(pending.len())
+                    },
+                    |_| {},
+                )
+                .await
+            })
+        });
+        let searches = searches.collect::<Vec<_>>();
+
+        let encoded_per_search = runtime.block_on(async {
+            let mut encoded_per_search = vec![];
+            for search in searches {
+                encoded_per_search.push(
+                    search
+                        .await
+                        .expect("Search does not panic")
+                        .expect("Encoding succeeds"),
+                );
+            }
+            encoded_per_search
+        });
+
+        assert_eq!(encoded_per_search.iter().sum::<usize>(), 250);
+        assert_eq!(
+            encoded_per_search
+                .iter()
+                .filter(|encoded| **encoded > 0)
+                .count(),
+            1
+        );
+        assert!(encode_counts
+            .iter()
+            .all(|count| count.load(Ordering::SeqCst) == 1));
+    }
+
+    #[test]
+    pub fn test_plan_chunk_vector_update() {
+        let content_hash = get_chunk_content_hash("iphone", "iphone");
+        let semantic_boost = SemanticBoost {
+            phrase: "flagship".to_string(),
+            distance_factor: 0.25,
+        };
+        let fulltext_boost = FullTextBoost {
+            phrase: "iphone".to_string(),
+            boost_factor: 2.0,
+        };
+        let config = DatasetConfiguration::default();
+
+        // The dense and sparse inputs are hashed apart from each other
+        assert_ne!(
+            get_chunk_content_hash("ab", "c"),
+            get_chunk_content_hash("a", "bc")
+        );
+
+        // Changed content and points without a hash get all new vectors
+        for stored_content_hash in [Some(get_chunk_content_hash("ipad", "ipad")), None] {
+            assert_eq!(
+                plan_chunk_vector_update(
+                    stored_content_hash.as_deref(),
+                    &content_hash,
+                    None,
+                    None,
+                    &config
+                ),
+                ChunkVectorUpdate::FULL
+            );
+        }
+
+        // Metadata only updates keep every vector
+        let metadata_only =
+            plan_chunk_vector_update(Some(&content_hash), &content_hash, None, None, &config);
+        assert!(!metadata_only.regenerates_vectors());
+
+        // Boost only updates recreate the vectors the boost applies to
+        let fulltext_only = plan_chunk_vector_update(
+            Some(&content_hash),
+            &content_hash,
+            None,
+            Some(&fulltext_boost),
+            &config,
+        );
+        assert!(fulltext_only.regenerate_sparse && !fulltext_only.regenerate_dense);
+
+        let semantic_only = plan_chunk_vector_update(
+            Some(&content_hash),
+            &content_hash,
+            Some(&semantic_boost),
+            None,
+            &config,
+        );
+        assert!(semantic_only.regenerate_dense && !semantic_only.regenerate_sparse);
+
+        let separate_config = DatasetConfiguration {
+            SEPARATE_DISTANCE_PHRASE_VECTORS: true,
+            ..Default::default()
+        };
+        let phrase_only = plan_chunk_vector_update(
+            Some(&content_hash),
+            &content_hash,
+            Some(&semantic_boost),
+            None,
+            &separate_config,
+        );
+        assert_eq!(
+            phrase_only,
+            ChunkVectorUpdate {
+                regenerate_dense: false,
+                recompute_phrase: true,
+                regenerate_sparse: false,
+            }
+        );
+    }
+
+    #[test]
+    pub fn test_dual_write_vector_slots() {
+        use crate::data::models::EmbeddingDualWriteTarget;
+
+        assert_eq!(
+            get_dense_vector_name(768, DenseVectorSlot::Primary),
+            Some("768_vectors".to_string())
+        );
+        assert_eq!(
+            get_dense_vector_name(768, DenseVectorSlot::Secondary),
+            Some("768_migration_vectors".to_string())
+        );
+        assert_eq!(get_dense_vector_name(3, DenseVectorSlot::Primary), None);
+
+        let vector_names = |vector_payload: &HashMap<String, Vector>| {
+            vector_payload
+                .keys()
+                .cloned()
+                .sorted()
+                .collect::<Vec<String>>()
+        };
+
+        // Without a migration only the active slot is written
+        let dataset_config = DatasetConfiguration {
+            EMBEDDING_SIZE: 384,
+            ..Default::default()
+        };
+        let mut vector_payload = HashMap::new();
+        let mut payload = Payload::new();
+        insert_dense_vectors(
+            &mut vector_payload,
+            &mut payload,
+            vec![0.5; 384],
+            None,
+            &dataset_config,
+        )
+        .expect("Vector has a supported size");
+        assert_eq!(vector_names(&vector_payload), vec!["384_vectors"]);
+        assert!(get_dual_write_marker(&dataset_config).is_none());
+
+        // Ingest while dual-writing fills both slots and marks the point for the backfill
+        let dataset_config = DatasetConfiguration {
+            EMBEDDING_DUAL_WRITE_TARGET: Some(EmbeddingDualWriteTarget {
+                base_url: "http://target-model".to_string(),
+                model_name: "target-model".to_string(),
+            }),
+            ..dataset_config
+        };
+        let mut vector_payload = HashMap::new();
+        let mut payload = Payload::new();
+        insert_dense_vectors(
+            &mut vector_payload,
+            &mut payload,
+            vec![0.5; 384],
+            Some(vec![0.25; 384]),
+            &dataset_config,
+        )
+        .expect("Vectors have a supported size");
+        assert_eq!(
+            vector_names(&vector_payload),
+            vec!["384_migration_vectors", "384_vectors"]
+        );
+        assert_eq!(
+            vector_payload.get("384_migration_vectors"),
+            Some(&Vector::from(vec![0.25; 384]))
+        );
+        let marker = get_dual_write_marker(&dataset_config).expect("Dataset dual-writes");
+        assert_eq!(marker, "secondary:target-model");
+        let mut expected_payload = Payload::new();
+        expected_payload.insert(DUAL_WRITE_VECTOR_PAYLOAD_KEY, marker);
+        assert_eq!(payload, expected_payload);
+
+        // After the cutover the target's slot is the one searched
+        let dataset_config = DatasetConfiguration {
+            EMBEDDING_VECTOR_SLOT: DenseVectorSlot::Secondary,
+            EMBEDDING_DUAL_WRITE_TARGET: None,
+            ..dataset_config
+        };
+        assert_eq!(
+            get_dense_vector_name(384, dataset_config.EMBEDDING_VECTOR_SLOT),
+            Some("384_migration_vectors".to_string())
+        );
+        assert!(insert_dense_vectors(
+            &mut HashMap::new(),
+            &mut Payload::new(),
+            vec![0.5; 3],
+            None,
+            &dataset_config,
+        )
+        .is_err());
+    }
+}
@@ -7,23 +7,29 @@ use super::group_operator::{
     get_group_ids_from_tracking_ids_query, get_groups_from_group_ids_query,
 };
 use super::model_operator::{
-    cross_encoder, get_bm25_embeddings, get_dense_vector, get_sparse_vector,
+    cross_encoder, dedup_rerank_candidates, deterministic_tiebreak, filter_boost_for_embed_type,
+    get_bm25_embeddings_async, get_dense_vector, get_dense_vector_inputs, get_embedding_url,
+    get_rerank_documents, get_rerank_payloads, get_score_decimal_places, get_sparse_vector,
+    get_sparse_vector_inputs, merge_reranked_with_remainder, redact_upstream_payload,
+    redact_upstream_secrets, resolve_embedding_base_url, round_score, score_from_f32,
+    validate_vector_field_weights, EmbedContext,
 };
 use super::qdrant_operator::{
     count_qdrant_query, search_over_groups_qdrant_query, GroupSearchResults, QdrantSearchQuery,
     VectorType,
 };
 use super::typo_operator::correct_query;
+use super::vector_operator::{add_scaled, cosine};
 use crate::data::models::{
     convert_to_date_time, ChunkGroup, ChunkGroupAndFileId, ChunkMetadata,
     ChunkMetadataStringTagSet, ChunkMetadataTypes, ConditionType, ContentChunkMetadata, Dataset,
-    DatasetConfiguration, HasChunkIDCondition, MmrOptions, QdrantChunkMetadata, QdrantSortBy,
-    QueryTypes, ReRankOptions, RedisPool, ScoreChunk, ScoreChunkDTO, SearchMethod,
+    DatasetConfiguration, HasChunkIDCondition, HybridFusion, MmrOptions, QdrantChunkMetadata,
+    QdrantSortBy, QueryTypes, ReRankOptions, RedisPool, ScoreChunk, ScoreChunkDTO, SearchMethod,
     SlimChunkMetadata, SortByField, SortBySearchType, SortOptions, UnifiedId,
 };
 use crate::handlers::chunk_handler::{
     AutocompleteReqPayload, ChunkFilter, CountChunkQueryResponseBody, CountChunksReqPayload,
-    ParsedQuery, ParsedQueryTypes, ScoringOptions, SearchChunkQueryResponseBody,
+    FusionOptions, ParsedQuery, ParsedQueryTypes, ScoringOptions, SearchChunkQueryResponseBody,
     SearchChunksReqPayload,
 };
 use crate::handlers::group_handler::{
@@ -92,6 +98,9 @@ pub struct SearchChunkQueryResult {
     pub search_results: Vec<SearchResult>,
     pub total_chunk_pages: i64,
     pub batch_lengths: Vec<usize>,
+    /// Results of each query with its own scores, e.g. the semantic and fulltext results of a
+    /// hybrid search
+    pub stage_results: Vec<Vec<SearchResult>>,
 }
 
 async fn convert_group_tracking_ids_to_group_ids(
@@ -318,6 +327,7 @@ pub struct RetrievePointQuery {
     rerank_by: Option<SortBySearchType>,
     filter: Option<ChunkFilter>,
     group_size: Option<u64>,
+    vector_field_weights: Option<HashMap<String, f32>>,
 }
 
 impl RetrievePointQuery {
@@ -326,7 +336,7 @@ impl RetrievePointQuery {
         parsed_query: ParsedQueryTypes,
         dataset_id: uuid::Uuid,
         group_id: Option<uuid::Uuid>,
-        config: &DatasetConfiguration,
+        context: &EmbedContext,
         pool: web::Data<Pool>,
     ) -> Result<QdrantSearchQuery, ServiceError> {
         let parsed_query = match parsed_query {
@@ -376,7 +386,7 @@ impl RetrievePointQuery {
                             data.search_type,
                             ParsedQueryTypes::Single(parsed_query),
                             None,
-                            config,
+                            context,
                         )
                         .await?;
                         Some(QdrantSearchQuery {
@@ -387,6 +397,7 @@ impl RetrievePointQuery {
                             sort_by: None,
                             filter: filter.clone(),
                             group_size: None,
+                            vector_field_weights: None,
                         })
                     }
                     ReRankOptions::Semantic => {
@@ -405,7 +416,7 @@ impl RetrievePointQuery {
                             data.search_type,
                             ParsedQueryTypes::Single(parsed_query),
                             None,
-                            config,
+                            context,
                         )
                         .await?;
                         Some(QdrantSearchQuery {
@@ -416,6 +427,7 @@ impl RetrievePointQuery {
                             sort_by: None,
                             filter: filter.clone(),
                             group_size: None,
+                            vector_field_weights: None,
                         })
                     }
                     ReRankOptions::BM25 => {
@@ -434,7 +446,7 @@ impl RetrievePointQuery {
                             data.search_type,
                             ParsedQueryTypes::Single(parsed_query),
                             None,
-                            config,
+                            context,
                         )
                         .await?;
 
@@ -446,6 +458,7 @@ impl RetrievePointQuery {
                             sort_by: None,
                             filter: filter.clone(),
                             group_size: None,
+                            vector_field_weights: None,
                         })
                     }
                     ReRankOptions::CrossEncoder => None,
@@ -465,22 +478,11 @@ impl RetrievePointQuery {
             sort_by: self.sort_by,
             filter: filter.clone(),
             group_size: self.group_size,
+            vector_field_weights: self.vector_field_weights,
         })
     }
 }
 
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
-    let dot_product: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
-    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
-    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
-
-    if norm_a == 0.0 || norm_b == 0.0 {
-        0.0
-    } else {
-        dot_product / (norm_a * norm_b)
-    }
-}
-
 pub fn apply_mmr<T: SearchResultTrait + Clone>(
     mut docs: Vec<T>,
     lambda: f32,
@@ -526,7 +528,7 @@ pub fn apply_mmr<T: SearchResultTrait + Clone>(
                         Some(embedding) => embedding,
                         None => return 0.0,
                     };
-                    cosine_similarity(idx_embedding.as_slice(), sel_idx_embedding.as_slice())
+                    cosine(idx_embedding.as_slice(), sel_idx_embedding.as_slice())
                 })
                 .fold(f32::NEG_INFINITY, |a, b| a.max(b));
 
@@ -563,7 +565,7 @@ pub async fn retrieve_qdrant_points_query(
 
     let use_mmr = mmr_options.is_some_and(|mmr| mmr.use_mmr && mmr.mmr_lambda.unwrap_or(0.5) > 0.0);
 
-    let (point_ids, count, batch_lengths) = search_qdrant_query(
+    let (point_ids, count, stage_results) = search_qdrant_query(
         page,
         qdrant_searches.clone(),
         config.clone(),
@@ -583,7 +585,8 @@ pub async fn retrieve_qdrant_points_query(
     Ok(SearchChunkQueryResult {
         search_results: point_ids,
         total_chunk_pages: pages,
-        batch_lengths,
+        batch_lengths: stage_results.iter().map(|results| results.len()).collect(),
+        stage_results,
     })
 }
 
@@ -1365,7 +1368,9 @@ pub async fn retrieve_chunks_for_groups(
                     Some(ScoreChunkDTO {
                         metadata: vec![chunk],
                         highlights,
-                        score: search_result.score.into(),
+                        rerank_template_incomplete: None,
+                        preferred_highlight: None,
+                        score: score_from_f32(search_result.score),
                     })
                 })
                 .sorted_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal))
@@ -1447,7 +1452,9 @@ pub async fn get_metadata_from_groups(
                     Some(ScoreChunkDTO {
                         metadata: vec![chunk],
                         highlights: None,
-                        score: search_result.score.into(),
+                        rerank_template_incomplete: None,
+                        preferred_highlight: None,
+                        score: score_from_f32(search_result.score),
                     })
                 })
                 .collect_vec();
@@ -1601,7 +1608,9 @@ pub async fn retrieve_chunks_from_point_ids(
             Some(ScoreChunkDTO {
                 metadata: vec![chunk],
                 highlights,
-                score: search_result.score.into(),
+                rerank_template_incomplete: None,
+                preferred_highlight: None,
+                score: score_from_f32(search_result.score),
             })
         })
         .collect();
@@ -1614,6 +1623,7 @@ pub async fn retrieve_chunks_from_point_ids(
         score_chunks,
         corrected_query: None,
         total_chunk_pages: search_chunk_query_results.total_chunk_pages,
+        degraded: None,
     })
 }
 
@@ -1643,7 +1653,7 @@ pub fn rerank_chunks(
     }
 
     if sort_options.recency_bias.is_some_and(|r| r > 0.0) {
-        let recency_weight = sort_options.recency_bias.unwrap_or(0.0);
+        let recency_weight = f64::from(sort_options.recency_bias.unwrap_or(0.0));
         let min_timestamp = reranked_chunks
             .iter()
             .filter_map(|chunk| chunk.metadata[0].metadata().time_stamp)
@@ -1671,16 +1681,16 @@ pub fn rerank_chunks(
                     if let Some(time_stamp) = chunk.metadata[0].metadata().time_stamp {
                         let duration =
                             chrono::Utc::now().signed_duration_since(time_stamp.and_utc());
-                        let normalized_recency_score = (duration.num_seconds() as f32
-                            - min_duration.num_seconds() as f32)
-                            / (max_duration.num_seconds() as f32
-                                - min_duration.num_seconds() as f32);
+                        let normalized_recency_score = (duration.num_seconds() as f64
+                            - min_duration.num_seconds() as f64)
+                            / (max_duration.num_seconds() as f64
+                                - min_duration.num_seconds() as f64);
 
                         let normalized_chunk_score = (chunk.score - min_score.unwrap_or(0.0))
                             / (max_score.unwrap_or(1.0) - min_score.unwrap_or(0.0));
 
-                        chunk.score = (normalized_chunk_score * (1.0 / recency_weight) as f64)
-                            + (recency_weight * normalized_recency_score) as f64
+                        chunk.score = (normalized_chunk_score * (1.0 / recency_weight))
+                            + (recency_weight * normalized_recency_score)
                     }
                     chunk.clone()
                 })
@@ -1734,17 +1744,17 @@ pub fn rerank_chunks(
         reranked_chunks = reranked_chunks
             .iter_mut()
             .map(|chunk| {
-                let mut tag_score = 1.0;
+                let mut tag_score: f64 = 1.0;
                 for (tag, weight) in tag_weights.iter() {
                     if let Some(metadata) = chunk.metadata.get(0) {
                         if let Some(metadata_tags) = metadata.metadata().tag_set {
                             if metadata_tags.contains(&Some(tag.clone())) {
-                                tag_score *= weight;
+                                tag_score *= f64::from(*weight);
                             }
                         }
                     }
                 }
-                chunk.score *= tag_score as f64;
+                chunk.score *= tag_score;
                 chunk.clone()
             })
             .collect::<Vec<ScoreChunkDTO>>();
@@ -1775,7 +1785,7 @@ pub fn rerank_chunks(
                         return chunk.clone();
                     }
                 };
-                chunk.score = search_result.score.into();
+                chunk.score = score_from_f32(search_result.score);
                 chunk.clone()
             })
             .collect::<Vec<ScoreChunkDTO>>();
@@ -1817,7 +1827,7 @@ pub fn rerank_groups(
     }
 
     if sort_options.recency_bias.is_some_and(|r| r > 0.0) {
-        let recency_weight = sort_options.recency_bias.unwrap();
+        let recency_weight = f64::from(sort_options.recency_bias.unwrap());
         let min_timestamp = reranked_groups
             .iter()
             .filter_map(|group| group.metadata[0].metadata[0].metadata().time_stamp)
@@ -1846,16 +1856,16 @@ pub fn rerank_groups(
                     if let Some(time_stamp) = first_chunk.metadata[0].metadata().time_stamp {
                         let duration =
                             chrono::Utc::now().signed_duration_since(time_stamp.and_utc());
-                        let normalized_recency_score = (duration.num_seconds() as f32
-                            - min_duration.num_seconds() as f32)
-                            / (max_duration.num_seconds() as f32
-                                - min_duration.num_seconds() as f32);
+                        let normalized_recency_score = (duration.num_seconds() as f64
+                            - min_duration.num_seconds() as f64)
+                            / (max_duration.num_seconds() as f64
+                                - min_duration.num_seconds() as f64);
 
                         let normalized_chunk_score = (first_chunk.score - min_score.unwrap_or(0.0))
                             / (max_score.unwrap_or(1.0) - min_score.unwrap_or(0.0));
 
-                        first_chunk.score = (normalized_chunk_score * (1.0 / recency_weight) as f64)
-                            + (recency_weight * normalized_recency_score) as f64
+                        first_chunk.score = (normalized_chunk_score * (1.0 / recency_weight))
+                            + (recency_weight * normalized_recency_score)
                     }
                     group.clone()
                 })
@@ -1911,17 +1921,17 @@ pub fn rerank_groups(
             .iter_mut()
             .map(|group| {
                 let first_chunk = group.metadata.get_mut(0).unwrap();
-                let mut tag_score = 1.0;
+                let mut tag_score: f64 = 1.0;
                 for (tag, weight) in tag_weights.iter() {
                     if let Some(metadata) = first_chunk.metadata.get(0) {
                         if let Some(metadata_tags) = metadata.metadata().tag_set {
                             if metadata_tags.contains(&Some(tag.clone())) {
-                                tag_score *= weight;
+                                tag_score *= f64::from(*weight);
                             }
                         }
                     }
                 }
-                first_chunk.score *= tag_score as f64;
+                first_chunk.score *= tag_score;
                 group.clone()
             })
             .collect::<Vec<GroupScoreChunk>>();
@@ -1964,12 +1974,150 @@ pub fn rerank_groups(
     reranked_groups
 }
 
+/// Deadline for a search with a `latency_budget_ms`. Every stage which calls out to a model gets
+/// whatever is left of the budget when it starts.
+#[derive(Debug, Clone, Copy)]
+pub struct LatencyBudget {
+    deadline: std::time::Instant,
+}
+
+impl LatencyBudget {
+    pub fn from_millis(budget_ms: Option<u64>) -> Option<Self> {
+        budget_ms.map(|budget_ms| LatencyBudget {
+            deadline: std::time::Instant::now() + std::time::Duration::from_millis(budget_ms),
+        })
+    }
+
+    pub fn remaining(&self) -> std::time::Duration {
+        self.deadline
+            .saturating_duration_since(std::time::Instant::now())
+    }
+
+    pub fn is_spent(&self) -> bool {
+        self.remaining().is_zero()
+    }
+}
+
+/// Runs a required stage such as embedding the query, failing with a timeout once the budget is
+/// spent instead of waiting for a slow model.
+pub async fn within_latency_budget<T>(
+    budget: Option<LatencyBudget>,
+    stage: &str,
+    future: impl std::future::Future<Output = Result<T, ServiceError>>,
+) -> Result<T, ServiceError> {
+    let budget = match budget {
+        Some(budget) => budget,
+        None => return future.await,
+    };
+
+    if budget.is_spent() {
+        return Err(ServiceError::RequestTimeout(format!(
+            "The latency budget was spent before {}",
+            stage
+        )));
+    }
+
+    tokio::time::timeout(budget.remaining(), future)
+        .await
+        .map_err(|_| {
+            ServiceError::RequestTimeout(format!("{} exceeded the latency budget", stage))
+        })?
+}
+
+/// Reranks `score_chunks` with `rerank` if it finishes within the budget. Otherwise the chunks keep
+/// their retrieval order and "rerank" is added to `degraded`. The score threshold is applied by
+/// `rerank` itself, retrieval scores are not on the same scale as reranked ones.
+pub async fn rerank_within_latency_budget<F>(
+    budget: Option<LatencyBudget>,
+    score_chunks: Vec<ScoreChunkDTO>,
+    rerank: impl FnOnce(Vec<ScoreChunkDTO>) -> F,
+    degraded: &mut Vec<String>,
+) -> Result<Vec<ScoreChunkDTO>, ServiceError>
+where
+    F: std::future::Future<Output = Result<Vec<ScoreChunkDTO>, ServiceError>>,
+{
+    let reranked_chunks = match budget {
+        Some(budget) => {
+            let reranked_chunks = if budget.is_spent() {
+                None
+            } else {
+                tokio::time::timeout(budget.remaining(), rerank(score_chunks.clone()))
+                    .await
+                    .ok()
+            };
+
+            match reranked_chunks {
+                Some(reranked_chunks) => reranked_chunks?,
+                None => {
+                    log::warn!(
+                        "Skipping cross encoder rerank, it did not fit in the latency budget"
+                    );
+                    degraded.push("rerank".to_string());
+                    return Ok(score_chunks);
+                }
+            }
+        }
+        None => rerank(score_chunks).await?,
+    };
+
+    Ok(reranked_chunks)
+}
+
+/// Checks that a precomputed dense query vector matches the embedding size of the dataset.
+pub fn get_precomputed_dense_vector(
+    query_vector: Option<Vec<f32>>,
+    config: &DatasetConfiguration,
+) -> Result<Option<Vec<f32>>, ServiceError> {
+    match query_vector {
+        Some(vector) if vector.len() != config.EMBEDDING_SIZE => {
+            Err(ServiceError::BadRequest(format!(
+                "query_vector has {} dimensions but this dataset expects {}",
+                vector.len(),
+                config.EMBEDDING_SIZE
+            )))
+        }
+        vector => Ok(vector),
+    }
+}
+
+/// Returns the precomputed vector from the request which applies to `search_type`, if any.
+/// Callers fall back to encoding the query when this returns `None`.
+pub fn get_precomputed_query_vector(
+    search_type: &SearchMethod,
+    query_vector: Option<Vec<f32>>,
+    query_sparse_vector: Option<Vec<(u32, f32)>>,
+    config: &DatasetConfiguration,
+) -> Result<Option<VectorType>, ServiceError> {
+    match search_type {
+        SearchMethod::Semantic => {
+            if query_vector.is_some() && !config.SEMANTIC_ENABLED {
+                return Err(ServiceError::BadRequest(
+                    "Semantic search is not enabled for this dataset".to_string(),
+                ));
+            }
+            Ok(get_precomputed_dense_vector(query_vector, config)?.map(VectorType::Dense))
+        }
+        SearchMethod::FullText => {
+            if query_sparse_vector.is_some() && !config.FULLTEXT_ENABLED {
+                return Err(ServiceError::BadRequest(
+                    "Full text search is not enabled for this dataset".to_string(),
+                ));
+            }
+            Ok(query_sparse_vector.map(VectorType::SpladeSparse))
+        }
+        SearchMethod::BM25 => Ok(query_sparse_vector.map(VectorType::BM25Sparse)),
+        SearchMethod::Hybrid => Ok(None),
+    }
+}
+
 async fn get_qdrant_vector(
     search_type: SearchMethod,
     parsed_query: ParsedQueryTypes,
     scoring_options: Option<ScoringOptions>,
-    config: &DatasetConfiguration,
+    context: &EmbedContext,
 ) -> Result<VectorType, ServiceError> {
+    let config = &context.dataset_config;
+
     match search_type {
         SearchMethod::Semantic => {
             if !config.SEMANTIC_ENABLED {
@@ -1984,8 +2132,7 @@ async fn get_qdrant_vector(
 
             let embedding_vector = match parsed_query {
                 ParsedQueryTypes::Single(query) => {
-                    get_dense_vector(query.query.clone(), semantic_boost, "query", config.clone())
-                        .await?
+                    get_dense_vector(query.query.clone(), semantic_boost, "query", context).await?
                 }
                 ParsedQueryTypes::Multi(queries) => {
                     let mut embedding_futures = Vec::new();
@@ -1996,7 +2143,7 @@ async fn get_qdrant_vector(
                             query.query.clone(),
                             None,
                             "query",
-                            config.clone(),
+                            context,
                         ));
                     }
 
@@ -2013,13 +2160,7 @@ async fn get_qdrant_vector(
                             if final_vector.is_empty() {
                                 final_vector = vec.into_iter().map(|v| v * boost).collect();
                             } else {
-                                final_vector = final_vector
-                                    .iter()
-                                    .zip(vec)
-                                    .map(|(vec_elem, boost_vec_elem)| {
-                                        vec_elem + boost * boost_vec_elem
-                                    })
-                                    .collect();
+                                final_vector = add_scaled(&final_vector, &vec, boost);
                             }
 
                             final_vector
@@ -2041,14 +2182,13 @@ async fn get_qdrant_vector(
                 .clone()
                 .map(|options| options.fulltext_boost)
                 .unwrap_or(None);
+            let fulltext_boost = filter_boost_for_embed_type(fulltext_boost, "query", config);
 
             let sparse_vectors = match parsed_query {
-                ParsedQueryTypes::Single(query) => get_bm25_embeddings(
-                    vec![(query.query.clone(), fulltext_boost)],
-                    config.BM25_AVG_LEN,
-                    config.BM25_B,
-                    config.BM25_K,
-                ),
+                ParsedQueryTypes::Single(query) => {
+                    get_bm25_embeddings_async(vec![(query.query.clone(), fulltext_boost)], context)
+                        .await?
+                }
                 ParsedQueryTypes::Multi(_) => {
                     return Err(ServiceError::BadRequest(
                         "BM25 search does not support multi queries".to_string(),
@@ -2070,10 +2210,11 @@ async fn get_qdrant_vector(
                 .clone()
                 .map(|options| options.fulltext_boost)
                 .unwrap_or(None);
+            let fulltext_boost = filter_boost_for_embed_type(fulltext_boost, "query", config);
 
             let sparse_vector = match parsed_query {
                 ParsedQueryTypes::Single(query) => {
-                    get_sparse_vector(query.query.clone(), fulltext_boost, "query").await?
+                    get_sparse_vector(query.query.clone(), fulltext_boost, "query", context).await?
                 }
                 ParsedQueryTypes::Multi(_) => {
                     return Err(ServiceError::BadRequest(
@@ -2099,6 +2240,8 @@ pub async fn search_chunks_query(
     config: &DatasetConfiguration,
     timer: &mut Timer,
 ) -> Result<SearchChunkQueryResponseBody, actix_web::Error> {
+    let latency_budget = LatencyBudget::from_millis(data.latency_budget_ms);
+    let context = EmbedContext::from_dataset_config(config).with_latency_budget(latency_budget);
     let mut parsed_query = parsed_query.clone();
     let mut corrected_query = None;
 
@@ -2132,13 +2275,30 @@ pub async fn search_chunks_query(
 
     timer.add("start to create query vector");
 
-    let vector = get_qdrant_vector(
-        data.clone().search_type,
-        parsed_query.clone(),
-        data.clone().scoring_options,
+    validate_vector_field_weights(data.vector_field_weights.as_ref(), config)?;
+    let precomputed_vector = get_precomputed_query_vector(
+        &data.search_type,
+        data.query_vector.clone(),
+        data.query_sparse_vector.clone(),
         config,
-    )
-    .await?;
+    )?;
+
+    let vector = match precomputed_vector {
+        Some(vector) => vector,
+        None => {
+            within_latency_budget(
+                latency_budget,
+                "embedding the query",
+                get_qdrant_vector(
+                    data.clone().search_type,
+                    parsed_query.clone(),
+                    data.clone().scoring_options,
+                    &context,
+                ),
+            )
+            .await?
+        }
+    };
 
     timer.add("computed query vector");
 
@@ -2164,8 +2324,9 @@ pub async fn search_chunks_query(
         rerank_by: rerank_by.clone(),
         filter: data.filters.clone(),
         group_size: None,
+        vector_field_weights: data.vector_field_weights.clone(),
     }
-    .into_qdrant_query(parsed_query, dataset.id, None, config, pool.clone())
+    .into_qdrant_query(parsed_query, dataset.id, None, &context, pool.clone())
     .await?;
 
     let search_chunk_query_results = retrieve_qdrant_points_query(
@@ -2188,22 +2349,26 @@ pub async fn search_chunks_query(
     )
     .await?;
 
+    let mut degraded = vec![];
     let rerank_chunks_input = if let Some(rerank_by) = rerank_by {
         match rerank_by.rerank_type {
             ReRankOptions::CrossEncoder => {
-                let mut cross_encoder_results = cross_encoder(
-                    data.query.clone().to_single_query()?,
-                    data.page_size.unwrap_or(10),
+                let query = data.query.clone().to_single_query()?;
+                rerank_within_latency_budget(
+                    latency_budget,
                     result_chunks.score_chunks,
-                    config,
+                    |score_chunks| {
+                        cross_encoder(
+                            query,
+                            data.page_size.unwrap_or(10),
+                            score_chunks,
+                            data.score_threshold,
+                            &context,
+                        )
+                    },
+                    &mut degraded,
                 )
-                .await?;
-
-                if let Some(score_threshold) = data.score_threshold {
-                    cross_encoder_results.retain(|chunk| chunk.score >= score_threshold.into());
-                }
-
-                cross_encoder_results
+                .await?
             }
             _ => result_chunks.score_chunks,
         }
@@ -2223,10 +2388,98 @@ pub async fn search_chunks_query(
     timer.add("reranking");
 
     result_chunks.corrected_query = corrected_query.map(|c| c.query);
+    if !degraded.is_empty() {
+        result_chunks.degraded = Some(degraded);
+    }
+    result_chunks
+        .score_chunks
+        .iter_mut()
+        .for_each(|chunk| chunk.score = round_score(chunk.score, get_score_decimal_places(config)));
 
     Ok(result_chunks)
 }
 
+/// Rank constant of reciprocal rank fusion. 60 is the value from the original paper, it damps the
+/// advantage of the first few ranks of each result set.
+const RRF_K: f32 = 60.0;
+
+/// Fusion strategy and semantic weight for a hybrid search, from the request's fusion options
+/// with the dataset's settings as fallback.
+pub fn get_hybrid_fusion(
+    fusion_options: Option<&FusionOptions>,
+    config: &DatasetConfiguration,
+) -> Result<(HybridFusion, f32), ServiceError> {
+    let strategy = fusion_options
+        .and_then(|options| options.strategy)
+        .unwrap_or(config.HYBRID_FUSION);
+    let semantic_weight = fusion_options
+        .and_then(|options| options.semantic_weight)
+        .unwrap_or(config.HYBRID_FUSION_SEMANTIC_WEIGHT);
+
+    if !(0.0..=1.0).contains(&semantic_weight) {
+        return Err(ServiceError::BadRequest(
+            "fusion_options.semantic_weight must be between 0 and 1".to_string(),
+        ));
+    }
+
+    Ok((strategy, semantic_weight))
+}
+
+/// Merges the result sets of a hybrid search into one list ordered by fused score, which becomes
+/// the retrieval score of each chunk. `stages` and `weights` are in the same order and a chunk
+/// gets nothing from a result set it is missing from. Ties keep the order in which the chunks
+/// were first seen, or are ordered by id in determinism mode.
+///
+/// Weighted sum normalizes every result set with its own best and worst score, so cosine
+/// similarities and SPLADE scores, which are on different scales, can be added up. Qdrant returns
+/// each set best first, so this also holds for distance metrics where lower scores are better.
+pub fn fuse_stage_results(
+    stages: &[Vec<SearchResult>],
+    weights: &[f32],
+    fusion: HybridFusion,
+) -> Vec<SearchResult> {
+    let mut fused: Vec<SearchResult> = vec![];
+    let mut positions: HashMap<uuid::Uuid, usize> = HashMap::new();
+
+    for (results, weight) in stages.iter().zip(weights) {
+        let best = results.first().map(|result| result.score).unwrap_or(0.0);
+        let worst = results.last().map(|result| result.score).unwrap_or(0.0);
+
+        for (rank, result) in results.iter().enumerate() {
+            let contribution = match fusion {
+                HybridFusion::Rrf => weight / (RRF_K + rank as f32 + 1.0),
+                HybridFusion::WeightedSum => {
+                    let normalized = if best != worst {
+                        (result.score - worst) / (best - worst)
+                    } else {
+                        1.0
+                    };
+                    weight * normalized
+                }
+            };
+
+            match positions.get(&result.point_id) {
+                Some(&position) => fused[position].score += contribution,
+                None => {
+                    positions.insert(result.point_id, fused.len());
+                    fused.push(SearchResult {
+                        score: contribution,
+                        ..result.clone()
+                    });
+                }
+            }
+        }
+    }
+
+    fused.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| deterministic_tiebreak(&a.point_id, &b.point_id))
+    });
+    fused
+}
+
 #[allow(clippy::too_many_arguments)]
 
 pub async fn search_hybrid_chunks(
@@ -2238,6 +2491,8 @@ pub async fn search_hybrid_chunks(
     config: &DatasetConfiguration,
     timer: &mut Timer,
 ) -> Result<SearchChunkQueryResponseBody, actix_web::Error> {
+    let latency_budget = LatencyBudget::from_millis(data.latency_budget_ms);
+    let context = EmbedContext::from_dataset_config(config).with_latency_budget(latency_budget);
     let mut parsed_query = parsed_query.clone();
     let mut corrected_query = None;
 
@@ -2256,8 +2511,6 @@ pub async fn search_hybrid_chunks(
         timer.add("corrected query");
     }
 
-    let dataset_config = DatasetConfiguration::from_json(dataset.server_configuration.clone());
-
     let semantic_boost = data
         .scoring_options
         .clone()
@@ -2268,19 +2521,86 @@ pub async fn search_hybrid_chunks(
         .clone()
         .map(|options| options.fulltext_boost)
         .unwrap_or(None);
+    let fulltext_boost = filter_boost_for_embed_type(fulltext_boost, "query", config);
+
+    let (fusion, semantic_weight) = get_hybrid_fusion(data.fusion_options.as_ref(), config)?;
+    validate_vector_field_weights(data.vector_field_weights.as_ref(), config)?;
+    let precomputed_dense_vector = get_precomputed_dense_vector(data.query_vector.clone(), config)?;
+    let precomputed_sparse_vector = data.query_sparse_vector.clone();
+
+    let dense_query_vector_future = async {
+        match precomputed_dense_vector {
+            Some(vector) => Ok(vector),
+            None => {
+                within_latency_budget(
+                    latency_budget,
+                    "dense embedding",
+                    get_dense_vector(
+                        parsed_query.query.clone(),
+                        semantic_boost,
+                        "query",
+                        &context,
+                    ),
+                )
+                .await
+            }
+        }
+    };
 
-    let dense_query_vector_future = get_dense_vector(
-        parsed_query.query.clone(),
-        semantic_boost,
-        "query",
-        dataset_config.clone(),
-    );
-
-    let sparse_query_vector_future =
-        get_sparse_vector(parsed_query.query.clone(), fulltext_boost, "query");
+    let sparse_query_vector_future = async {
+        match precomputed_sparse_vector {
+            Some(vector) => Ok(vector),
+            None => {
+                within_latency_budget(
+                    latency_budget,
+                    "sparse encoding",
+                    get_sparse_vector(
+                        parsed_query.query.clone(),
+                        fulltext_boost,
+                        "query",
+                        &context,
+                    ),
+                )
+                .await
+            }
+        }
+    };
 
     let (dense_vector, sparse_vector) =
-        futures::try_join!(dense_query_vector_future, sparse_query_vector_future)?;
+        futures::join!(dense_query_vector_future, sparse_query_vector_future);
+
+    // If exactly one of the encoders is unavailable, search with the surviving modality instead of failing the request
+    let mut degraded = vec![];
+    let query_vectors = match (dense_vector, sparse_vector) {
+        (Ok(dense_vector), Ok(sparse_vector)) => vec![
+            VectorType::Dense(dense_vector),
+            VectorType::SpladeSparse(sparse_vector),
+        ],
+        (Ok(dense_vector), Err(err)) => {
+            log::error!(
+                "Sparse encoder failed, continuing hybrid search with only semantic: {:?}",
+                err
+            );
+            degraded.push("fulltext".to_string());
+            vec![VectorType::Dense(dense_vector)]
+        }
+        (Err(err), Ok(sparse_vector)) => {
+            log::error!(
+                "Dense encoder failed, continuing hybrid search with only fulltext: {:?}",
+                err
+            );
+            degraded.push("semantic".to_string());
+            vec![VectorType::SpladeSparse(sparse_vector)]
+        }
+        (Err(dense_err), Err(sparse_err)) => {
+            log::error!(
+                "Both encoders failed for hybrid search: {:?} {:?}",
+                dense_err,
+                sparse_err
+            );
+            return Err(dense_err.into());
+        }
+    };
 
     timer.add("computed sparse and dense embeddings");
 
@@ -2292,44 +2612,31 @@ pub async fn search_hybrid_chunks(
         _ => (None, None),
     };
 
-    let qdrant_queries = vec![
-        RetrievePointQuery {
-            vector: VectorType::Dense(dense_vector),
-            score_threshold: None,
-            sort_by: sort_by.clone(),
-            rerank_by: rerank_by.clone(),
-            limit: data.page_size.unwrap_or(10),
-            filter: data.filters.clone(),
-            group_size: None,
-        }
-        .into_qdrant_query(
-            ParsedQueryTypes::Single(parsed_query.clone()),
-            dataset.id,
-            None,
-            config,
-            pool.clone(),
-        )
-        .await?,
-        RetrievePointQuery {
-            vector: VectorType::SpladeSparse(sparse_vector),
-            score_threshold: None,
-            sort_by: sort_by.clone(),
-            rerank_by: rerank_by.clone(),
-            limit: data.page_size.unwrap_or(10),
-            filter: data.filters.clone(),
-            group_size: None,
-        }
-        .into_qdrant_query(
-            ParsedQueryTypes::Single(parsed_query.clone()),
-            dataset.id,
-            None,
-            config,
-            pool.clone(),
-        )
-        .await?,
-    ];
+    let mut qdrant_queries = vec![];
+    for vector in query_vectors {
+        qdrant_queries.push(
+            RetrievePointQuery {
+                vector,
+                score_threshold: None,
+                sort_by: sort_by.clone(),
+                rerank_by: rerank_by.clone(),
+                limit: data.page_size.unwrap_or(10),
+                filter: data.filters.clone(),
+                group_size: None,
+                vector_field_weights: data.vector_field_weights.clone(),
+            }
+            .into_qdrant_query(
+                ParsedQueryTypes::Single(parsed_query.clone()),
+                dataset.id,
+                None,
+                &context,
+                pool.clone(),
+            )
+            .await?,
+        );
+    }
 
-    let search_chunk_query_results = retrieve_qdrant_points_query(
+    let mut search_chunk_query_results = retrieve_qdrant_points_query(
         qdrant_queries,
         data.page.unwrap_or(1),
         data.sort_options.as_ref().and_then(|d| d.mmr.clone()),
@@ -2338,6 +2645,15 @@ pub async fn search_hybrid_chunks(
     )
     .await?;
 
+    // Both encoders succeeded, the semantic results come first
+    if search_chunk_query_results.stage_results.len() == 2 {
+        search_chunk_query_results.search_results = fuse_stage_results(
+            &search_chunk_query_results.stage_results,
+            &[semantic_weight, 1.0 - semantic_weight],
+            fusion,
+        );
+    }
+
     let result_chunks = retrieve_chunks_from_point_ids(
         search_chunk_query_results.clone(),
         Some(timer),
@@ -2351,18 +2667,23 @@ pub async fn search_hybrid_chunks(
 
     let mut reranked_chunks = {
         let mut reranked_chunks = {
-            let mut cross_encoder_results = cross_encoder(
-                data.query.clone().to_single_query()?,
-                data.page_size.unwrap_or(10),
+            let query = data.query.clone().to_single_query()?;
+            let cross_encoder_results = rerank_within_latency_budget(
+                latency_budget,
                 result_chunks.score_chunks,
-                config,
+                |score_chunks| {
+                    cross_encoder(
+                        query,
+                        data.page_size.unwrap_or(10),
+                        score_chunks,
+                        data.score_threshold,
+                        &context,
+                    )
+                },
+                &mut degraded,
             )
             .await?;
 
-            if let Some(score_threshold) = data.score_threshold {
-                cross_encoder_results.retain(|chunk| chunk.score >= score_threshold.into());
-            }
-
             rerank_chunks(
                 cross_encoder_results,
                 search_chunk_query_results.search_results,
@@ -2378,6 +2699,11 @@ pub async fn search_hybrid_chunks(
             score_chunks: reranked_chunks,
             corrected_query: corrected_query.map(|c| c.query),
             total_chunk_pages: result_chunks.total_chunk_pages,
+            degraded: if degraded.is_empty() {
+                None
+            } else {
+                Some(degraded)
+            },
         }
     };
 
@@ -2400,16 +2726,348 @@ pub async fn search_hybrid_chunks(
                     })
                     .collect(),
                 highlights: score_chunk.highlights,
+                rerank_template_incomplete: score_chunk.rerank_template_incomplete,
+                preferred_highlight: score_chunk.preferred_highlight,
                 score: score_chunk.score,
             })
             .collect();
     }
 
+    reranked_chunks
+        .score_chunks
+        .iter_mut()
+        .for_each(|chunk| chunk.score = round_score(chunk.score, get_score_decimal_places(config)));
+
     Ok(reranked_chunks)
 }
 
-#[allow(clippy::too_many_arguments)]
+/// One upstream model call of a search, as reported by `explain_search_query`.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ExplainedModelCall {
+    /// "dense_embedding", "sparse_encoding" or "rerank"
+    pub operation: String,
+    /// Resolved url of the call with credentials redacted
+    pub url: String,
+    pub model_name: Option<String>,
+    /// Texts sent to the model after prefixing, clipping and PII redaction
+    pub texts: Vec<String>,
+    /// Whether the call was made. Rerank calls are only built, never sent.
+    pub sent: bool,
+    /// Dimensions of the returned vector, the number of non-zero entries for sparse vectors
+    pub dimensions: Option<usize>,
+    /// Time the call took in milliseconds
+    pub latency_ms: Option<f64>,
+    /// Request bodies which would be sent, one per batch
+    pub payloads: Option<Vec<serde_json::Value>>,
+    /// Error the call failed with or the reason it could not be built
+    pub error: Option<String>,
+}
+
+impl ExplainedModelCall {
+    fn sent(
+        operation: &str,
+        url: &str,
+        model_name: Option<String>,
+        texts: Vec<String>,
+        start: std::time::Instant,
+        result: Result<usize, &ServiceError>,
+    ) -> Self {
+        ExplainedModelCall {
+            operation: operation.to_string(),
+            url: redact_upstream_secrets(url),
+            model_name,
+            texts: texts
+                .iter()
+                .map(|text| redact_upstream_secrets(text))
+                .collect(),
+            sent: true,
+            dimensions: result.as_ref().ok().copied(),
+            latency_ms: Some(start.elapsed().as_secs_f64() * 1000.0),
+            payloads: None,
+            error: result
+                .err()
+                .map(|err| redact_upstream_secrets(&err.to_string())),
+        }
+    }
+}
 
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct SearchExplainResponse {
+    /// Query after typo correction, which is the query the models receive
+    pub query: String,
+    pub calls: Vec<ExplainedModelCall>,
+    /// Ids of the retrieved candidates in retrieval order, before reranking
+    pub candidate_ids: Vec<uuid::Uuid>,
+    /// Time retrieving the candidates took in milliseconds
+    pub retrieval_latency_ms: f64,
+}
+
+/// Dry run of a chunk search for debugging model configuration. The query side embedding and
+/// sparse encoding calls are made as the search would make them and candidates are retrieved with
+/// the resulting vectors, but the rerank payloads are only built. Results are never reranked,
+/// returned or recorded in analytics.
+pub async fn explain_search_query(
+    mut data: SearchChunksReqPayload,
+    parsed_query: ParsedQuery,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+    dataset: Dataset,
+    config: &DatasetConfiguration,
+) -> Result<SearchExplainResponse, actix_web::Error> {
+    let context = EmbedContext::from_dataset_config(config);
+
+    let mut parsed_query = parsed_query;
+    if let Some(options) = &data.typo_options {
+        let typo_corrected_query =
+            correct_query(parsed_query.clone(), dataset.id, redis_pool, options).await?;
+        parsed_query = typo_corrected_query.query.unwrap_or(parsed_query);
+        data.query = QueryTypes::Single(parsed_query.query.clone());
+    }
+
+    let semantic_boost = data
+        .scoring_options
+        .clone()
+        .map(|options| options.semantic_boost)
+        .unwrap_or(None);
+    let fulltext_boost = data
+        .scoring_options
+        .clone()
+        .map(|options| options.fulltext_boost)
+        .unwrap_or(None);
+    let fulltext_boost = filter_boost_for_embed_type(fulltext_boost, "query", config);
+
+    validate_vector_field_weights(data.vector_field_weights.as_ref(), config)?;
+    let mut calls = vec![];
+    let mut query_vectors = vec![];
+
+    if matches!(
+        data.search_type,
+        SearchMethod::Semantic | SearchMethod::Hybrid
+    ) {
+        match get_precomputed_dense_vector(data.query_vector.clone(), config)? {
+            Some(vector) => query_vectors.push(VectorType::Dense(vector)),
+            None => {
+                let texts = get_dense_vector_inputs(
+                    &parsed_query.query,
+                    filter_boost_for_embed_type(semantic_boost.clone(), "query", config).as_ref(),
+                    "query",
+                    config,
+                )?;
+                let start = std::time::Instant::now();
+                let vector = get_dense_vector(
+                    parsed_query.query.clone(),
+                    semantic_boost,
+                    "query",
+                    &context,
+                )
+                .await;
+                calls.push(ExplainedModelCall::sent(
+                    "dense_embedding",
+                    &get_embedding_url(
+                        &resolve_embedding_base_url(&config.EMBEDDING_BASE_URL)
+                            .unwrap_or(config.EMBEDDING_BASE_URL.clone()),
+                        &config.EMBEDDING_MODEL_NAME,
+                        config.EMBEDDING_PROVIDER,
+                    ),
+                    Some(config.EMBEDDING_MODEL_NAME.clone()),
+                    texts,
+                    start,
+                    vector.as_ref().map(|vector| vector.len()),
+                ));
+                if let Ok(vector) = vector {
+                    query_vectors.push(VectorType::Dense(vector));
+                }
+            }
+        }
+    }
+
+    if matches!(
+        data.search_type,
+        SearchMethod::FullText | SearchMethod::Hybrid
+    ) {
+        match data.query_sparse_vector.clone() {
+            Some(vector) => query_vectors.push(VectorType::SpladeSparse(vector)),
+            None => {
+                let texts = get_sparse_vector_inputs(&parsed_query.query, fulltext_boost.as_ref())?;
+                let start = std::time::Instant::now();
+                let vector = get_sparse_vector(
+                    parsed_query.query.clone(),
+                    fulltext_boost,
+                    "query",
+                    &context,
+                )
+                .await;
+                calls.push(ExplainedModelCall::sent(
+                    "sparse_encoding",
+                    &format!(
+                        "{}/embed_sparse",
+                        context.sparse_query_origin.clone().unwrap_or_default()
+                    ),
+                    None,
+                    texts,
+                    start,
+                    vector
+                        .as_ref()
+                        .map(|vector| vector.iter().filter(|(_, value)| *value != 0.0).count()),
+                ));
+                if let Ok(vector) = vector {
+                    query_vectors.push(VectorType::SpladeSparse(vector));
+                }
+            }
+        }
+    }
+
+    if data.search_type == SearchMethod::BM25 {
+        let vector = match get_precomputed_query_vector(
+            &data.search_type,
+            None,
+            data.query_sparse_vector.clone(),
+            config,
+        )? {
+            Some(vector) => vector,
+            None => {
+                get_qdrant_vector(
+                    data.search_type.clone(),
+                    ParsedQueryTypes::Single(parsed_query.clone()),
+                    data.scoring_options.clone(),
+                    &context,
+                )
+                .await?
+            }
+        };
+        query_vectors.push(vector);
+    }
+
+    let (sort_by, rerank_by) = match data.sort_options.as_ref().map(|d| d.sort_by.clone()) {
+        Some(Some(sort_by)) => match sort_by {
+            QdrantSortBy::Field(field) => (Some(field.clone()), None),
+            QdrantSortBy::SearchType(search_type) => (None, Some(search_type)),
+        },
+        _ => (None, None),
+    };
+    let cross_encoder_rerank = data.search_type == SearchMethod::Hybrid
+        || rerank_by.clone().map(|r| r.rerank_type) == Some(ReRankOptions::CrossEncoder);
+
+    let retrieval_start = std::time::Instant::now();
+    let mut candidates = vec![];
+    if !query_vectors.is_empty() {
+        let mut qdrant_queries = vec![];
+        for vector in query_vectors {
+            qdrant_queries.push(
+                RetrievePointQuery {
+                    vector,
+                    score_threshold: if cross_encoder_rerank {
+                        None
+                    } else {
+                        data.score_threshold
+                    },
+                    limit: data.page_size.unwrap_or(10),
+                    sort_by: sort_by.clone(),
+                    rerank_by: rerank_by.clone(),
+                    filter: data.filters.clone(),
+                    group_size: None,
+                    vector_field_weights: data.vector_field_weights.clone(),
+                }
+                .into_qdrant_query(
+                    ParsedQueryTypes::Single(parsed_query.clone()),
+                    dataset.id,
+                    None,
+                    &context,
+                    pool.clone(),
+                )
+                .await?,
+            );
+        }
+
+        let mut search_chunk_query_results = retrieve_qdrant_points_query(
+            qdrant_queries,
+            data.page.unwrap_or(1),
+            data.sort_options.as_ref().and_then(|d| d.mmr.clone()),
+            false,
+            config,
+        )
+        .await?;
+
+        if data.search_type == SearchMethod::Hybrid
+            && search_chunk_query_results.stage_results.len() == 2
+        {
+            let (fusion, semantic_weight) =
+                get_hybrid_fusion(data.fusion_options.as_ref(), config)?;
+            search_chunk_query_results.search_results = fuse_stage_results(
+                &search_chunk_query_results.stage_results,
+                &[semantic_weight, 1.0 - semantic_weight],
+                fusion,
+            );
+        }
+
+        candidates = retrieve_chunks_from_point_ids(
+            search_chunk_query_results,
+            None,
+            &data,
+            config.QDRANT_ONLY,
+            pool.clone(),
+        )
+        .await?
+        .score_chunks;
+    }
+    let retrieval_latency_ms = retrieval_start.elapsed().as_secs_f64() * 1000.0;
+
+    if cross_encoder_rerank && !candidates.is_empty() {
+        candidates = dedup_rerank_candidates(candidates);
+        let query = data.query.clone().to_single_query()?;
+        let mut call = ExplainedModelCall {
+            operation: "rerank".to_string(),
+            url: redact_upstream_secrets(&format!("{}/rerank", config.RERANKER_BASE_URL)),
+            model_name: Some(config.RERANKER_MODEL_NAME.clone()),
+            texts: vec![],
+            sent: false,
+            dimensions: None,
+            latency_ms: None,
+            payloads: None,
+            error: None,
+        };
+
+        match std::env::var("RERANKER_SERVER_ORIGIN") {
+            Ok(default_server_origin) => {
+                call.texts = get_rerank_documents(&candidates, config)?
+                    .iter()
+                    .map(|text| redact_upstream_secrets(text))
+                    .collect();
+                call.payloads = Some(
+                    get_rerank_payloads(
+                        &query,
+                        data.page_size.unwrap_or(10),
+                        &candidates,
+                        config,
+                        &default_server_origin,
+                    )?
+                    .into_iter()
+                    .map(redact_upstream_payload)
+                    .collect(),
+                );
+            }
+            Err(_) => {
+                call.error = Some(
+                    "RERANKER_SERVER_ORIGIN is not set, reranking is disabled for this deployment"
+                        .to_string(),
+                );
+            }
+        }
+        calls.push(call);
+    }
+
+    Ok(SearchExplainResponse {
+        query: parsed_query.query,
+        calls,
+        candidate_ids: candidates
+            .iter()
+            .filter_map(|candidate| candidate.metadata.first().map(|metadata| metadata.id()))
+            .collect(),
+        retrieval_latency_ms,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn search_groups_query(
     mut data: SearchWithinGroupReqPayload,
     parsed_query: ParsedQueryTypes,
@@ -2420,8 +3078,15 @@ pub async fn search_groups_query(
     config: &DatasetConfiguration,
     timer: &mut Timer,
 ) -> Result<SearchWithinGroupResults, actix_web::Error> {
-    let vector =
-        get_qdrant_vector(data.clone().search_type, parsed_query.clone(), None, config).await?;
+    let context = EmbedContext::from_dataset_config(config);
+
+    let vector = get_qdrant_vector(
+        data.clone().search_type,
+        parsed_query.clone(),
+        data.scoring_options.clone(),
+        &context,
+    )
+    .await?;
 
     let mut parsed_query = parsed_query.clone();
     let mut corrected_query = None;
@@ -2475,8 +3140,9 @@ pub async fn search_groups_query(
         rerank_by: rerank_by.clone(),
         filter: data.filters.clone(),
         group_size: None,
+        vector_field_weights: None,
     }
-    .into_qdrant_query(parsed_query, dataset.id, None, config, pool.clone())
+    .into_qdrant_query(parsed_query, dataset.id, None, &context, pool.clone())
     .await?;
 
     let search_semantic_chunk_query_results = retrieve_qdrant_points_query(
@@ -2500,19 +3166,14 @@ pub async fn search_groups_query(
     let rerank_chunks_input = if let Some(rerank_by) = rerank_by {
         match rerank_by.rerank_type {
             ReRankOptions::CrossEncoder => {
-                let mut cross_encoder_results = cross_encoder(
+                cross_encoder(
                     data.query.clone().to_single_query()?,
                     data.page_size.unwrap_or(10),
                     result_chunks.score_chunks,
-                    config,
+                    data.score_threshold,
+                    &context,
                 )
-                .await?;
-
-                if let Some(score_threshold) = data.score_threshold {
-                    cross_encoder_results.retain(|chunk| chunk.score >= score_threshold.into());
-                }
-
-                cross_encoder_results
+                .await?
             }
             _ => result_chunks.score_chunks,
         }
@@ -2549,7 +3210,7 @@ pub async fn search_hybrid_groups(
     config: &DatasetConfiguration,
     timer: &mut Timer,
 ) -> Result<SearchWithinGroupResults, actix_web::Error> {
-    let dataset_config = DatasetConfiguration::from_json(dataset.server_configuration.clone());
+    let context = EmbedContext::from_dataset_config(config);
 
     let mut parsed_query = parsed_query.clone();
     let mut corrected_query = None;
@@ -2569,14 +3230,29 @@ pub async fn search_hybrid_groups(
         timer.add("corrected query");
     }
 
+    let semantic_boost = data
+        .scoring_options
+        .clone()
+        .and_then(|options| options.semantic_boost);
+    let fulltext_boost = data
+        .scoring_options
+        .clone()
+        .and_then(|options| options.fulltext_boost);
+    let fulltext_boost = filter_boost_for_embed_type(fulltext_boost, "query", config);
+
     let dense_vector_future = get_dense_vector(
         parsed_query.query.clone(),
-        None,
+        semantic_boost,
         "query",
-        dataset_config.clone(),
+        &context,
     );
 
-    let sparse_vector_future = get_sparse_vector(parsed_query.query.clone(), None, "query");
+    let sparse_vector_future = get_sparse_vector(
+        parsed_query.query.clone(),
+        fulltext_boost,
+        "query",
+        &context,
+    );
 
     let (dense_vector, sparse_vector) =
         futures::try_join!(dense_vector_future, sparse_vector_future)?;
@@ -2598,12 +3274,13 @@ pub async fn search_hybrid_groups(
             limit: data.page_size.unwrap_or(10),
             filter: data.filters.clone(),
             group_size: None,
+            vector_field_weights: None,
         }
         .into_qdrant_query(
             ParsedQueryTypes::Single(parsed_query.clone()),
             dataset.id,
             Some(group.id),
-            config,
+            &context,
             pool.clone(),
         )
         .await?,
@@ -2615,12 +3292,13 @@ pub async fn search_hybrid_groups(
             limit: data.page_size.unwrap_or(10),
             filter: data.filters.clone(),
             group_size: None,
+            vector_field_weights: None,
         }
         .into_qdrant_query(
             ParsedQueryTypes::Single(parsed_query.clone()),
             dataset.id,
             Some(group.id),
-            config,
+            &context,
             pool.clone(),
         )
         .await?,
@@ -2652,7 +3330,7 @@ pub async fn search_hybrid_groups(
     .await?;
 
     let reranked_chunks = {
-        let mut reranked_chunks = if result_chunks.score_chunks.len() > 20 {
+        let reranked_chunks = if result_chunks.score_chunks.len() > 20 {
             let split_results = result_chunks
                 .score_chunks
                 .chunks(20)
@@ -2666,27 +3344,28 @@ pub async fn search_hybrid_groups(
                     .get(0)
                     .expect("Split results must exist")
                     .to_vec(),
-                config,
+                data.score_threshold,
+                &context,
             )
             .await?;
-            let mut score_chunks: Vec<ScoreChunkDTO> = rerank_chunks(
+            let score_chunks: Vec<ScoreChunkDTO> = rerank_chunks(
                 cross_encoder_results,
                 qdrant_results.search_results,
                 data.sort_options,
             );
-            score_chunks.truncate(data.page_size.unwrap_or(10) as usize);
 
-            score_chunks
-                .iter()
-                .chain(split_results.get(1).unwrap().iter())
-                .cloned()
-                .collect::<Vec<ScoreChunkDTO>>()
+            merge_reranked_with_remainder(
+                score_chunks,
+                split_results.get(1).unwrap().to_vec(),
+                data.page_size.unwrap_or(10) as usize,
+            )
         } else {
             let cross_encoder_results = cross_encoder(
                 data.query.clone().to_single_query()?,
                 data.page_size.unwrap_or(10),
                 result_chunks.score_chunks.clone(),
-                config,
+                data.score_threshold,
+                &context,
             )
             .await?;
 
@@ -2699,14 +3378,11 @@ pub async fn search_hybrid_groups(
             score_chunks
         };
 
-        if let Some(score_threshold) = data.score_threshold {
-            reranked_chunks.retain(|chunk| chunk.score >= score_threshold.into());
-        }
-
         SearchChunkQueryResponseBody {
             score_chunks: reranked_chunks,
             corrected_query: None,
             total_chunk_pages: result_chunks.total_chunk_pages,
+            degraded: None,
         }
     };
 
@@ -2727,6 +3403,8 @@ pub async fn search_over_groups_query(
     config: &DatasetConfiguration,
     timer: &mut Timer,
 ) -> Result<DeprecatedSearchOverGroupsResponseBody, actix_web::Error> {
+    let context = EmbedContext::from_dataset_config(config);
+
     timer.add("start to create dense embedding vector");
 
     let mut parsed_query = parsed_query.clone();
@@ -2759,8 +3437,13 @@ pub async fn search_over_groups_query(
         timer.add("corrected query");
     }
 
-    let vector =
-        get_qdrant_vector(data.clone().search_type, parsed_query.clone(), None, config).await?;
+    let vector = get_qdrant_vector(
+        data.clone().search_type,
+        parsed_query.clone(),
+        data.scoring_options.clone(),
+        &context,
+    )
+    .await?;
 
     timer.add("computed dense embedding");
 
@@ -2786,8 +3469,9 @@ pub async fn search_over_groups_query(
         rerank_by: rerank_by.clone(),
         filter: data.filters.clone(),
         group_size: data.group_size,
+        vector_field_weights: None,
     }
-    .into_qdrant_query(parsed_query, dataset.id, None, config, pool.clone())
+    .into_qdrant_query(parsed_query, dataset.id, None, &context, pool.clone())
     .await?;
 
     let search_over_groups_qdrant_result = retrieve_group_qdrant_points_query(
@@ -2840,14 +3524,15 @@ async fn cross_encoder_for_groups(
     query: String,
     page_size: u64,
     groups_chunks: Vec<GroupScoreChunk>,
-    config: &DatasetConfiguration,
+    context: &EmbedContext,
 ) -> Result<Vec<GroupScoreChunk>, actix_web::Error> {
     let score_chunks = groups_chunks
         .iter()
         .filter_map(|group| group.metadata.clone().get(0).cloned().clone())
         .collect_vec();
 
-    let cross_encoder_results = cross_encoder(query, page_size, score_chunks, config).await?;
+    let cross_encoder_results =
+        cross_encoder(query, page_size, score_chunks, None, context).await?;
     let mut group_results = cross_encoder_results
         .into_iter()
         .filter_map(|score_chunk| {
@@ -2887,7 +3572,7 @@ pub async fn hybrid_search_over_groups(
     config: &DatasetConfiguration,
     timer: &mut Timer,
 ) -> Result<DeprecatedSearchOverGroupsResponseBody, actix_web::Error> {
-    let dataset_config = DatasetConfiguration::from_json(dataset.server_configuration.clone());
+    let context = EmbedContext::from_dataset_config(config);
 
     timer.add("start to create dense embedding vector and sparse vector");
 
@@ -2909,15 +3594,29 @@ pub async fn hybrid_search_over_groups(
         timer.add("corrected query");
     }
 
+    let semantic_boost = data
+        .scoring_options
+        .clone()
+        .and_then(|options| options.semantic_boost);
+    let fulltext_boost = data
+        .scoring_options
+        .clone()
+        .and_then(|options| options.fulltext_boost);
+    let fulltext_boost = filter_boost_for_embed_type(fulltext_boost, "query", config);
+
     let dense_embedding_vectors_future = get_dense_vector(
         data.query.clone().to_single_query()?,
-        None,
+        semantic_boost,
         "query",
-        dataset_config.clone(),
+        &context,
     );
 
-    let sparse_embedding_vector_future =
-        get_sparse_vector(data.query.clone().to_single_query()?, None, "query");
+    let sparse_embedding_vector_future = get_sparse_vector(
+        data.query.clone().to_single_query()?,
+        fulltext_boost,
+        "query",
+        &context,
+    );
 
     let (dense_vector, sparse_vector) = futures::try_join!(
         dense_embedding_vectors_future,
@@ -2943,12 +3642,13 @@ pub async fn hybrid_search_over_groups(
             limit: data.page_size.unwrap_or(10),
             filter: data.filters.clone(),
             group_size: data.group_size,
+            vector_field_weights: None,
         }
         .into_qdrant_query(
             ParsedQueryTypes::Single(parsed_query.clone()),
             dataset.id,
             None,
-            config,
+            &context,
             pool.clone(),
         )
         .await?,
@@ -2960,12 +3660,13 @@ pub async fn hybrid_search_over_groups(
             limit: data.page_size.unwrap_or(10),
             filter: data.filters.clone(),
             group_size: data.group_size,
+            vector_field_weights: None,
         }
         .into_qdrant_query(
             ParsedQueryTypes::Single(parsed_query.clone()),
             dataset.id,
             None,
-            config,
+            &context,
             pool.clone(),
         )
         .await?,
@@ -3006,7 +3707,7 @@ pub async fn hybrid_search_over_groups(
                 .get(0)
                 .expect("Split results must exist")
                 .to_vec(),
-            config,
+            &context,
         )
         .await?;
 
@@ -3020,7 +3721,7 @@ pub async fn hybrid_search_over_groups(
             data.query.clone().to_single_query()?,
             data.page_size.unwrap_or(10),
             combined_result_chunks.group_chunks.clone(),
-            config,
+            &context,
         )
         .await?
     };
@@ -3061,6 +3762,8 @@ pub async fn autocomplete_chunks_query(
     config: &DatasetConfiguration,
     timer: &mut Timer,
 ) -> Result<SearchChunkQueryResponseBody, actix_web::Error> {
+    let context = EmbedContext::from_dataset_config(config);
+
     let mut parsed_query = parsed_query.clone();
     let mut corrected_query = None;
 
@@ -3095,7 +3798,7 @@ pub async fn autocomplete_chunks_query(
         data.clone().search_type,
         ParsedQueryTypes::Single(parsed_query.clone()),
         data.clone().scoring_options,
-        config,
+        &context,
     )
     .await?;
 
@@ -3108,12 +3811,13 @@ pub async fn autocomplete_chunks_query(
             limit: data.page_size.unwrap_or(10),
             filter: data.filters.clone(),
             group_size: None,
+            vector_field_weights: None,
         }
         .into_qdrant_query(
             ParsedQueryTypes::Single(parsed_query.clone()),
             dataset.id,
             None,
-            config,
+            &context,
             pool.clone(),
         )
         .await?,
@@ -3135,12 +3839,13 @@ pub async fn autocomplete_chunks_query(
                 limit: data.page_size.unwrap_or(10),
                 filter: data.filters.clone(),
                 group_size: None,
+                vector_field_weights: None,
             }
             .into_qdrant_query(
                 ParsedQueryTypes::Single(parsed_query.clone()),
                 dataset.id,
                 None,
-                config,
+                &context,
                 pool.clone(),
             )
             .await?,
@@ -3199,6 +3904,10 @@ pub async fn autocomplete_chunks_query(
     timer.add("reranking");
 
     result_chunks.corrected_query = corrected_query.map(|c| c.query);
+    result_chunks
+        .score_chunks
+        .iter_mut()
+        .for_each(|chunk| chunk.score = round_score(chunk.score, get_score_decimal_places(config)));
 
     Ok(result_chunks)
 }
@@ -3210,11 +3919,13 @@ pub async fn count_chunks_query(
     dataset: Dataset,
     config: &DatasetConfiguration,
 ) -> Result<CountChunkQueryResponseBody, actix_web::Error> {
+    let context = EmbedContext::from_dataset_config(config);
+
     let vector = get_qdrant_vector(
         data.clone().search_type.into(),
         parsed_query.clone(),
         None,
-        config,
+        &context,
     )
     .await?;
 
@@ -3226,8 +3937,9 @@ pub async fn count_chunks_query(
         limit: data.limit.unwrap_or(100000_u64),
         filter: data.filters.clone(),
         group_size: None,
+        vector_field_weights: None,
     }
-    .into_qdrant_query(parsed_query, dataset.id, None, config, pool.clone())
+    .into_qdrant_query(parsed_query, dataset.id, None, &context, pool.clone())
     .await?;
 
     let count = count_qdrant_query(
@@ -3239,3 +3951,263 @@ pub async fn count_chunks_query(
 
     Ok(CountChunkQueryResponseBody { count })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_precomputed_query_vectors() {
+        let config = DatasetConfiguration {
+            EMBEDDING_SIZE: 3,
+            ..Default::default()
+        };
+        let dense = vec![0.1, 0.2, 0.3];
+        let sparse = vec![(7, 0.5), (42, 1.0)];
+
+        // Dense only
+        let vector = get_precomputed_query_vector(
+            &SearchMethod::Semantic,
+            Some(dense.clone()),
+            None,
+            &config,
+        )
+        .unwrap();
+        assert!(matches!(vector, Some(VectorType::Dense(v)) if v == dense));
+        let vector = get_precomputed_query_vector(
+            &SearchMethod::FullText,
+            Some(dense.clone()),
+            None,
+            &config,
+        )
+        .unwrap();
+        assert!(vector.is_none());
+
+        // Sparse only
+        let vector = get_precomputed_query_vector(
+            &SearchMethod::FullText,
+            None,
+            Some(sparse.clone()),
+            &config,
+        )
+        .unwrap();
+        assert!(matches!(vector, Some(VectorType::SpladeSparse(v)) if v == sparse));
+        let vector =
+            get_precomputed_query_vector(&SearchMethod::BM25, None, Some(sparse.clone()), &config)
+                .unwrap();
+        assert!(matches!(vector, Some(VectorType::BM25Sparse(v)) if v == sparse));
+        let vector = get_precomputed_query_vector(
+            &SearchMethod::Semantic,
+            None,
+            Some(sparse.clone()),
+            &config,
+        )
+        .unwrap();
+        assert!(vector.is_none());
+
+        // Both, each search type picks the vector it searches with
+        let vector = get_precomputed_query_vector(
+            &SearchMethod::Semantic,
+            Some(dense.clone()),
+            Some(sparse.clone()),
+            &config,
+        )
+        .unwrap();
+        assert!(matches!(vector, Some(VectorType::Dense(v)) if v == dense));
+        assert_eq!(
+            get_precomputed_dense_vector(Some(dense.clone()), &config).unwrap(),
+            Some(dense.clone())
+        );
+        assert_eq!(get_precomputed_dense_vector(None, &config).unwrap(), None);
+
+        // Dimension mismatch
+        let err = get_precomputed_query_vector(
+            &SearchMethod::Semantic,
+            Some(vec![0.1, 0.2]),
+            None,
+            &config,
+        )
+        .unwrap_err();
+        assert!(
+            matches!(err, ServiceError::BadRequest(msg) if msg == "query_vector has 2 dimensions but this dataset expects 3")
+        );
+        assert!(get_precomputed_dense_vector(Some(vec![0.0; 4]), &config).is_err());
+    }
+
+    #[test]
+    pub fn test_latency_budget_skips_rerank() {
+        use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+        use std::time::Duration;
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_time()
+            .build()
+            .expect("Runtime builds");
+
+        let score_chunk = |score: f64| ScoreChunkDTO {
+            metadata: vec![],
+            highlights: None,
+            rerank_template_incomplete: None,
+            preferred_highlight: None,
+            score,
+        };
+        // Mock cross encoder which reverses the order and gives the last chunk the best score
+        let reverse = |score_chunks: Vec<ScoreChunkDTO>| async move {
+            Ok::<_, ServiceError>(
+                score_chunks
+                    .into_iter()
+                    .rev()
+                    .enumerate()
+                    .map(|(i, chunk)| ScoreChunkDTO {
+                        score: 1.0 - i as f64 * 0.5,
+                        ..chunk
+                    })
+                    .collect::<Vec<ScoreChunkDTO>>(),
+            )
+        };
+
+        runtime.block_on(async {
+            let budget = LatencyBudget::from_millis(Some(50));
+
+            // A slow embedder fails fast once the budget is spent instead of waiting on the model
+            let started = std::time::Instant::now();
+            let embedding = within_latency_budget(budget, "dense embedding", async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(vec![0.0; 3])
+            })
+            .await;
+            assert!(matches!(embedding, Err(ServiceError::RequestTimeout(_))));
+            assert!(started.elapsed() < Duration::from_secs(1));
+            assert!(budget.expect("Budget is set").is_spent());
+
+            // Nothing is left for the rerank, so it never runs and the search is marked degraded
+            let rerank_ran = AtomicBool::new(false);
+            let mut degraded = vec![];
+            let chunks = rerank_within_latency_budget(
+                budget,
+                vec![score_chunk(0.9), score_chunk(0.4)],
+                |score_chunks| {
+                    rerank_ran.store(true, AtomicOrdering::SeqCst);
+                    reverse(score_chunks)
+                },
+                &mut degraded,
+            )
+            .await
+            .expect("Skipping the rerank is not an error");
+            assert!(!rerank_ran.load(AtomicOrdering::SeqCst));
+            assert_eq!(degraded, vec!["rerank".to_string()]);
+            // Retrieval order and scores are kept
+            assert_eq!(
+                chunks.iter().map(|chunk| chunk.score).collect::<Vec<f64>>(),
+                vec![0.9, 0.4]
+            );
+
+            // A slow reranker is abandoned when it outlasts the budget
+            let mut degraded = vec![];
+            let chunks = rerank_within_latency_budget(
+                LatencyBudget::from_millis(Some(50)),
+                vec![score_chunk(0.9), score_chunk(0.4)],
+                |score_chunks| async move {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    reverse(score_chunks).await
+                },
+                &mut degraded,
+            )
+            .await
+            .expect("Skipping the rerank is not an error");
+            assert_eq!(degraded, vec!["rerank".to_string()]);
+            assert_eq!(chunks.len(), 2);
+
+            // Within budget, or without one, the rerank runs
+            for budget in [LatencyBudget::from_millis(Some(5_000)), None] {
+                let mut degraded = vec![];
+                let chunks = rerank_within_latency_budget(
+                    budget,
+                    vec![score_chunk(0.9), score_chunk(0.4)],
+                    reverse,
+                    &mut degraded,
+                )
+                .await
+                .expect("Rerank succeeds");
+                assert!(degraded.is_empty());
+                assert_eq!(
+                    chunks.iter().map(|chunk| chunk.score).collect::<Vec<f64>>(),
+                    vec![1.0, 0.5]
+                );
+            }
+        });
+    }
+
+    #[test]
+    pub fn test_hybrid_fusion() {
+        let ids = (0..4)
+            .map(|_| uuid::Uuid::new_v4())
+            .collect::<Vec<uuid::Uuid>>();
+        let (a, b, c, d) = (ids[0], ids[1], ids[2], ids[3]);
+        let results = |scored: &[(uuid::Uuid, f32)]| {
+            scored
+                .iter()
+                .map(|(point_id, score)| SearchResult {
+                    score: *score,
+                    point_id: *point_id,
+                    payload: HashMap::new(),
+                    embedding: None,
+                })
+                .collect::<Vec<SearchResult>>()
+        };
+        let order = |fused: &[SearchResult]| {
+            fused
+                .iter()
+                .map(|result| result.point_id)
+                .collect::<Vec<uuid::Uuid>>()
+        };
+
+        let semantic = results(&[(a, 0.9), (b, 0.8), (c, 0.5)]);
+        let fulltext = results(&[(c, 12.0), (d, 9.0), (a, 3.0)]);
+        let stages = vec![semantic, fulltext];
+
+        // Rank based, the raw scores of the two sets never meet
+        let fused = fuse_stage_results(&stages, &[0.7, 0.3], HybridFusion::Rrf);
+        assert_eq!(order(&fused), vec![a, c, b, d]);
+        assert!((fused[0].score - (0.7 / 61.0 + 0.3 / 63.0)).abs() < 1e-6);
+
+        // Each set is normalized with its own best and worst score before the weighted sum
+        let fused = fuse_stage_results(&stages, &[0.3, 0.7], HybridFusion::WeightedSum);
+        assert_eq!(order(&fused), vec![c, d, a, b]);
+        assert!((fused[0].score - 0.7).abs() < 1e-6);
+        assert!((fused[3].score - 0.3 * 0.75).abs() < 1e-6);
+
+        // Ties keep the order in which chunks were first seen
+        let fused = fuse_stage_results(&stages, &[0.5, 0.5], HybridFusion::WeightedSum);
+        assert_eq!(order(&fused), vec![a, c, b, d]);
+
+        // Distance metrics return the best, i.e. lowest, score first
+        let distances = results(&[(b, 0.1), (a, 0.4), (d, 0.9)]);
+        let fused = fuse_stage_results(&[distances], &[1.0], HybridFusion::WeightedSum);
+        assert_eq!(order(&fused), vec![b, a, d]);
+        assert!((fused[1].score - 0.625).abs() < 1e-6);
+
+        let config = DatasetConfiguration {
+            HYBRID_FUSION: HybridFusion::WeightedSum,
+            HYBRID_FUSION_SEMANTIC_WEIGHT: 0.8,
+            ..Default::default()
+        };
+        assert_eq!(
+            get_hybrid_fusion(None, &config).unwrap(),
+            (HybridFusion::WeightedSum, 0.8)
+        );
+        let options = FusionOptions {
+            strategy: Some(HybridFusion::Rrf),
+            semantic_weight: None,
+        };
+        assert_eq!(
+            get_hybrid_fusion(Some(&options), &config).unwrap(),
+            (HybridFusion::Rrf, 0.8)
+        );
+        let options = FusionOptions {
+            strategy: None,
+            semantic_weight: Some(1.5),
+        };
+        assert!(get_hybrid_fusion(Some(&options), &config).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use crate::data::models::{Bm25TermToken, DatasetConfiguration, Pool};
+use crate::errors::ServiceError;
+use crate::handlers::chunk_handler::FullTextBoost;
+use crate::operators::model_operator::{get_bm25_term_tokens, Bm25Stopwords};
+use crate::operators::token_operator::TermTokens;
+use actix_web::web;
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use diesel_async::RunQueryDsl;
+
+/// Rows per insert, postgres accepts at most 65535 bind parameters per statement.
+const TERM_TOKENS_INSERT_BATCH_SIZE: usize = 10000;
+
+/// Records the stems behind the BM25 term ids of `chunks_and_boost` if the dataset has
+/// `BM25_TERM_TOKENS_ENABLED`. Failing to store them only logs an error, they are a debugging
+/// aid and must not fail ingestion.
+pub async fn record_bm25_term_tokens(
+    dataset_id: uuid::Uuid,
+    chunks_and_boost: Vec<(String, Option<FullTextBoost>)>,
+    dataset_config: &DatasetConfiguration,
+    pool: web::Data<Pool>,
+) {
+    if !dataset_config.BM25_TERM_TOKENS_ENABLED || chunks_and_boost.is_empty() {
+        return;
+    }
+
+    let min_token_length = dataset_config.BM25_MIN_TOKEN_LENGTH;
+    let max_token_length = dataset_config.BM25_MAX_TOKEN_LENGTH;
+    let language = dataset_config.BM25_LANGUAGE;
+    let stopwords = Bm25Stopwords::from_dataset_config(dataset_config);
+    let term_tokens = match tokio::task::spawn_blocking(move || {
+        get_bm25_term_tokens(
+            &chunks_and_boost,
+            min_token_length,
+            max_token_length,
+            language,
+            &stopwords,
+        )
+    })
+    .await
+    {
+        Ok(term_tokens) => term_tokens,
+        Err(err) => {
+            log::error!("Failed to collect bm25 term tokens {:?}", err);
+            return;
+        }
+    };
+
+    if let Err(err) = upsert_bm25_term_tokens_query(
+        dataset_id,
+        term_tokens,
+        dataset_config.BM25_TERM_TOKENS_MAX_ENTRIES,
+        pool,
+    )
+    .await
+    {
+        log::error!("Failed to store bm25 term tokens {:?}", err);
+    }
+}
+
+/// Stores `term_tokens` for the dataset, refreshing when already stored pairs were last seen.
+/// Once the dataset has more than `max_entries` pairs the least recently seen are dropped.
+pub async fn upsert_bm25_term_tokens_query(
+    dataset_id: uuid::Uuid,
+    term_tokens: TermTokens,
+    max_entries: usize,
+    pool: web::Data<Pool>,
+) -> Result<(), ServiceError> {
+    use crate::data::schema::bm25_term_tokens::dsl as bm25_term_tokens_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|e| ServiceError::InternalServerError(e.to_string()))?;
+
+    let rows = term_tokens
+        .iter()
+        .map(|(term_id, token)| Bm25TermToken::from_details(dataset_id, term_id, token.to_string()))
+        .collect::<Vec<Bm25TermToken>>();
+
+    for batch in rows.chunks(TERM_TOKENS_INSERT_BATCH_SIZE) {
+        diesel::insert_into(bm25_term_tokens_columns::bm25_term_tokens)
+            .values(batch)
+            .on_conflict((
+                bm25_term_tokens_columns::dataset_id,
+                bm25_term_tokens_columns::term_id,
+                bm25_term_tokens_columns::token,
+            ))
+            .do_update()
+            .set(
+                bm25_term_tokens_columns::last_seen_at
+                    .eq(excluded(bm25_term_tokens_columns::last_seen_at)),
+            )
+            .execute(&mut conn)
+            .await
+            .map_err(|e| ServiceError::InternalServerError(e.to_string()))?;
+    }
+
+    diesel::sql_query(
+        "DELETE FROM bm25_term_tokens WHERE dataset_id = $1 AND (term_id, token) IN (
+            SELECT term_id, token FROM bm25_term_tokens
+            WHERE dataset_id = $1
+            ORDER BY last_seen_at DESC
+            OFFSET $2
+        )",
+    )
+    .bind::<diesel::sql_types::Uuid, _>(dataset_id)
+    .bind::<diesel::sql_types::BigInt, _>(max_entries as i64)
+    .execute(&mut conn)
+    .await
+    .map_err(|e| ServiceError::InternalServerError(e.to_string()))?;
+
+    Ok(())
+}
+
+/// The stored candidate tokens of `term_ids` for the dataset. Ids which were never recorded, or
+/// were dropped by the `BM25_TERM_TOKENS_MAX_ENTRIES` cap, are left out.
+pub async fn get_bm25_term_tokens_query(
+    dataset_id: uuid::Uuid,
+    term_ids: Vec<u32>,
+    pool: web::Data<Pool>,
+) -> Result<TermTokens, ServiceError> {
+    use crate::data::schema::bm25_term_tokens::dsl as bm25_term_tokens_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|e| ServiceError::InternalServerError(e.to_string()))?;
+
+    let rows = bm25_term_tokens_columns::bm25_term_tokens
+        .filter(bm25_term_tokens_columns::dataset_id.eq(dataset_id))
+        .filter(
+            bm25_term_tokens_columns::term_id.eq_any(
+                term_ids
+                    .into_iter()
+                    .map(|term_id| term_id as i64)
+                    .collect::<Vec<i64>>(),
+            ),
+        )
+        .select(Bm25TermToken::as_select())
+        .load::<Bm25TermToken>(&mut conn)
+        .await
+        .map_err(|e| ServiceError::InternalServerError(e.to_string()))?;
+
+    let mut term_tokens = TermTokens::default();
+    for row in rows {
+        term_tokens.insert_with_id(row.term_id as u32, row.token);
+    }
+
+    Ok(term_tokens)
+}
@@ -0,0 +1,128 @@
+use murmur3::murmur3_32;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Cursor;
+
+/// Returns the sparse vector index of a BM25 token.
+///
+/// The id is the seed 0 murmur3 hash of the token, reinterpreted as an `i32` and then made
+/// positive with `unsigned_abs`. Because of this signedness quirk a hash `h` above `i32::MAX`
+/// maps to `2^32 - h`, so two hashes can share an id and the ids never exceed `2^31`. The
+/// quirk has to be kept as is: every stored BM25 vector and every query vector built against it
+/// uses these ids, and changing them would silently stop old documents from matching.
+pub fn token_id(token: &str) -> u32 {
+    let hash = murmur3_32(&mut Cursor::new(token), 0).expect("Reading from memory never fails");
+
+    (hash as i32).unsigned_abs()
+}
+
+/// Returns the ids of `tokens` in the same order, see `token_id`.
+pub fn token_ids<S: AsRef<str>>(tokens: &[S]) -> Vec<u32> {
+    tokens
+        .iter()
+        .map(|token| token_id(token.as_ref()))
+        .collect()
+}
+
+/// The tokens seen for each term id. Ids are one way hashes which can collide, so an id keeps
+/// every distinct token hashed to it as a candidate.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TermTokens(BTreeMap<u32, BTreeSet<String>>);
+
+impl TermTokens {
+    pub fn insert(&mut self, token: &str) {
+        self.insert_with_id(token_id(token), token.to_string());
+    }
+
+    pub fn insert_with_id(&mut self, term_id: u32, token: String) {
+        self.0.entry(term_id).or_default().insert(token);
+    }
+
+    /// Candidate tokens of `term_id` in sorted order, empty if it was never seen.
+    pub fn get(&self, term_id: u32) -> Vec<&str> {
+        self.0
+            .get(&term_id)
+            .map(|tokens| tokens.iter().map(|token| token.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.values().map(|tokens| tokens.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Every `(term_id, token)` pair, ordered by id.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &str)> + '_ {
+        self.0.iter().flat_map(|(term_id, tokens)| {
+            tokens.iter().map(move |token| (*term_id, token.as_str()))
+        })
+    }
+
+    pub fn into_map(self) -> BTreeMap<u32, BTreeSet<String>> {
+        self.0
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<S> for TermTokens {
+    fn from_iter<I: IntoIterator<Item = S>>(tokens: I) -> Self {
+        let mut term_tokens = TermTokens::default();
+        for token in tokens {
+            term_tokens.insert(token.as_ref());
+        }
+        term_tokens
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_token_ids_are_stable() {
+        // Hashes which fit in an i32 are used as is
+        assert_eq!(token_id(""), 0);
+        assert_eq!(token_id("hello"), 613153351);
+        assert_eq!(token_id("search"), 553238108);
+        assert_eq!(token_id("vector"), 1955147705);
+        assert_eq!(token_id("a"), 1009084850);
+        assert_eq!(token_id("embed"), 959644756);
+
+        // Hashes above i32::MAX wrap negative and are then made positive
+        assert_eq!(token_id("trieve"), 1732310131);
+        assert_eq!(token_id("run"), 243905464);
+        assert_eq!(token_id("the"), 1132748958);
+        assert_eq!(token_id("q"), 8255000);
+
+        assert_eq!(
+            token_ids(&["hello", "trieve", "q"]),
+            vec![613153351, 1732310131, 8255000]
+        );
+        assert_eq!(token_ids(&["run".to_string()]), vec![token_id("run")]);
+    }
+
+    #[test]
+    pub fn test_term_tokens() {
+        let term_tokens = ["search", "vector", "search"]
+            .iter()
+            .collect::<TermTokens>();
+        assert_eq!(term_tokens.get(553238108), vec!["search"]);
+        assert_eq!(term_tokens.get(token_id("vector")), vec!["vector"]);
+        assert!(term_tokens.get(token_id("missing")).is_empty());
+        assert_eq!(term_tokens.len(), 2);
+
+        // Both tokens hash to the same id, neither can be ruled out from the id alone
+        assert_eq!(token_id("term15470"), token_id("term98506"));
+        let collided = ["term98506", "term15470"].iter().collect::<TermTokens>();
+        assert_eq!(
+            collided.get(token_id("term15470")),
+            vec!["term15470", "term98506"]
+        );
+        assert_eq!(collided.len(), 2);
+        assert_eq!(
+            collided.iter().collect::<Vec<(u32, &str)>>(),
+            vec![(867809535, "term15470"), (867809535, "term98506")]
+        );
+    }
+}
@@ -0,0 +1,120 @@
+/// Dot product of `a` and `b`. Extra elements of the longer vector are ignored.
+pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+pub fn l2_norm(vector: &[f32]) -> f32 {
+    dot(vector, vector).sqrt()
+}
+
+/// Scales `vector` to unit length in place. Zero vectors have no direction and are left as is.
+pub fn normalize(vector: &mut [f32]) {
+    let norm = l2_norm(vector);
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+}
+
+/// Returns `a + factor * b`. Extra elements of the longer vector are dropped.
+pub fn add_scaled(a: &[f32], b: &[f32], factor: f32) -> Vec<f32> {
+    a.iter().zip(b).map(|(x, y)| x + factor * y).collect()
+}
+
+/// Element-wise mean of `vectors`, or `None` if there are none or their dimensions differ.
+pub fn mean(vectors: &[Vec<f32>]) -> Option<Vec<f32>> {
+    let dimensions = vectors.first()?.len();
+    if vectors.iter().any(|vector| vector.len() != dimensions) {
+        return None;
+    }
+
+    let mut sum = vec![0.0; dimensions];
+    for vector in vectors {
+        sum.iter_mut()
+            .zip(vector)
+            .for_each(|(total, x)| *total += x);
+    }
+
+    let count = vectors.len() as f32;
+    Some(sum.into_iter().map(|total| total / count).collect())
+}
+
+/// Cosine similarity of `a` and `b`, or 0 if either is a zero vector.
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let norm_a = l2_norm(a);
+    let norm_b = l2_norm(b);
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot(a, b) / (norm_a * norm_b)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn random_vectors(count: usize, dimensions: usize) -> Vec<Vec<f32>> {
+        let mut rng = StdRng::seed_from_u64(42);
+        (0..count)
+            .map(|_| (0..dimensions).map(|_| rng.gen_range(-1.0..1.0)).collect())
+            .collect()
+    }
+
+    #[test]
+    pub fn test_l2_norm() {
+        assert_eq!(l2_norm(&[3.0, 4.0]), 5.0);
+        assert_eq!(l2_norm(&[0.0; 8]), 0.0);
+    }
+
+    #[test]
+    pub fn test_vector_ops() {
+        assert_eq!(dot(&[1.0, 2.0, 3.0], &[4.0, -5.0, 6.0]), 12.0);
+        assert_eq!(add_scaled(&[1.0, 2.0], &[4.0, -2.0], 0.5), vec![3.0, 1.0]);
+        assert_eq!(
+            mean(&[vec![3.0, 2.5, 1.0], vec![1.0, 2.5, 1.0]]),
+            Some(vec![2.0, 2.5, 1.0])
+        );
+        assert_eq!(mean(&[]), None);
+        assert_eq!(mean(&[vec![1.0, 2.0], vec![1.0]]), None);
+        assert_eq!(cosine(&[1.0, 0.0], &[0.0, 0.0]), 0.0);
+
+        let mut zero = vec![0.0; 4];
+        normalize(&mut zero);
+        assert_eq!(zero, vec![0.0; 4]);
+    }
+
+    #[test]
+    pub fn test_vector_op_properties() {
+        let vectors = random_vectors(50, 1536);
+
+        for pair in vectors.windows(2) {
+            let (a, b) = (&pair[0], &pair[1]);
+
+            let mut normalized = a.clone();
+            normalize(&mut normalized);
+            assert!((l2_norm(&normalized) - 1.0).abs() < 1e-5);
+            // Normalizing keeps the direction
+            assert!((cosine(a, &normalized) - 1.0).abs() < 1e-5);
+
+            assert_eq!(dot(a, b), dot(b, a));
+            assert_eq!(cosine(a, b), cosine(b, a));
+            assert!(cosine(a, b).abs() <= 1.0 + 1e-6);
+            assert!((cosine(a, a) - 1.0).abs() < 1e-5);
+
+            // Adding a scaled vector and subtracting it again is a no-op up to rounding
+            let restored = add_scaled(&add_scaled(a, b, 0.75), b, -0.75);
+            assert!(restored.iter().zip(a).all(|(x, y)| (x - y).abs() < 1e-5));
+
+            let midpoint = mean(&[a.clone(), b.clone()]).expect("Dimensions match");
+            assert_eq!(
+                midpoint,
+                add_scaled(a, b, 1.0)
+                    .iter()
+                    .map(|x| x / 2.0)
+                    .collect::<Vec<f32>>()
+            );
+        }
+    }
+}